@@ -0,0 +1,58 @@
+//! Compares the range tree with the dense backend on a list that gets every
+//! item measured one by one, the case the dense backend is meant for.
+//!
+//! cargo bench --features fenwick
+
+use rust_virtuoso::{FenwickTree, SizeTree};
+use std::time::{Duration, Instant};
+
+const COUNT: u32 = 20_000;
+
+fn size_of(index: u32) -> u32 {
+    20 + index.wrapping_mul(2_654_435_761) % 40
+}
+
+fn time(run: impl FnOnce()) -> Duration {
+    let started = Instant::now();
+    run();
+    started.elapsed()
+}
+
+fn main() {
+    let indices: Vec<u32> = (0..COUNT).step_by(3).collect();
+
+    let mut ranges: SizeTree<u32> = SizeTree::new();
+    let ranges_measure = time(|| {
+        for index in 0..COUNT {
+            ranges.write_range(index, index, size_of(index));
+        }
+    });
+    let ranges_query = time(|| {
+        ranges.offsets_of(&indices, 0);
+    });
+
+    let mut dense: FenwickTree<u32> = FenwickTree::new(COUNT);
+    let dense_measure = time(|| {
+        for index in 0..COUNT {
+            dense.write_range(index, index, size_of(index));
+        }
+    });
+    let dense_query = time(|| {
+        dense.offsets_of(&indices, 0);
+    });
+
+    assert_eq!(
+        ranges.offsets_of(&indices, 0),
+        dense.offsets_of(&indices, 0)
+    );
+
+    println!("{} items measured one by one", COUNT);
+    println!(
+        "ranges: measure {:?}, query {:?}",
+        ranges_measure, ranges_query
+    );
+    println!(
+        "dense:  measure {:?}, query {:?}",
+        dense_measure, dense_query
+    );
+}
@@ -0,0 +1,102 @@
+//! Feeds arbitrary sequences of measurements, prunes and range queries into
+//! an `OffsetList`, and checks after each of them that nothing panicked and
+//! that the offsets still add up. Run with `cargo fuzz run operations`.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rust_virtuoso::OffsetList;
+
+/// The items the checks walk over; the operations stay around them.
+const ITEMS: u32 = 300;
+
+#[derive(Arbitrary, Debug)]
+enum Operation {
+    Insert { start: u8, length: u8, size: u8 },
+    InsertSpots { spots: Vec<u8>, size: u8 },
+    SetDefaultSize { size: u8 },
+    SetGap { gap: u8 },
+    Prune { distance: u8, first: u8, length: u8 },
+    Range { start_offset: u16, length: u16, min_index: u8, span: u8 },
+}
+
+fn apply(list: &mut OffsetList, operation: &Operation) {
+    match *operation {
+        Operation::Insert {
+            start,
+            length,
+            size,
+        } => {
+            let start = u32::from(start);
+            list.insert(start, start + u32::from(length), u32::from(size));
+        }
+        Operation::InsertSpots { ref spots, size } => {
+            let mut spots: Vec<u32> = spots.iter().map(|spot| u32::from(*spot)).collect();
+            spots.sort_unstable();
+            spots.dedup();
+            list.insert_spots(spots, u32::from(size));
+        }
+        Operation::SetDefaultSize { size } => list.set_default_size(u32::from(size)),
+        Operation::SetGap { gap } => list.set_gap(u32::from(gap)),
+        Operation::Prune {
+            distance,
+            first,
+            length,
+        } => {
+            list.set_measurement_pruning(u32::from(distance));
+            let first = u32::from(first);
+            list.prune_measurements(first, first + u32::from(length));
+        }
+        Operation::Range {
+            start_offset,
+            length,
+            min_index,
+            span,
+        } => {
+            let start_offset = u32::from(start_offset);
+            let min_index = u32::from(min_index);
+            let end_offset = start_offset + u32::from(length);
+            let max_index = min_index + u32::from(span);
+
+            // an error is fine, as long as it isn't a panic
+            if let Ok(items) = list.try_range(start_offset, end_offset, min_index, max_index) {
+                for pair in items.windows(2) {
+                    assert_eq!(pair[0].index + 1, pair[1].index);
+                    assert!(pair[0].offset <= pair[1].offset);
+                }
+                for item in items.iter() {
+                    assert!(item.index >= min_index && item.index <= max_index);
+                    let (start, end) = (item.offset, item.offset + item.size);
+                    assert_eq!(start, list.offset_of(item.index));
+                    assert!(end + item.gap >= start);
+                }
+            }
+        }
+    }
+}
+
+/// Every offset is the one before it, plus that item and the gap.
+fn check(list: &OffsetList) {
+    if list.is_empty() {
+        return;
+    }
+
+    let indices: Vec<u32> = (0..ITEMS).collect();
+    let offsets = list.offsets_of(&indices);
+    for index in 1..ITEMS {
+        let previous = list.item_at(index - 1);
+        assert_eq!(
+            offsets[index as usize],
+            offsets[index as usize - 1] + previous.size + list.gap()
+        );
+    }
+    assert_eq!(list.total_size(ITEMS), offsets[ITEMS as usize - 1] + list.item_at(ITEMS - 1).size);
+}
+
+fuzz_target!(|operations: Vec<Operation>| {
+    let mut list = OffsetList::new();
+    for operation in operations.iter() {
+        apply(&mut list, operation);
+        check(&list);
+    }
+});
@@ -0,0 +1,50 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The direction a list scrolls in. The sizing engine only ever works with
+/// main axis offsets; `Axis` picks the matching values out of a DOM element's
+/// two-dimensional scroll position and client size.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    Vertical,
+    Horizontal,
+}
+
+/// Which way offsets grow along the main axis. `Backward` is right to left
+/// for horizontal lists, for RTL locales.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+impl Axis {
+    pub fn main(self, x: f64, y: f64) -> f64 {
+        match self {
+            Axis::Vertical => y,
+            Axis::Horizontal => x,
+        }
+    }
+
+    pub fn cross(self, x: f64, y: f64) -> f64 {
+        match self {
+            Axis::Vertical => x,
+            Axis::Horizontal => y,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Axis;
+
+    #[test]
+    fn test_main_and_cross() {
+        assert_eq!(Axis::Vertical.main(1.0, 2.0), 2.0);
+        assert_eq!(Axis::Vertical.cross(1.0, 2.0), 1.0);
+        assert_eq!(Axis::Horizontal.main(1.0, 2.0), 1.0);
+        assert_eq!(Axis::Horizontal.cross(1.0, 2.0), 2.0);
+    }
+}
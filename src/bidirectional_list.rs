@@ -0,0 +1,208 @@
+use crate::log::trace;
+use crate::offset_list::OffsetList;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BidirectionalRange {
+    pub first_index: i32,
+    pub last_index: i32,
+}
+
+/// A list that grows in both directions, for timelines that load past and
+/// future pages. Indices are signed; the loaded ones are
+/// `first_index..end_index`, and prepending moves `first_index` down without
+/// touching the sizes recorded so far.
+///
+/// The sizes live in two offset lists growing away from index 0: `after` holds
+/// the indices from 0 up, and `before` the negative ones mirrored, so that
+/// index -1 is at 0 in it. Offsets are in scroll space, where the first loaded
+/// item starts at zero.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct BidirectionalList {
+    after: OffsetList,
+    before: OffsetList,
+    first_index: i32,
+    end_index: i32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BidirectionalList {
+    pub fn new(first_index: i32, end_index: i32) -> BidirectionalList {
+        BidirectionalList {
+            after: OffsetList::new(),
+            before: OffsetList::new(),
+            first_index,
+            end_index: end_index.max(first_index),
+        }
+    }
+
+    pub fn first_index(&self) -> i32 {
+        self.first_index
+    }
+
+    pub fn end_index(&self) -> i32 {
+        self.end_index
+    }
+
+    /// Loads `count` more items before the first one. Answers by how much the
+    /// scroll position has to grow to keep the same items in view.
+    pub fn prepend(&mut self, count: u32) -> f64 {
+        let first_index = self.first_index.saturating_sub_unsigned(count);
+        let added = self.position(self.first_index) - self.position(first_index);
+        trace!(
+            Debug,
+            "prepend: {} items, the scroll position grows by {}",
+            count,
+            added
+        );
+
+        self.first_index = first_index;
+        added
+    }
+
+    pub fn append(&mut self, count: u32) {
+        self.end_index = self.end_index.saturating_add_unsigned(count);
+    }
+
+    /// Records the size of an item. The first measurement is also the
+    /// estimate for both directions.
+    pub fn measure(&mut self, index: i32, size: u32) {
+        if self.after.is_empty() && self.before.is_empty() {
+            self.after.insert(0, 0, size);
+            self.before.insert(0, 0, size);
+        }
+
+        if index < 0 {
+            let mirrored = index.unsigned_abs() - 1;
+            self.before.insert(mirrored, mirrored, size);
+        } else {
+            let index = index as u32;
+            self.after.insert(index, index, size);
+        }
+    }
+
+    pub fn offset_of(&self, index: i32) -> f64 {
+        self.position(index) - self.position(self.first_index)
+    }
+
+    pub fn total_size(&self) -> f64 {
+        self.position(self.end_index) - self.position(self.first_index)
+    }
+
+    /// The loaded items intersecting `scroll_offset..scroll_offset + viewport_size`.
+    pub fn range(&self, scroll_offset: f64, viewport_size: f64) -> Option<BidirectionalRange> {
+        if self.first_index == self.end_index {
+            return None;
+        }
+
+        let start = self.position(self.first_index) + scroll_offset.max(0.0);
+        let end = start + viewport_size.max(0.0);
+        let last = self.end_index - 1;
+
+        let first_index = self.index_at(start).clamp(self.first_index, last);
+        let last_index = self
+            .index_at((end - 1.0).max(start))
+            .clamp(first_index, last);
+
+        Some(BidirectionalRange {
+            first_index,
+            last_index,
+        })
+    }
+}
+
+impl BidirectionalList {
+    /// The offset of the index from the start of index 0.
+    fn position(&self, index: i32) -> f64 {
+        if index < 0 {
+            -f64::from(self.before.offsets_of(&[index.unsigned_abs()])[0])
+        } else {
+            f64::from(self.after.offsets_of(&[index as u32])[0])
+        }
+    }
+
+    /// The index of the item covering the position.
+    fn index_at(&self, position: f64) -> i32 {
+        if position >= 0.0 {
+            let index = self.after.indices_at_offsets(&[position as u32])[0];
+            return index.min(i32::MAX as u32) as i32;
+        }
+
+        // a mirrored item covers the positions right after its offset
+        let distance = (-position).ceil() as u32 - 1;
+        let mirrored = self.before.indices_at_offsets(&[distance])[0];
+        -1 - mirrored.min(i32::MAX as u32) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BidirectionalList;
+    use super::BidirectionalRange;
+
+    #[test]
+    fn test_offsets() {
+        let mut list = BidirectionalList::new(-10, 10);
+        list.measure(0, 30);
+        list.measure(-1, 50);
+
+        assert_eq!(list.offset_of(-10), 0.0);
+        assert_eq!(list.offset_of(-1), 270.0);
+        assert_eq!(list.offset_of(0), 320.0);
+        assert_eq!(list.total_size(), 620.0);
+    }
+
+    #[test]
+    fn test_prepend_keeps_the_recorded_sizes() {
+        let mut list = BidirectionalList::new(0, 10);
+        list.measure(0, 30);
+        list.measure(3, 60);
+
+        assert_eq!(list.prepend(5), 150.0);
+        assert_eq!(list.first_index(), -5);
+        assert_eq!(list.offset_of(4), 150.0 + 150.0);
+
+        list.append(5);
+        assert_eq!(list.end_index(), 15);
+        assert_eq!(list.total_size(), 150.0 + 480.0);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut list = BidirectionalList::new(-100, 100);
+        list.measure(0, 30);
+
+        // index 0 starts at 3000
+        assert_eq!(
+            list.range(2950.0, 100.0),
+            Some(BidirectionalRange {
+                first_index: -2,
+                last_index: 1,
+            })
+        );
+        assert_eq!(
+            list.range(2970.0, 30.0),
+            Some(BidirectionalRange {
+                first_index: -1,
+                last_index: -1,
+            })
+        );
+        assert_eq!(
+            list.range(0.0, 10000.0),
+            Some(BidirectionalRange {
+                first_index: -100,
+                last_index: 99,
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let list = BidirectionalList::new(5, 5);
+
+        assert_eq!(list.range(0.0, 100.0), None);
+        assert_eq!(list.total_size(), 0.0);
+    }
+}
@@ -0,0 +1,145 @@
+//! What the native bindings share: the `Item` they hand out as a plain
+//! record, with nothing to free, and the offset list methods they wrap. Each
+//! binding passes in the attributes of its framework and the constructor,
+//! and gives its wrapper `list()` and `list_mut()`, for the list to query and
+//! to write.
+
+macro_rules! offset_list_bindings {
+    (
+        $(#[$item_meta:meta])*
+        item $item:ident;
+
+        $(#[$impl_meta:meta])*
+        impl $wrapper:ident { $($constructor:tt)* }
+
+        methods [$(#[$method_meta:meta])*], writes (&mut self);
+    ) => {
+        offset_list_bindings!(@expand
+            [$(#[$item_meta])*] $item
+            [$(#[$impl_meta])*] $wrapper { $($constructor)* }
+            [$(#[$method_meta])*] [mut]
+        );
+    };
+
+    (
+        $(#[$item_meta:meta])*
+        item $item:ident;
+
+        $(#[$impl_meta:meta])*
+        impl $wrapper:ident { $($constructor:tt)* }
+
+        methods [$(#[$method_meta:meta])*], writes (&self);
+    ) => {
+        offset_list_bindings!(@expand
+            [$(#[$item_meta])*] $item
+            [$(#[$impl_meta])*] $wrapper { $($constructor)* }
+            [$(#[$method_meta])*] []
+        );
+    };
+
+    (@expand
+        [$(#[$item_meta:meta])*] $item:ident
+        [$(#[$impl_meta:meta])*] $wrapper:ident { $($constructor:tt)* }
+        [$(#[$method_meta:meta])*] [$($mut:tt)?]
+    ) => {
+        $(#[$item_meta])*
+        pub struct $item {
+            pub index: u32,
+            pub size: u32,
+            pub offset: u32,
+            pub tag: u32,
+        }
+
+        impl From<&$crate::Item> for $item {
+            fn from(item: &$crate::Item) -> $item {
+                $item {
+                    index: item.index,
+                    size: item.size,
+                    offset: item.offset,
+                    tag: item.tag,
+                }
+            }
+        }
+
+        $(#[$impl_meta])*
+        impl $wrapper {
+            $($constructor)*
+
+            $(#[$method_meta])*
+            pub fn insert(&$($mut)? self, start: u32, end: u32, size: u32) {
+                self.list_mut().insert(start, end, size);
+            }
+
+            $(#[$method_meta])*
+            pub fn insert_spots(&$($mut)? self, spots: Vec<u32>, size: u32) {
+                self.list_mut().insert_spots(spots, size);
+            }
+
+            $(#[$method_meta])*
+            pub fn insert_sized_spots(&$($mut)? self, spots: Vec<u32>, sizes: Vec<u32>) {
+                self.list_mut().insert_sized_spots(spots, sizes);
+            }
+
+            $(#[$method_meta])*
+            pub fn set_default_size(&$($mut)? self, size: u32) {
+                self.list_mut().set_default_size(size);
+            }
+
+            $(#[$method_meta])*
+            pub fn set_gap(&$($mut)? self, gap: u32) {
+                self.list_mut().set_gap(gap);
+            }
+
+            $(#[$method_meta])*
+            pub fn set_header_height(&$($mut)? self, height: u32) {
+                self.list_mut().set_header_height(height);
+            }
+
+            $(#[$method_meta])*
+            pub fn set_footer_height(&$($mut)? self, height: u32) {
+                self.list_mut().set_footer_height(height);
+            }
+
+            $(#[$method_meta])*
+            pub fn offsets_of(&self, indices: Vec<u32>) -> Vec<u32> {
+                self.list().offsets_of(&indices)
+            }
+
+            $(#[$method_meta])*
+            pub fn indices_at_offsets(&self, offsets: Vec<u32>) -> Vec<u32> {
+                self.list().indices_at_offsets(&offsets)
+            }
+
+            $(#[$method_meta])*
+            pub fn total_size(&self, count: u32) -> u32 {
+                self.list().total_size(count)
+            }
+
+            $(#[$method_meta])*
+            pub fn index_range(&self, start_index: u32, end_index: u32) -> Vec<$item> {
+                self.list()
+                    .index_range(start_index, end_index)
+                    .iter()
+                    .map($item::from)
+                    .collect()
+            }
+
+            $(#[$method_meta])*
+            pub fn range(
+                &self,
+                start_offset: u32,
+                end_offset: u32,
+                min_index: u32,
+                max_index: u32,
+            ) -> Vec<$item> {
+                self.list()
+                    .range(start_offset, end_offset, min_index, max_index)
+                    .iter()
+                    .map($item::from)
+                    .collect()
+            }
+        }
+    };
+}
+
+pub(crate) use offset_list_bindings;
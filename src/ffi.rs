@@ -0,0 +1,77 @@
+//! UniFFI bindings, for Swift and Kotlin apps that keep their scroll math
+//! identical to the web client's. They cover the offset list.
+
+use crate::bindings::offset_list_bindings;
+use crate::offset_list::OffsetList;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// UniFFI hands objects out as `Arc`s, usable from any thread, so the list
+/// sits behind a mutex. It is `Send` with the `sync` feature, which `uniffi`
+/// turns on.
+#[derive(uniffi::Object)]
+pub struct FfiOffsetList {
+    list: Mutex<OffsetList>,
+}
+
+offset_list_bindings! {
+    #[derive(uniffi::Record)]
+    item FfiItem;
+
+    #[uniffi::export]
+    impl FfiOffsetList {
+        #[uniffi::constructor]
+        pub fn new() -> Arc<FfiOffsetList> {
+            Arc::new(FfiOffsetList {
+                list: Mutex::new(OffsetList::new()),
+            })
+        }
+    }
+
+    methods [], writes (&self);
+}
+
+impl FfiOffsetList {
+    /// A panic while the lock was held leaves the list as consistent as any
+    /// panic does, so the poisoning is ignored.
+    fn list(&self) -> MutexGuard<'_, OffsetList> {
+        self.list.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn list_mut(&self) -> MutexGuard<'_, OffsetList> {
+        self.list()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FfiOffsetList;
+    use std::thread;
+
+    #[test]
+    fn test_moves_between_threads() {
+        let list = FfiOffsetList::new();
+        list.insert(0, 0, 10);
+
+        let shared = list.clone();
+        thread::spawn(move || shared.insert(3, 3, 30))
+            .join()
+            .unwrap();
+
+        assert_eq!(list.offsets_of(vec![1, 4, 5]), [10, 60, 70]);
+    }
+
+    #[test]
+    fn test_wraps_the_list() {
+        let list = FfiOffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 3, 20);
+
+        let items = list.range(0, 45, 0, 9);
+        let sizes: Vec<(u32, u32, u32)> = items
+            .iter()
+            .map(|item| (item.index, item.size, item.offset))
+            .collect();
+        assert_eq!(sizes, [(0, 10, 0), (1, 10, 10), (2, 20, 20), (3, 20, 40)]);
+        assert_eq!(list.offsets_of(vec![1, 4]), [10, 60]);
+    }
+}
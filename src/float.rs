@@ -0,0 +1,22 @@
+//! The rounding of `f64` without `std`, from `libm`. With `std`, the inherent
+//! methods shadow these, so the call sites read the same either way.
+
+pub trait Float {
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+}
+
+impl Float for f64 {
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+}
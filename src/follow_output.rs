@@ -0,0 +1,226 @@
+use crate::axis::{Axis, Direction};
+use crate::utils;
+use crate::viewport_state::ViewportState;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FollowScroll {
+    pub scroll_top: f64,
+    pub smooth: bool,
+    pub version: u32,
+    pub config_hash: u32,
+}
+
+/// Keeps a list pinned to its bottom edge (chat and log views). Feed it every
+/// scroll event through `update` and call `content_changed` after items are
+/// appended or measured; it answers with the scrollTop that keeps the last
+/// item in view, or nothing when the user has scrolled away from the bottom.
+///
+/// A `Backward` list (a reversed chat, laid out with
+/// `flex-direction: column-reverse`) has its newest item at the scroll start,
+/// so following it means staying at scroll offset zero.
+///
+/// Each answer carries the `version` of the scroll state it was computed from
+/// (bumped by `update`), so a target computed before a newer scroll event can
+/// be recognized and dropped.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FollowOutput {
+    state: ViewportState,
+    direction: Direction,
+    smooth_threshold: f64,
+    scroll_offset: f64,
+    viewport_size: f64,
+    version: u32,
+    config_hash: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FollowOutput {
+    pub fn new(tolerance: f64, smooth_threshold: f64) -> FollowOutput {
+        FollowOutput {
+            state: ViewportState::new(tolerance, 0.0),
+            direction: Direction::Forward,
+            smooth_threshold,
+            scroll_offset: 0.0,
+            viewport_size: 0.0,
+            version: 0,
+            config_hash: utils::config_hash(&[tolerance, smooth_threshold]),
+        }
+    }
+
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.direction = direction;
+    }
+
+    pub fn update(&mut self, scroll_offset: f64, viewport_size: f64, total_size: f64) {
+        self.version = self.version.wrapping_add(1);
+        self.scroll_offset = scroll_offset;
+        self.viewport_size = viewport_size;
+        self.state.update(scroll_offset, viewport_size, total_size);
+    }
+
+    /// `update` with the raw scroll position and client size of the scroller.
+    pub fn update_element(
+        &mut self,
+        axis: Axis,
+        scroll_left: f64,
+        scroll_top: f64,
+        client_width: f64,
+        client_height: f64,
+        total_size: f64,
+    ) {
+        // a reversed scroller reports negative offsets away from its start
+        self.update(
+            axis.main(scroll_left, scroll_top).abs(),
+            axis.main(client_width, client_height),
+            total_size,
+        );
+    }
+
+    /// Whether the followed edge is in view.
+    pub fn at_bottom(&self) -> bool {
+        match self.direction {
+            Direction::Forward => self.state.at_bottom(),
+            Direction::Backward => self.state.at_top(),
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn config_hash(&self) -> u32 {
+        self.config_hash
+    }
+
+    pub fn content_changed(&mut self, total_size: f64) -> Option<FollowScroll> {
+        if !self.at_bottom() {
+            return None;
+        }
+
+        let scroll_top = match self.direction {
+            Direction::Forward => (total_size - self.viewport_size).max(0.0),
+            Direction::Backward => 0.0,
+        };
+        let delta = (scroll_top - self.scroll_offset).abs();
+        self.scroll_offset = scroll_top;
+
+        Some(FollowScroll {
+            scroll_top,
+            smooth: delta <= self.smooth_threshold,
+            version: self.version,
+            config_hash: self.config_hash,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FollowOutput;
+    use super::FollowScroll;
+    use crate::axis::{Axis, Direction};
+
+    #[test]
+    fn test_follows_when_at_bottom() {
+        let mut follow = FollowOutput::new(5.0, 50.0);
+        follow.update(700.0, 300.0, 1000.0);
+
+        assert!(follow.at_bottom());
+        assert_eq!(
+            follow.content_changed(1030.0),
+            Some(FollowScroll {
+                scroll_top: 730.0,
+                smooth: true,
+                version: 1,
+                config_hash: follow.config_hash(),
+            })
+        );
+        assert_eq!(
+            follow.content_changed(1500.0),
+            Some(FollowScroll {
+                scroll_top: 1200.0,
+                smooth: false,
+                version: 1,
+                config_hash: follow.config_hash(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let mut follow = FollowOutput::new(5.0, 50.0);
+
+        follow.update(696.0, 300.0, 1000.0);
+        assert!(follow.at_bottom());
+
+        follow.update(690.0, 300.0, 1000.0);
+        assert!(!follow.at_bottom());
+    }
+
+    #[test]
+    fn test_does_not_follow_when_scrolled_away() {
+        let mut follow = FollowOutput::new(0.0, 50.0);
+        follow.update(100.0, 300.0, 1000.0);
+
+        assert_eq!(follow.content_changed(1100.0), None);
+    }
+
+    #[test]
+    fn test_short_content() {
+        let mut follow = FollowOutput::new(0.0, 50.0);
+        follow.update(0.0, 300.0, 100.0);
+
+        assert_eq!(
+            follow.content_changed(200.0),
+            Some(FollowScroll {
+                scroll_top: 0.0,
+                smooth: true,
+                version: 1,
+                config_hash: follow.config_hash(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_results_are_stamped() {
+        let mut follow = FollowOutput::new(0.0, 50.0);
+        follow.update(700.0, 300.0, 1000.0);
+
+        let target = follow.content_changed(1100.0).expect("should follow");
+        assert_eq!(target.version, follow.version());
+
+        follow.update(790.0, 300.0, 1100.0);
+        assert_ne!(target.version, follow.version());
+        assert_ne!(
+            target.config_hash,
+            FollowOutput::new(1.0, 50.0).config_hash()
+        );
+    }
+
+    #[test]
+    fn test_horizontal_element() {
+        let mut follow = FollowOutput::new(0.0, 50.0);
+        follow.update_element(Axis::Horizontal, 700.0, 0.0, 300.0, 40.0, 1000.0);
+
+        let target = follow.content_changed(1020.0).expect("should follow");
+        assert_eq!(target.scroll_top, 720.0);
+    }
+
+    #[test]
+    fn test_follows_reversed_list() {
+        let mut follow = FollowOutput::new(5.0, 50.0);
+        follow.set_direction(Direction::Backward);
+        follow.update_element(Axis::Vertical, 0.0, -3.0, 300.0, 300.0, 1000.0);
+
+        assert!(follow.at_bottom());
+        let scroll = follow.content_changed(1030.0).expect("follows");
+        assert_eq!(scroll.scroll_top, 0.0);
+        assert!(scroll.smooth);
+
+        follow.update_element(Axis::Vertical, 0.0, -200.0, 300.0, 300.0, 1030.0);
+        assert!(!follow.at_bottom());
+        assert_eq!(follow.content_changed(1060.0), None);
+    }
+}
@@ -0,0 +1,441 @@
+use crate::offset_list::OffsetList;
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridRange {
+    pub start_index: u32,
+    pub end_index: u32,
+    pub start_row: u32,
+    pub end_row: u32,
+    pub columns: u32,
+}
+
+/// Virtualizes a grid of cells that wrap into as many columns as fit the
+/// viewport width. Ranges are inclusive.
+///
+/// Cells are `item_width` by `item_height` until measured. Once a cell height
+/// is measured, its row is as tall as the tallest measured cell in it; the row
+/// sizes live in an `OffsetList` keyed by row, which is rebuilt from the
+/// measured cells when the column count changes.
+///
+/// A cell can span several columns. Cells are then laid out in order, and a
+/// cell that doesn't fit the rest of a row starts the next one.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GridEngine {
+    rows: OffsetList,
+    item_heights: BTreeMap<u32, u32>,
+    column_spans: BTreeMap<u32, u32>,
+    row_starts: Vec<u32>,
+    bucket_columns: u32,
+    total_count: u32,
+    item_width: u32,
+    item_height: u32,
+    row_gap: u32,
+    column_gap: u32,
+    viewport_width: f64,
+    viewport_height: f64,
+    scroll_top: f64,
+    overscan: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GridEngine {
+    pub fn new(total_count: u32, item_width: u32, item_height: u32) -> GridEngine {
+        GridEngine {
+            rows: OffsetList::new(),
+            item_heights: BTreeMap::new(),
+            column_spans: BTreeMap::new(),
+            row_starts: Vec::new(),
+            bucket_columns: 1,
+            total_count,
+            item_width,
+            item_height,
+            row_gap: 0,
+            column_gap: 0,
+            viewport_width: 0.0,
+            viewport_height: 0.0,
+            scroll_top: 0.0,
+            overscan: 0,
+        }
+    }
+
+    pub fn set_total_count(&mut self, total_count: u32) {
+        self.total_count = total_count;
+
+        if !self.column_spans.is_empty() {
+            self.rebucket();
+        }
+    }
+
+    /// Makes the cell at `index` span `span` columns (at most all of them).
+    pub fn set_column_span(&mut self, index: u32, span: u32) {
+        if span > 1 {
+            self.column_spans.insert(index, span);
+        } else {
+            self.column_spans.remove(&index);
+        }
+        self.rebucket();
+    }
+
+    pub fn set_item_size(&mut self, item_width: u32, item_height: u32) {
+        self.item_width = item_width;
+        self.item_height = item_height;
+        self.rebucket();
+    }
+
+    pub fn set_gaps(&mut self, row_gap: u32, column_gap: u32) {
+        self.row_gap = row_gap;
+        self.column_gap = column_gap;
+        self.rebucket();
+    }
+
+    pub fn set_viewport(&mut self, viewport_width: f64, viewport_height: f64) {
+        self.viewport_width = viewport_width.max(0.0);
+        self.viewport_height = viewport_height.max(0.0);
+
+        if self.columns() != self.bucket_columns {
+            self.rebucket();
+        }
+    }
+
+    pub fn measure_item(&mut self, index: u32, height: u32) {
+        self.item_heights.insert(index, height);
+
+        if self.rows.is_empty() {
+            self.rebucket();
+        } else {
+            let row = self.row_of(index);
+            let row_size = self.row_height(row).saturating_add(self.row_gap);
+            self.rows.insert(row, row, row_size);
+        }
+    }
+
+    pub fn set_scroll_top(&mut self, scroll_top: f64) {
+        self.scroll_top = scroll_top.max(0.0);
+    }
+
+    pub fn set_overscan(&mut self, overscan: u32) {
+        self.overscan = overscan;
+    }
+
+    /// How many cells fit next to each other; at least one.
+    pub fn columns(&self) -> u32 {
+        let stride = f64::from(self.item_width) + f64::from(self.column_gap);
+        if stride <= 0.0 {
+            return 1;
+        }
+
+        (((self.viewport_width + f64::from(self.column_gap)) / stride) as u32).max(1)
+    }
+
+    pub fn row_count(&self) -> u32 {
+        if self.column_spans.is_empty() {
+            self.total_count.div_ceil(self.bucket_columns)
+        } else {
+            self.row_starts.len() as u32
+        }
+    }
+
+    pub fn row_offset(&self, row: u32) -> u32 {
+        if self.rows.is_empty() {
+            row.saturating_mul(self.item_height.saturating_add(self.row_gap))
+        } else {
+            self.rows.offsets_of(&[row])[0]
+        }
+    }
+
+    pub fn total_height(&self) -> u32 {
+        match self.row_count() {
+            0 => 0,
+            rows => self.row_offset(rows).saturating_sub(self.row_gap),
+        }
+    }
+
+    pub fn visible_range(&self) -> Option<GridRange> {
+        let rows = self.row_count();
+        if rows == 0 {
+            return None;
+        }
+
+        let overscan = f64::from(self.overscan);
+        let start_offset = (self.scroll_top - overscan).max(0.0) as u32;
+        let end_offset = (self.scroll_top + self.viewport_height + overscan) as u32;
+
+        let start_row = self.row_at(start_offset).min(rows - 1);
+        let end_row = self
+            .row_at(end_offset.saturating_sub(1))
+            .clamp(start_row, rows - 1);
+
+        Some(GridRange {
+            start_index: self.row_start(start_row),
+            end_index: self.row_start(end_row + 1) - 1,
+            start_row,
+            end_row,
+            columns: self.bucket_columns,
+        })
+    }
+
+    /// The offsets of the rows `start_row..=end_row`.
+    pub fn row_offsets(&self, start_row: u32, end_row: u32) -> Vec<u32> {
+        let rows: Vec<u32> = (start_row..=end_row).collect();
+
+        if self.rows.is_empty() {
+            rows.iter().map(|row| self.row_offset(*row)).collect()
+        } else {
+            self.rows.offsets_of(&rows)
+        }
+    }
+}
+
+impl GridEngine {
+    fn row_at(&self, offset: u32) -> u32 {
+        if !self.rows.is_empty() {
+            return self.rows.indices_at_offsets(&[offset])[0];
+        }
+
+        match self.item_height.saturating_add(self.row_gap) {
+            0 => 0,
+            stride => offset / stride,
+        }
+    }
+
+    fn row_of(&self, index: u32) -> u32 {
+        if self.column_spans.is_empty() {
+            index / self.bucket_columns
+        } else {
+            self.row_starts
+                .partition_point(|start| *start <= index)
+                .saturating_sub(1) as u32
+        }
+    }
+
+    /// The first cell of the row, or the total count past the last row.
+    fn row_start(&self, row: u32) -> u32 {
+        if self.column_spans.is_empty() {
+            row.saturating_mul(self.bucket_columns)
+                .min(self.total_count)
+        } else {
+            match self.row_starts.get(row as usize) {
+                None => self.total_count,
+                Some(start) => *start,
+            }
+        }
+    }
+
+    fn layout_spans(&mut self) {
+        self.row_starts.clear();
+
+        if self.column_spans.is_empty() {
+            return;
+        }
+
+        let columns = self.bucket_columns;
+        let mut column = columns;
+
+        for index in 0..self.total_count {
+            let span = match self.column_spans.get(&index) {
+                None => 1,
+                Some(span) => (*span).min(columns),
+            };
+
+            if column + span > columns {
+                self.row_starts.push(index);
+                column = 0;
+            }
+            column += span;
+        }
+    }
+
+    /// The tallest measured cell of the row, or the default cell height.
+    fn row_height(&self, row: u32) -> u32 {
+        let start = self.row_start(row);
+        let end = self.row_start(row + 1);
+
+        self.item_heights
+            .range(start..end)
+            .map(|(_, height)| *height)
+            .max()
+            .unwrap_or(self.item_height)
+    }
+
+    fn rebucket(&mut self) {
+        self.bucket_columns = self.columns();
+        self.layout_spans();
+        self.rows = OffsetList::new();
+
+        if self.item_heights.is_empty() {
+            return;
+        }
+
+        self.rows
+            .insert(0, 0, self.item_height.saturating_add(self.row_gap));
+
+        let mut measured_rows: Vec<u32> = self
+            .item_heights
+            .keys()
+            .map(|index| self.row_of(*index))
+            .collect();
+        measured_rows.dedup();
+
+        for row in measured_rows {
+            let row_size = self.row_height(row).saturating_add(self.row_gap);
+            self.rows.insert(row, row, row_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GridEngine;
+    use super::GridRange;
+
+    #[test]
+    fn test_columns() {
+        let mut grid = GridEngine::new(100, 100, 50);
+        grid.set_viewport(350.0, 200.0);
+        assert_eq!(grid.columns(), 3);
+
+        grid.set_gaps(10, 30);
+        assert_eq!(grid.columns(), 2);
+
+        grid.set_viewport(50.0, 200.0);
+        assert_eq!(grid.columns(), 1);
+    }
+
+    #[test]
+    fn test_visible_range() {
+        let mut grid = GridEngine::new(100, 100, 50);
+        grid.set_viewport(300.0, 200.0);
+        grid.set_gaps(10, 0);
+        grid.set_scroll_top(130.0);
+
+        assert_eq!(
+            grid.visible_range(),
+            Some(GridRange {
+                start_index: 6,
+                end_index: 17,
+                start_row: 2,
+                end_row: 5,
+                columns: 3,
+            })
+        );
+        assert_eq!(grid.row_offsets(2, 5), [120, 180, 240, 300]);
+    }
+
+    #[test]
+    fn test_huge_rows_saturate() {
+        let mut grid = GridEngine::new(u32::MAX, 100, u32::MAX / 2);
+        grid.set_viewport(100.0, 200.0);
+        grid.set_gaps(10, 0);
+
+        assert_eq!(grid.row_offset(3), u32::MAX);
+        assert_eq!(grid.total_height(), u32::MAX - 10);
+    }
+
+    #[test]
+    fn test_last_row_is_partial() {
+        let mut grid = GridEngine::new(10, 100, 50);
+        grid.set_viewport(300.0, 200.0);
+        grid.set_scroll_top(100.0);
+
+        let range = grid.visible_range().expect("there are items");
+        assert_eq!(range.start_row, 2);
+        assert_eq!(range.end_row, 3);
+        assert_eq!(range.end_index, 9);
+        assert_eq!(grid.total_height(), 200);
+    }
+
+    #[test]
+    fn test_empty_grid() {
+        let mut grid = GridEngine::new(0, 100, 50);
+        grid.set_viewport(300.0, 200.0);
+
+        assert_eq!(grid.visible_range(), None);
+        assert_eq!(grid.total_height(), 0);
+    }
+
+    #[test]
+    fn test_measured_rows() {
+        let mut grid = GridEngine::new(100, 100, 50);
+        grid.set_viewport(300.0, 200.0);
+        grid.set_gaps(10, 0);
+
+        grid.measure_item(1, 80);
+        grid.measure_item(2, 70);
+        grid.measure_item(4, 40);
+
+        // row 0 is 80 high, row 1 keeps its 40 high measurement
+        assert_eq!(grid.row_offsets(0, 3), [0, 90, 140, 200]);
+        // 90 + 50 + 32 rows of 60, without the gap after the last row
+        assert_eq!(grid.total_height(), 2050);
+    }
+
+    #[test]
+    fn test_rows_are_rebucketed_on_column_change() {
+        let mut grid = GridEngine::new(100, 100, 50);
+        grid.set_viewport(300.0, 200.0);
+        grid.measure_item(3, 90);
+
+        // item 3 starts row 1
+        assert_eq!(grid.row_offsets(1, 2), [50, 140]);
+
+        // with two columns, item 3 ends row 1
+        grid.set_viewport(200.0, 200.0);
+        assert_eq!(grid.columns(), 2);
+        assert_eq!(grid.row_offsets(1, 2), [50, 140]);
+
+        // with four columns, item 3 ends row 0
+        grid.set_viewport(400.0, 200.0);
+        assert_eq!(grid.row_offsets(1, 2), [90, 140]);
+    }
+
+    #[test]
+    fn test_visible_range_with_measured_rows() {
+        let mut grid = GridEngine::new(100, 100, 50);
+        grid.set_viewport(300.0, 100.0);
+        grid.measure_item(0, 200);
+        grid.set_scroll_top(150.0);
+
+        let range = grid.visible_range().expect("there are items");
+        assert_eq!(range.start_row, 0);
+        assert_eq!(range.end_row, 1);
+    }
+
+    #[test]
+    fn test_column_spans() {
+        let mut grid = GridEngine::new(10, 100, 50);
+        grid.set_viewport(300.0, 100.0);
+        // rows: [0, 1], [2, 3], [4, 5, 6], [7, 8, 9]
+        grid.set_column_span(1, 2);
+        grid.set_column_span(2, 2);
+
+        assert_eq!(grid.row_count(), 4);
+
+        grid.set_scroll_top(50.0);
+        assert_eq!(
+            grid.visible_range(),
+            Some(GridRange {
+                start_index: 2,
+                end_index: 6,
+                start_row: 1,
+                end_row: 2,
+                columns: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_column_span_clamped_to_columns() {
+        let mut grid = GridEngine::new(3, 100, 50);
+        grid.set_viewport(200.0, 100.0);
+        grid.set_column_span(1, 5);
+
+        // rows: [0], [1], [2]
+        assert_eq!(grid.row_count(), 3);
+        grid.measure_item(1, 80);
+        assert_eq!(grid.row_offsets(0, 2), [0, 50, 130]);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::offset_list::OffsetList;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupLocation {
+    pub group_index: u32,
+    pub item_index: u32,
+    pub is_header: bool,
+}
+
+/// The group header to render stuck to the top of the viewport. `offset` is
+/// its position relative to the viewport top: zero while it sits in place, and
+/// negative while the next header is pushing it out.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StickyHeader {
+    pub group_index: u32,
+    pub index: u32,
+    pub offset: f64,
+}
+
+/// An offset list over a grouped index space: each group contributes a header
+/// at its flat index followed by its items. `transpose` maps a flat index back
+/// to the group and the position of the item within that group. The groups
+/// are the ones `OffsetList::set_groups` lays out, with the flat count as the
+/// total count of the list.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GroupedOffsetList {
+    list: OffsetList,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GroupedOffsetList {
+    pub fn new(group_counts: &[u32]) -> GroupedOffsetList {
+        let mut list = OffsetList::new();
+        list.set_group_counts(group_counts);
+        GroupedOffsetList::counted(list, group_counts)
+    }
+
+    /// Groups sized up front, `header_size` large headers over `item_size`
+    /// large items, in place of `measure_headers` and a first `measure`.
+    pub fn from_group_counts(
+        group_counts: &[u32],
+        header_size: u32,
+        item_size: u32,
+    ) -> GroupedOffsetList {
+        let list = OffsetList::from_group_counts(group_counts, header_size, item_size);
+        GroupedOffsetList::counted(list, group_counts)
+    }
+
+    pub fn group_indices(&self) -> Vec<u32> {
+        self.list.group_indices()
+    }
+
+    pub fn group_count(&self) -> u32 {
+        self.list.group_count()
+    }
+
+    /// The number of flat indices, headers included.
+    pub fn total_count(&self) -> u32 {
+        self.list.total_count().unwrap_or(0)
+    }
+
+    pub fn is_group_header(&self, index: u32) -> bool {
+        self.list.is_group_header(index)
+    }
+
+    pub fn transpose(&self, index: u32) -> GroupLocation {
+        let group_index = self.list.group_of(index).unwrap_or(0);
+
+        match self.list.group_start(group_index) {
+            None => GroupLocation {
+                group_index: 0,
+                item_index: index,
+                is_header: false,
+            },
+            Some(group_start) if group_start == index => GroupLocation {
+                group_index,
+                item_index: 0,
+                is_header: true,
+            },
+            Some(group_start) => GroupLocation {
+                group_index,
+                item_index: index - group_start - 1,
+                is_header: false,
+            },
+        }
+    }
+
+    /// The flat index of the `item_index`-th item of the given group, or
+    /// `total_count` past the last group.
+    pub fn flat_index(&self, group_index: u32, item_index: u32) -> u32 {
+        match self.list.group_start(group_index) {
+            None => self.total_count(),
+            Some(group_start) => group_start.saturating_add(item_index).saturating_add(1),
+        }
+    }
+
+    /// Records the size of the group headers, over their measurements. Until
+    /// the first `measure`, the items have none.
+    pub fn measure_headers(&mut self, size: u32) {
+        self.list.set_group_header_size(size);
+    }
+
+    pub fn measure(&mut self, index: u32, size: u32) {
+        self.list.insert(index, index, size);
+    }
+
+    pub fn offset_of(&self, index: u32) -> u32 {
+        self.list.offsets_of(&[index])[0]
+    }
+
+    pub fn total_size(&self) -> u32 {
+        self.list.content_size()
+    }
+
+    pub fn sticky_header(&self, scroll_top: f64) -> Option<StickyHeader> {
+        let group_indices = self.list.group_indices();
+        let header_offsets = self.list.offsets_of(&group_indices);
+
+        let group_index = header_offsets
+            .partition_point(|offset| f64::from(*offset) <= scroll_top)
+            .max(1)
+            - 1;
+
+        let index = *group_indices.get(group_index)?;
+        let header_end = self.list.offsets_of(&[index.saturating_add(1)])[0];
+        let header_size = f64::from(header_end.saturating_sub(header_offsets[group_index]));
+
+        let offset = match header_offsets.get(group_index + 1) {
+            None => 0.0,
+            Some(next_offset) => (f64::from(*next_offset) - scroll_top - header_size).min(0.0),
+        };
+
+        Some(StickyHeader {
+            group_index: group_index as u32,
+            index,
+            offset,
+        })
+    }
+}
+
+impl GroupedOffsetList {
+    /// `list`, counting the headers and items of `group_counts`.
+    fn counted(mut list: OffsetList, group_counts: &[u32]) -> GroupedOffsetList {
+        let total_count = group_counts.iter().fold(0u32, |total, count| {
+            total.saturating_add(count.saturating_add(1))
+        });
+        list.set_total_count(total_count);
+        GroupedOffsetList { list }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GroupLocation;
+    use super::GroupedOffsetList;
+    use super::StickyHeader;
+
+    #[test]
+    fn test_group_indices() {
+        let list = GroupedOffsetList::new(&[2, 0, 3]);
+
+        assert_eq!(list.group_indices(), [0, 3, 4]);
+        assert_eq!(list.total_count(), 8);
+        assert!(list.is_group_header(3));
+        assert!(!list.is_group_header(5));
+    }
+
+    #[test]
+    fn test_transpose() {
+        let list = GroupedOffsetList::new(&[2, 0, 3]);
+
+        assert_eq!(
+            list.transpose(0),
+            GroupLocation {
+                group_index: 0,
+                item_index: 0,
+                is_header: true,
+            }
+        );
+        assert_eq!(
+            list.transpose(2),
+            GroupLocation {
+                group_index: 0,
+                item_index: 1,
+                is_header: false,
+            }
+        );
+        assert_eq!(
+            list.transpose(7),
+            GroupLocation {
+                group_index: 2,
+                item_index: 2,
+                is_header: false,
+            }
+        );
+        assert_eq!(list.flat_index(2, 2), 7);
+        assert_eq!(list.flat_index(3, 0), 8);
+    }
+
+    #[test]
+    fn test_offsets() {
+        let mut list = GroupedOffsetList::new(&[2, 3]);
+        list.measure_headers(10);
+        list.measure(1, 30);
+
+        assert_eq!(list.offset_of(3), 70);
+        assert_eq!(list.offset_of(4), 80);
+        assert_eq!(list.total_size(), 170);
+
+        // the headers take their size over a measurement
+        list.measure(3, 40);
+        list.measure_headers(20);
+        assert_eq!(list.offset_of(4), 100);
+        assert_eq!(list.total_count(), 7);
+    }
+
+    #[test]
+    fn test_from_group_counts() {
+        let mut list = GroupedOffsetList::from_group_counts(&[2, 3], 10, 20);
+        assert_eq!(list.offset_of(3), 50);
+        assert_eq!(list.total_size(), 120);
+
+        list.measure(1, 30);
+        list.measure_headers(15);
+        assert_eq!(list.offset_of(3), 65);
+        assert_eq!(list.total_size(), 140);
+    }
+
+    #[test]
+    fn test_sticky_header() {
+        // headers at offsets 0 and 70, 10px each
+        let mut list = GroupedOffsetList::new(&[2, 3]);
+        list.measure_headers(10);
+        list.measure(1, 30);
+
+        assert_eq!(
+            list.sticky_header(20.0),
+            Some(StickyHeader {
+                group_index: 0,
+                index: 0,
+                offset: 0.0,
+            })
+        );
+        assert_eq!(
+            list.sticky_header(64.0),
+            Some(StickyHeader {
+                group_index: 0,
+                index: 0,
+                offset: -4.0,
+            })
+        );
+        assert_eq!(
+            list.sticky_header(70.0),
+            Some(StickyHeader {
+                group_index: 1,
+                index: 3,
+                offset: 0.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_sticky_header_without_groups() {
+        let list = GroupedOffsetList::new(&[]);
+
+        assert_eq!(list.sticky_header(0.0), None);
+    }
+}
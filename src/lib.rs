@@ -1,19 +1,121 @@
-mod utils;
+//! Without the `std` feature, the crate is `no_std` and needs only `alloc`.
+//! That leaves the offset lists and their queries; the engines on top of them
+//! need `std`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod axis;
+#[cfg(feature = "std")]
+mod bidirectional_list;
+#[cfg(any(feature = "napi", feature = "python", feature = "uniffi"))]
+mod bindings;
+#[cfg(feature = "uniffi")]
+mod ffi;
+#[cfg(not(any(feature = "std", test)))]
+mod float;
+#[cfg(feature = "std")]
+mod follow_output;
+#[cfg(feature = "grid")]
+mod grid_engine;
+#[cfg(feature = "group")]
+mod grouped_list;
+#[cfg(feature = "std")]
+mod list_engine;
+mod log;
+#[cfg(feature = "napi")]
+mod node;
 mod offset_list;
+#[cfg(feature = "std")]
+mod page_planner;
+#[cfg(feature = "python")]
+mod python;
+mod range_set;
+#[cfg(feature = "std")]
+mod reach_tracker;
+#[cfg(feature = "std")]
+mod selection;
+#[cfg(feature = "smooth-scroll")]
+mod smooth_scroll;
+#[cfg(feature = "table")]
+mod table_engine;
+#[cfg(feature = "tree")]
+mod tree_view;
+mod utils;
+#[cfg(feature = "std")]
+mod viewport_state;
+
+pub use axis::{Axis, Direction};
+#[cfg(feature = "std")]
+pub use bidirectional_list::{BidirectionalList, BidirectionalRange};
+#[cfg(feature = "std")]
+pub use follow_output::{FollowOutput, FollowScroll};
+#[cfg(feature = "grid")]
+pub use grid_engine::{GridEngine, GridRange};
+#[cfg(feature = "group")]
+pub use grouped_list::{GroupLocation, GroupedOffsetList, StickyHeader};
+#[cfg(feature = "std")]
+pub use list_engine::{ListEngine, VisibleDelta};
+#[cfg(feature = "log")]
+pub use log::{set_log_level, LogLevel};
+#[cfg(feature = "fenwick")]
+pub use offset_list::FenwickTree;
+pub use offset_list::PerfStats;
+#[cfg(feature = "sync")]
+pub use offset_list::SharedOffsetList;
+#[cfg(any(test, feature = "leak-check"))]
+pub use offset_list::{live_item_lists, live_items};
+pub use offset_list::{CenterItem, NavIntent, NavTarget, ScrollbarThumb};
+pub use offset_list::{ErrorCode, ListError};
+pub use offset_list::{FloatOffsetList, MinimapProjection, OffsetList, OffsetListSnapshot};
+pub use offset_list::{Item, ItemBuffer, ItemList};
+pub use offset_list::{ListEvent, ListEventKind, ListMetrics, MemoryStats, MergePolicy};
+pub use offset_list::{OffsetListBuilder, OffsetListOptions};
+pub use offset_list::{Rounding, Size, SizeTree, SnapAlign, SnapTarget, VisibleSummary};
+#[cfg(feature = "std")]
+pub use page_planner::{PagePlan, PagePlanner};
+#[cfg(feature = "std")]
+pub use reach_tracker::{PrefetchUnit, ReachEvents, ReachTracker};
+#[cfg(feature = "std")]
+pub use selection::SelectionSet;
+#[cfg(feature = "smooth-scroll")]
+pub use smooth_scroll::{Easing, ScrollAnimation};
+#[cfg(feature = "table")]
+pub use table_engine::{TableEngine, TableRange};
+#[cfg(feature = "tree")]
+pub use tree_view::TreeView;
+#[cfg(feature = "std")]
+pub use viewport_state::ViewportState;
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[cfg(all(feature = "wee_alloc", feature = "small"))]
+compile_error!("`wee_alloc` and `small` both set the global allocator, pick one.");
+
+// When the `small` feature is enabled, use `lol_alloc` as the global allocator
+// in wasm builds. It is single threaded, as is the wasm module.
+#[cfg(all(feature = "small", not(feature = "wee_alloc"), target_arch = "wasm32"))]
+#[global_allocator]
+static ALLOC: lol_alloc::AssumeSingleThreaded<lol_alloc::FreeListAllocator> =
+    unsafe { lol_alloc::AssumeSingleThreaded::new(lol_alloc::FreeListAllocator::new()) };
+
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
-extern {
+extern "C" {
     fn alert(s: &str);
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn greet() {
     alert("Hello, rust-virtuoso!");
@@ -0,0 +1,960 @@
+use crate::axis::{Axis, Direction};
+use crate::offset_list::{Item, ItemList, OffsetList, OffsetListOptions, Rounding};
+use std::cmp;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// What changed in the rendered items since the previous `visible_delta`:
+/// the items to mount, with their geometry, and the indices to unmount. The
+/// items rendered both times are left out, although they may have moved.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct VisibleDelta {
+    entered: ItemList,
+    exited: Vec<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl VisibleDelta {
+    pub fn is_empty(&self) -> bool {
+        self.entered.is_empty() && self.exited.is_empty()
+    }
+
+    pub fn entered_indices(&self) -> Vec<u32> {
+        self.entered.indices()
+    }
+
+    pub fn entered_sizes(&self) -> Vec<u32> {
+        self.entered.sizes()
+    }
+
+    pub fn entered_offsets(&self) -> Vec<u32> {
+        self.entered.offsets()
+    }
+
+    pub fn entered_tags(&self) -> Vec<u32> {
+        self.entered.tags()
+    }
+
+    pub fn exited_indices(&self) -> Vec<u32> {
+        self.exited.clone()
+    }
+}
+
+/// A single object the JS adapter drives: it owns the sizes of the items
+/// together with the viewport state, and answers which items to render.
+///
+/// Sticky indices are always rendered, stacked at the top of the viewport in
+/// order. By default they keep their slot in the flow and stick like CSS
+/// `position: sticky`; excluded from the flow, their slots collapse and they
+/// stay pinned at the top.
+///
+/// Horizontal lists read the scroll position and viewport size from the
+/// horizontal axis. Going `Backward`, the offsets grow right to left (or
+/// bottom to top, for reversed chat lists with index 0 at the bottom), and the
+/// reported item offsets are flipped back to distances from the left (top)
+/// edge of the content, so JS can position the items the same way in both
+/// directions. The content is at least as large as the viewport, so a short
+/// backward list stays anchored at its start edge. The scroller is expected to
+/// be laid out backward as well (`direction: rtl` or
+/// `flex-direction: column-reverse`), so that its scroll position counts from
+/// the start edge.
+///
+/// In window scroll mode, the scroll position and viewport size are the ones
+/// of the window, and the list starts `window_offset` pixels into the page.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ListEngine {
+    list: OffsetList,
+    axis: Axis,
+    direction: Direction,
+    scroll_offset: f64,
+    viewport_size: f64,
+    window_offset: Option<f64>,
+    overscan: u32,
+    sticky_indices: Vec<u32>,
+    exclude_sticky_from_flow: bool,
+    total_size_listener: Option<Box<dyn Fn(u32)>>,
+    reported_total_size: u32,
+    /// The indices the last `visible_delta` left rendered.
+    delta_indices: Vec<u32>,
+    max_content_size: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ListEngine {
+    pub fn new(total_count: u32, overscan: u32) -> ListEngine {
+        let mut list = OffsetList::new();
+        list.set_total_count(total_count);
+
+        ListEngine {
+            list,
+            axis: Axis::Vertical,
+            direction: Direction::Forward,
+            scroll_offset: 0.0,
+            viewport_size: 0.0,
+            window_offset: None,
+            overscan,
+            sticky_indices: Vec::new(),
+            exclude_sticky_from_flow: false,
+            total_size_listener: None,
+            reported_total_size: 0,
+            delta_indices: Vec::new(),
+            max_content_size: 0,
+        }
+    }
+
+    /// An engine over `options.expected_count` items, on the axis and with
+    /// the list settings in `options`.
+    pub fn with_options(options: &OffsetListOptions, overscan: u32) -> ListEngine {
+        let mut engine = ListEngine::new(options.expected_count, overscan);
+        engine.list = OffsetList::with_options(options);
+        engine.list.set_total_count(options.expected_count);
+        engine.set_axis(options.axis, options.direction);
+        engine
+    }
+
+    pub fn set_axis(&mut self, axis: Axis, direction: Direction) {
+        self.axis = axis;
+        self.direction = direction;
+    }
+
+    pub fn set_scroll_top(&mut self, scroll_top: f64) {
+        self.scroll_offset = scroll_top.max(0.0);
+    }
+
+    pub fn set_viewport_height(&mut self, viewport_height: f64) {
+        self.viewport_size = viewport_height.max(0.0);
+    }
+
+    /// Takes the raw `scrollLeft` and `scrollTop` of the scroller. The
+    /// `scrollLeft` of an RTL scroller goes negative as it scrolls away from
+    /// its right edge.
+    pub fn set_scroll_position(&mut self, scroll_left: f64, scroll_top: f64) {
+        let scroll_offset = match self.direction {
+            Direction::Forward => self.axis.main(scroll_left, scroll_top),
+            Direction::Backward => self.axis.main(scroll_left, scroll_top).abs(),
+        };
+        self.scroll_offset = scroll_offset.max(0.0);
+    }
+
+    pub fn set_viewport_size(&mut self, width: f64, height: f64) {
+        self.viewport_size = self.axis.main(width, height).max(0.0);
+    }
+
+    /// Switches to window scroll mode, for a list starting `list_offset`
+    /// pixels from the top (or the left) of the document.
+    pub fn set_window_scroll(&mut self, list_offset: f64) {
+        self.window_offset = Some(list_offset);
+    }
+
+    pub fn set_element_scroll(&mut self) {
+        self.window_offset = None;
+    }
+
+    /// How far the viewport is scrolled into the list; zero while the list
+    /// starts below the top of the window.
+    pub fn list_scroll_offset(&self) -> f64 {
+        self.scroll_start().max(0.0)
+    }
+
+    pub fn set_overscan(&mut self, overscan: u32) {
+        self.overscan = overscan;
+    }
+
+    pub fn set_gap(&mut self, gap: u32) {
+        self.list.set_gap(gap);
+        self.notify_total_size();
+    }
+
+    pub fn set_header_height(&mut self, height: u32) {
+        self.list.set_header_height(height);
+        self.notify_total_size();
+    }
+
+    pub fn set_footer_height(&mut self, height: u32) {
+        self.list.set_footer_height(height);
+        self.notify_total_size();
+    }
+
+    pub fn set_padding(&mut self, padding_top: u32, padding_bottom: u32) {
+        self.list.set_padding(padding_top, padding_bottom);
+        self.notify_total_size();
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.list.set_scale(scale);
+        self.notify_total_size();
+    }
+
+    pub fn set_rounding(&mut self, rounding: Rounding, device_pixel_ratio: f64) {
+        self.list.set_rounding(rounding, device_pixel_ratio);
+        self.notify_total_size();
+    }
+
+    /// Sets the count of the list, which clamps its ranges to it.
+    pub fn set_total_count(&mut self, total_count: u32) {
+        self.list.set_total_count(total_count);
+        self.notify_total_size();
+    }
+
+    pub fn total_count(&self) -> u32 {
+        self.list.total_count().unwrap_or(0)
+    }
+
+    /// The size of the items that haven't been measured yet.
+    pub fn set_default_size(&mut self, size: u32) {
+        self.list.set_default_size(size);
+        self.notify_total_size();
+    }
+
+    pub fn set_type_size(&mut self, item_type: u32, size: u32) {
+        self.list.set_type_size(item_type, size);
+        self.notify_total_size();
+    }
+
+    pub fn set_index_type(&mut self, start: u32, end: u32, item_type: u32) {
+        self.list.set_index_type(start, end, item_type);
+        self.notify_total_size();
+    }
+
+    /// Estimates the size of the unmeasured items from the measurements, see
+    /// `OffsetList::set_size_estimation`.
+    pub fn set_size_estimation(&mut self, alpha: f64) {
+        self.list.set_size_estimation(alpha);
+        self.notify_total_size();
+    }
+
+    pub fn measure(&mut self, index: u32, size: u32) {
+        self.list.insert(index, index, size);
+        self.notify_total_size();
+    }
+
+    pub fn measure_tagged(&mut self, index: u32, size: u32, tag: u32) {
+        self.list.insert_tagged(index, index, size, tag);
+        self.notify_total_size();
+    }
+
+    /// See `OffsetList::set_measurement_pruning`.
+    pub fn set_measurement_pruning(&mut self, distance: u32) {
+        self.list.set_measurement_pruning(distance);
+    }
+
+    /// Forgets the measurements far from the rendered items, under the
+    /// pruning policy, and returns how many items were forgotten. Meant to be
+    /// called once the scrolling settles, as the forgotten sizes above the
+    /// viewport shift it.
+    pub fn prune_measurements(&mut self) -> u32 {
+        let rendered: Vec<u32> = self.render_items().iter().map(|item| item.index).collect();
+
+        let pruned = match (rendered.first(), rendered.last()) {
+            (Some(first), Some(last)) => self.list.prune_measurements(*first, *last),
+            _ => 0,
+        };
+        self.notify_total_size();
+        pruned
+    }
+
+    /// Calls `callback` with the new `total_size` after every call that
+    /// changes it, so the adapter can resize the scroller without polling.
+    /// Replaces the previous callback.
+    #[cfg(feature = "wasm")]
+    pub fn on_total_size_change(&mut self, callback: js_sys::Function) {
+        self.set_total_size_listener(Box::new(move |total| {
+            // an exception in the callback is the app's to report
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from(total));
+        }));
+    }
+
+    pub fn remove_total_size_listener(&mut self) {
+        self.total_size_listener = None;
+    }
+
+    pub fn set_sticky_indices(&mut self, indices: &[u32], exclude_from_flow: bool) {
+        let mut sticky_indices = indices.to_vec();
+        sticky_indices.sort_unstable();
+        sticky_indices.dedup();
+
+        self.sticky_indices = sticky_indices;
+        self.exclude_sticky_from_flow = exclude_from_flow;
+        self.notify_total_size();
+    }
+
+    /// The size of the scrollable content, capped at the maximum content
+    /// size when one is set.
+    pub fn total_size(&self) -> u32 {
+        match self.max_content_size {
+            0 => self.content_size(),
+            max => cmp::min(self.content_size(), max),
+        }
+    }
+
+    /// Browsers cap the size of an element, around 33.5M pixels in Chrome;
+    /// the content of a longer list is cut off. With a maximum set, a longer
+    /// list is compressed into it: the scroll position maps proportionally to
+    /// the true offset, and the items are drawn around the viewport at their
+    /// true sizes. Zero turns the compression off.
+    pub fn set_max_content_size(&mut self, size: u32) {
+        self.max_content_size = size;
+        self.notify_total_size();
+    }
+
+    /// The scroll position that brings `index` to the top of the viewport,
+    /// compressed or not, for `scrollTo`.
+    pub fn scroll_top_of(&self, index: u32) -> f64 {
+        let stickies = self.sticky_items();
+        let offset = match self.list.items(index, index).first() {
+            None => 0.0,
+            Some(item) => f64::from(self.flow_offset(item, &stickies)),
+        };
+
+        match self.compression() {
+            None => offset,
+            Some(ratio) => offset / ratio,
+        }
+    }
+
+    /// Where the item is drawn, in the same coordinates as `render_range`.
+    pub fn offset_of(&self, index: u32) -> u32 {
+        let stickies = self.sticky_items();
+
+        let items: Vec<Item> = self
+            .list
+            .items(index, index)
+            .into_iter()
+            .map(|item| item.at_offset(self.flow_offset(&item, &stickies)))
+            .collect();
+
+        match self.physical(items).first() {
+            None => 0,
+            Some(item) => item.offset,
+        }
+    }
+
+    /// The rendered items that were never measured, to observe for size
+    /// changes. The layout is settled once this is empty.
+    pub fn unmeasured_indices(&self) -> Vec<u32> {
+        self.render_items()
+            .iter()
+            .map(|item| item.index)
+            .filter(|index| !self.list.is_measured(*index))
+            .collect()
+    }
+
+    /// The sticky items with the offsets they are drawn at.
+    pub fn pinned_items(&self) -> ItemList {
+        ItemList::new(
+            self.physical(self.pinned(&self.sticky_items())),
+            self.list.version(),
+            self.list.config_hash(),
+        )
+    }
+
+    /// Moves the viewport and returns how the rendered items changed since
+    /// the previous call, so the adapter mounts and unmounts only those. The
+    /// first call reports every rendered item as entered.
+    pub fn visible_delta(&mut self, scroll_top: f64, viewport_height: f64) -> VisibleDelta {
+        self.set_scroll_top(scroll_top);
+        self.set_viewport_height(viewport_height);
+
+        let items = self.physical(self.render_items());
+        let indices: Vec<u32> = items.iter().map(|item| item.index).collect();
+        let exited: Vec<u32> = self
+            .delta_indices
+            .iter()
+            .filter(|index| indices.binary_search(index).is_err())
+            .copied()
+            .collect();
+        let entered: Vec<Item> = items
+            .into_iter()
+            .filter(|item| self.delta_indices.binary_search(&item.index).is_err())
+            .collect();
+
+        self.delta_indices = indices;
+        VisibleDelta {
+            entered: ItemList::new(entered, self.list.version(), self.list.config_hash()),
+            exited,
+        }
+    }
+
+    /// The items intersecting the viewport, extended by `overscan` pixels in
+    /// both directions, together with the sticky items. Before anything is
+    /// measured, this is the first item alone, with a zero size, so that JS
+    /// can render and measure it.
+    pub fn render_range(&self) -> ItemList {
+        ItemList::new(
+            self.physical(self.render_items()),
+            self.list.version(),
+            self.list.config_hash(),
+        )
+    }
+}
+
+impl ListEngine {
+    /// `on_total_size_change` for Rust callers.
+    pub fn set_total_size_listener(&mut self, listener: Box<dyn Fn(u32)>) {
+        self.reported_total_size = self.total_size();
+        self.total_size_listener = Some(listener);
+    }
+
+    fn notify_total_size(&mut self) {
+        if self.total_size_listener.is_none() {
+            return;
+        }
+
+        let total = self.total_size();
+        if total != self.reported_total_size {
+            self.reported_total_size = total;
+            if let Some(listener) = &self.total_size_listener {
+                listener(total);
+            }
+        }
+    }
+
+    /// The scroll position relative to the start of the list, negative while
+    /// the window hasn't reached the list yet.
+    fn scroll_start(&self) -> f64 {
+        match self.window_offset {
+            None => self.scroll_offset,
+            Some(window_offset) => self.scroll_offset - window_offset,
+        }
+    }
+
+    /// `scroll_start` in the offsets of the items, which are further apart
+    /// than the scroll positions with the content compressed.
+    fn viewport_start(&self) -> f64 {
+        match self.compression() {
+            None => self.scroll_start(),
+            Some(ratio) => self.scroll_start() * ratio,
+        }
+    }
+
+    /// The content size over the total size, for the content that doesn't
+    /// fit into the maximum size. That many pixels of content scroll by for
+    /// each pixel of scrolling.
+    fn compression(&self) -> Option<f64> {
+        let max = self.max_content_size;
+        let content = self.content_size();
+        if max == 0 || content <= max {
+            return None;
+        }
+
+        let viewport = self.viewport_size.min(f64::from(max) - 1.0);
+        Some((f64::from(content) - viewport) / (f64::from(max) - viewport))
+    }
+
+    fn content_size(&self) -> u32 {
+        let total = self.list.content_size();
+
+        if self.exclude_sticky_from_flow {
+            let sticky_size = self
+                .sticky_items()
+                .iter()
+                .fold(0u32, |size, item| size.saturating_add(item.size));
+            total.saturating_sub(sticky_size)
+        } else {
+            total
+        }
+    }
+
+    fn render_items(&self) -> Vec<Item> {
+        if self.total_count() == 0 {
+            return Vec::new();
+        }
+
+        if self.list.is_empty() {
+            return vec![Item::new(0, 0, 0)];
+        }
+
+        let stickies = self.sticky_items();
+
+        let overscan = f64::from(self.overscan);
+        let viewport_start = self.viewport_start();
+        let start_offset = (viewport_start - overscan).max(0.0) as u32;
+        let end_offset = (viewport_start + self.viewport_size + overscan).max(0.0) as u32;
+
+        let (first, last) = match self.list.visible_span(
+            self.list_offset(start_offset, &stickies),
+            self.list_offset(end_offset, &stickies),
+            self.total_count(),
+        ) {
+            None => return Vec::new(),
+            Some(span) => span,
+        };
+
+        let mut result: Vec<Item> = self
+            .list
+            .items(first, last)
+            .into_iter()
+            .filter(|item| self.sticky_indices.binary_search(&item.index).is_err())
+            .map(|item| item.at_offset(self.flow_offset(&item, &stickies)))
+            .collect();
+
+        result.extend(self.pinned(&stickies));
+        result.sort_unstable_by_key(|item| item.index);
+        result
+    }
+
+    fn sticky_items(&self) -> Vec<Item> {
+        if self.list.is_empty() {
+            return Vec::new();
+        }
+
+        self.sticky_indices
+            .iter()
+            .filter(|index| **index < self.total_count())
+            .flat_map(|index| self.list.items(*index, *index))
+            .collect()
+    }
+
+    /// Where an item sits in the scrollable flow, which lacks the slots of the
+    /// sticky items when they are excluded from it.
+    fn flow_offset(&self, item: &Item, stickies: &[Item]) -> u32 {
+        if !self.exclude_sticky_from_flow {
+            return item.offset;
+        }
+
+        let excluded: u32 = stickies
+            .iter()
+            .take_while(|sticky| sticky.index < item.index)
+            .fold(0, |excluded: u32, sticky| {
+                excluded.saturating_add(sticky.size)
+            });
+
+        item.offset.saturating_sub(excluded)
+    }
+
+    /// The inverse of `flow_offset`, for a pixel position in the flow.
+    fn list_offset(&self, flow_offset: u32, stickies: &[Item]) -> u32 {
+        if !self.exclude_sticky_from_flow {
+            return flow_offset;
+        }
+
+        let mut offset = flow_offset;
+        for sticky in stickies {
+            if self.flow_offset(sticky, stickies) > flow_offset {
+                break;
+            }
+            offset = offset.saturating_add(sticky.size);
+        }
+
+        offset
+    }
+
+    fn pinned(&self, stickies: &[Item]) -> Vec<Item> {
+        let mut stack_offset = self.viewport_start().max(0.0) as u32;
+
+        stickies
+            .iter()
+            .map(|sticky| {
+                let offset = if self.exclude_sticky_from_flow {
+                    stack_offset
+                } else {
+                    cmp::max(sticky.offset, stack_offset)
+                };
+                stack_offset = stack_offset.saturating_add(sticky.size);
+                sticky.at_offset(offset)
+            })
+            .collect()
+    }
+
+    /// Maps offsets from the start of the list to offsets from the left (top)
+    /// edge of the content: pulls them in around the viewport when it is
+    /// compressed, and flips them going backward.
+    fn physical(&self, items: Vec<Item>) -> Vec<Item> {
+        let items = match self.compression() {
+            None => items,
+            Some(_) => {
+                let scroll_start = self.scroll_start().max(0.0) as u32;
+                let viewport_start = self.viewport_start().max(0.0) as u32;
+                items
+                    .into_iter()
+                    .map(|item| {
+                        item.at_offset(
+                            item.offset
+                                .saturating_add(scroll_start)
+                                .saturating_sub(viewport_start),
+                        )
+                    })
+                    .collect()
+            }
+        };
+
+        if self.direction == Direction::Forward {
+            return items;
+        }
+
+        let total = cmp::max(self.total_size(), self.viewport_size as u32);
+        items
+            .into_iter()
+            .map(|item| {
+                let offset = total.saturating_sub(item.offset.saturating_add(item.size));
+                item.at_offset(offset)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListEngine;
+    use crate::axis::{Axis, Direction};
+    use crate::offset_list::OffsetListBuilder;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_initial_render_range() {
+        let engine = ListEngine::new(100, 0);
+        let items = engine.render_range();
+
+        assert_eq!(items.indices(), [0]);
+        assert_eq!(items.sizes(), [0]);
+    }
+
+    #[test]
+    fn test_empty_list() {
+        let engine = ListEngine::new(0, 0);
+
+        assert!(engine.render_range().is_empty());
+    }
+
+    #[test]
+    fn test_render_range() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+
+        assert_eq!(engine.render_range().indices(), [0, 1, 2, 3]);
+        assert_eq!(engine.total_size(), 3000);
+
+        engine.set_scroll_top(45.0);
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [1, 2, 3, 4]);
+        assert_eq!(items.offsets(), [30, 60, 90, 120]);
+    }
+
+    #[test]
+    fn test_overscan() {
+        let mut engine = ListEngine::new(100, 20);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.set_scroll_top(45.0);
+
+        assert_eq!(engine.render_range().indices(), [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_measurements_change_the_range() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.measure(1, 100);
+
+        assert_eq!(engine.render_range().indices(), [0, 1]);
+        assert_eq!(engine.total_size(), 3070);
+    }
+
+    #[test]
+    fn test_clamps_to_total_count() {
+        let mut engine = ListEngine::new(5, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.set_scroll_top(1000.0);
+
+        assert_eq!(engine.render_range().indices(), [4]);
+
+        // the list has the same count, and clamps its own queries to it
+        engine.set_total_count(3);
+        assert_eq!(engine.total_count(), 3);
+        assert_eq!(engine.list.total_count(), Some(3));
+        assert_eq!(engine.list.offset_range(0, 1000).len(), 3);
+        assert_eq!(engine.total_size(), 90);
+    }
+
+    #[test]
+    fn test_sticky_items_are_always_rendered() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.set_sticky_indices(&[0], false);
+        engine.set_scroll_top(300.0);
+
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [0, 10, 11, 12, 13]);
+        assert_eq!(items.offsets(), [300, 300, 330, 360, 390]);
+        assert_eq!(engine.total_size(), 3000);
+    }
+
+    #[test]
+    fn test_sticky_item_before_its_slot() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.set_sticky_indices(&[5], false);
+        engine.set_scroll_top(30.0);
+
+        let pinned = engine.pinned_items();
+        assert_eq!(pinned.indices(), [5]);
+        assert_eq!(pinned.offsets(), [150]);
+    }
+
+    #[test]
+    fn test_sticky_items_excluded_from_flow() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.measure(2, 50);
+        engine.set_sticky_indices(&[0, 2], true);
+
+        assert_eq!(engine.total_size(), 2940);
+
+        let pinned = engine.pinned_items();
+        assert_eq!(pinned.indices(), [0, 2]);
+        assert_eq!(pinned.offsets(), [0, 30]);
+
+        // the flow is 1, 3, 4, ... at 0, 30, 60, ...
+        engine.set_scroll_top(60.0);
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [0, 2, 4, 5, 6, 7]);
+        assert_eq!(items.offsets(), [60, 90, 60, 90, 120, 150]);
+    }
+
+    #[test]
+    fn test_huge_sticky_items_saturate() {
+        let mut engine = ListEngine::new(10, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, u32::MAX / 2);
+        engine.measure(1, u32::MAX / 2);
+        engine.measure(2, u32::MAX / 2);
+        engine.measure(3, u32::MAX / 2);
+        engine.set_sticky_indices(&[0, 1, 2, 3], true);
+
+        let pinned = engine.pinned_items();
+        assert_eq!(pinned.indices(), [0, 1, 2, 3]);
+        assert_eq!(pinned.offsets(), [0, u32::MAX / 2, u32::MAX - 1, u32::MAX]);
+        assert!(!engine.render_range().indices().is_empty());
+    }
+
+    #[test]
+    fn test_horizontal() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_axis(Axis::Horizontal, Direction::Forward);
+        engine.set_viewport_size(100.0, 30.0);
+        engine.measure(0, 30);
+        engine.set_scroll_position(45.0, 0.0);
+
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [1, 2, 3, 4]);
+        assert_eq!(items.offsets(), [30, 60, 90, 120]);
+    }
+
+    #[test]
+    fn test_with_options() {
+        let mut engine = OffsetListBuilder::new()
+            .default_size(30)
+            .expected_count(100)
+            .axis(Axis::Horizontal, Direction::Forward)
+            .gap(2)
+            .build_engine(0);
+        engine.set_viewport_size(100.0, 30.0);
+        engine.set_scroll_position(45.0, 0.0);
+
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [1, 2, 3, 4]);
+        assert_eq!(items.offsets(), [32, 64, 96, 128]);
+    }
+
+    #[test]
+    fn test_rtl() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_axis(Axis::Horizontal, Direction::Backward);
+        engine.set_viewport_size(100.0, 30.0);
+        engine.measure(0, 30);
+        engine.set_scroll_position(-45.0, 0.0);
+
+        // item 1 spans 30..60 from the right edge of the 3000px content
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [1, 2, 3, 4]);
+        assert_eq!(items.offsets(), [2940, 2910, 2880, 2850]);
+    }
+
+    #[test]
+    fn test_rtl_pinned_items() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_axis(Axis::Horizontal, Direction::Backward);
+        engine.set_viewport_size(100.0, 30.0);
+        engine.measure(0, 30);
+        engine.set_sticky_indices(&[0], false);
+        engine.set_scroll_position(-300.0, 0.0);
+
+        assert_eq!(engine.pinned_items().offsets(), [2670]);
+    }
+
+    #[test]
+    fn test_window_scroll() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_window_scroll(200.0);
+        engine.set_viewport_height(300.0);
+        engine.measure(0, 30);
+
+        // the window shows the first 100px of the list
+        engine.set_scroll_top(0.0);
+        assert_eq!(engine.render_range().indices(), [0, 1, 2, 3]);
+        assert_eq!(engine.list_scroll_offset(), 0.0);
+
+        engine.set_scroll_top(500.0);
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [10, 11, 12, 13, 14, 15, 16, 17, 18, 19]);
+        assert_eq!(items.offsets()[0], 300);
+        assert_eq!(engine.list_scroll_offset(), 300.0);
+
+        engine.set_element_scroll();
+        assert_eq!(engine.render_range().indices()[0], 16);
+    }
+
+    #[test]
+    fn test_reversed() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_axis(Axis::Vertical, Direction::Backward);
+        engine.set_viewport_size(300.0, 100.0);
+        engine.measure(0, 30);
+
+        // index 0 sits at the bottom of the 3000px content
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [0, 1, 2, 3]);
+        assert_eq!(items.offsets(), [2970, 2940, 2910, 2880]);
+        assert_eq!(engine.offset_of(1), 2940);
+
+        engine.set_scroll_position(0.0, -45.0);
+        assert_eq!(engine.render_range().indices(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_short_reversed_list_is_bottom_anchored() {
+        let mut engine = ListEngine::new(2, 0);
+        engine.set_axis(Axis::Vertical, Direction::Backward);
+        engine.set_viewport_size(300.0, 100.0);
+        engine.measure(0, 30);
+
+        assert_eq!(engine.render_range().offsets(), [70, 40]);
+    }
+
+    #[test]
+    fn test_default_size() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_viewport_height(100.0);
+        engine.set_default_size(50);
+
+        assert_eq!(engine.render_range().indices(), [0, 1]);
+        assert_eq!(engine.total_size(), 5000);
+
+        assert_eq!(engine.unmeasured_indices(), [0, 1]);
+
+        engine.measure(0, 30);
+        assert_eq!(engine.render_range().indices(), [0, 1, 2]);
+        assert_eq!(engine.total_size(), 4980);
+        assert_eq!(engine.unmeasured_indices(), [1, 2]);
+    }
+
+    #[test]
+    fn test_gap() {
+        let mut engine = ListEngine::new(10, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.set_gap(10);
+
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [0, 1, 2]);
+        assert_eq!(items.offsets(), [0, 40, 80]);
+        assert_eq!(engine.total_size(), 390);
+    }
+
+    #[test]
+    fn test_header_and_footer() {
+        let mut engine = ListEngine::new(10, 0);
+        engine.set_viewport_height(100.0);
+        engine.measure(0, 30);
+        engine.set_header_height(80);
+        engine.set_footer_height(40);
+
+        let items = engine.render_range();
+        assert_eq!(items.indices(), [0]);
+        assert_eq!(items.offsets(), [80]);
+        assert_eq!(engine.total_size(), 420);
+        assert_eq!(engine.offset_of(2), 140);
+    }
+
+    #[test]
+    fn test_prune_measurements() {
+        let mut engine = ListEngine::new(1000, 0);
+        engine.set_viewport_height(100.0);
+        engine.set_default_size(10);
+        for index in 0..20 {
+            engine.measure(index, 50);
+        }
+        engine.set_measurement_pruning(5);
+
+        assert_eq!(engine.prune_measurements(), 13);
+        assert_eq!(engine.render_range().indices(), [0, 1]);
+        assert_eq!(engine.total_size(), 10 * 1000 + 40 * 7);
+    }
+
+    #[test]
+    fn test_total_size_listener() {
+        let totals = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = ListEngine::new(10, 0);
+        engine.set_default_size(10);
+
+        let recorded = Rc::clone(&totals);
+        engine.set_total_size_listener(Box::new(move |total| recorded.borrow_mut().push(total)));
+        engine.measure(0, 30);
+        engine.measure(1, 10);
+        engine.set_gap(2);
+        engine.set_total_count(5);
+        engine.remove_total_size_listener();
+        engine.set_total_count(6);
+
+        assert_eq!(*totals.borrow(), [120, 138, 78]);
+    }
+
+    #[test]
+    fn test_visible_delta() {
+        let mut engine = ListEngine::new(100, 0);
+        engine.set_default_size(10);
+
+        let first = engine.visible_delta(0.0, 30.0);
+        assert_eq!(first.entered_indices(), [0, 1, 2]);
+        assert!(first.exited_indices().is_empty());
+
+        let scrolled = engine.visible_delta(25.0, 30.0);
+        assert_eq!(scrolled.entered_indices(), [3, 4, 5]);
+        assert_eq!(scrolled.entered_offsets(), [30, 40, 50]);
+        assert_eq!(scrolled.exited_indices(), [0, 1]);
+
+        assert!(engine.visible_delta(25.0, 30.0).is_empty());
+    }
+
+    #[test]
+    fn test_max_content_size() {
+        let mut engine = ListEngine::new(100_000, 0);
+        engine.set_default_size(10);
+        engine.set_viewport_height(1000.0);
+        engine.set_max_content_size(100_900);
+        assert_eq!(engine.total_size(), 100_900);
+
+        engine.set_scroll_top(50_000.0);
+        let items = engine.render_range();
+        assert_eq!(items.indices()[0], 50_000);
+        assert_eq!(items.offsets()[0..2], [50_000, 50_010]);
+        assert_eq!(items.sizes()[0], 10);
+        assert_eq!(engine.scroll_top_of(50_000), 50_000.0);
+
+        engine.set_scroll_top(99_900.0);
+        let items = engine.render_range();
+        assert_eq!(*items.indices().last().unwrap(), 99_999);
+        assert_eq!(*items.offsets().last().unwrap(), 100_890);
+
+        engine.set_max_content_size(0);
+        assert_eq!(engine.total_size(), 1_000_000);
+        assert_eq!(engine.render_range().indices()[0], 9_990);
+    }
+}
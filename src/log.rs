@@ -0,0 +1,94 @@
+//! Leveled tracing of the engine decisions, merges, rebuilds and anchor
+//! compensation among them, for diagnosing scroll glitches in production.
+//! With the `log` feature, the messages go to `console.debug` in the browser
+//! and to stderr natively, once `set_log_level` turns them on. Without it,
+//! `trace!` compiles to nothing.
+
+#[cfg(feature = "log")]
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(all(feature = "log", feature = "wasm"))]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "log")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum LogLevel {
+    Off = 0,
+    /// The decisions that change what the list holds: merges, prunes.
+    Info = 1,
+    /// Every rebuild and compensation, with the numbers behind them.
+    Debug = 2,
+}
+
+#[cfg(feature = "log")]
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Off as u8);
+
+/// Logs the messages up to `level` from now on, for every list.
+#[cfg(feature = "log")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn set_log_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+#[cfg(feature = "log")]
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+#[cfg(all(feature = "log", feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(message: &str);
+}
+
+#[cfg(feature = "log")]
+pub fn write(message: &str) {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    console_debug(&alloc::format!("[rust-virtuoso] {}", message));
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    std::eprintln!("[rust-virtuoso] {}", message);
+    #[cfg(not(feature = "std"))]
+    let _ = message;
+}
+
+/// `trace!(Debug, "format", args)` logs the message when `Debug` is on.
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($level:ident, $($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::LogLevel::$level) {
+            $crate::log::write(&alloc::format!($($arg)*));
+        }
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($level:ident, $($arg:tt)*) => {
+        if false {
+            let _ = core::format_args!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use trace;
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use super::{enabled, set_log_level, LogLevel};
+
+    #[test]
+    fn test_levels() {
+        assert!(!enabled(LogLevel::Info));
+
+        set_log_level(LogLevel::Info);
+        assert!(enabled(LogLevel::Info));
+        assert!(!enabled(LogLevel::Debug));
+        trace!(Info, "merge: took the sizes of {} items", 3);
+
+        set_log_level(LogLevel::Off);
+        assert!(!enabled(LogLevel::Off));
+        assert!(!enabled(LogLevel::Info));
+    }
+}
@@ -0,0 +1,66 @@
+//! Native Node.js bindings through napi-rs, for Electron apps and SSR that
+//! would rather skip instantiating the wasm module. The methods are
+//! camelCased, the way Node addons name them.
+
+use crate::bindings::offset_list_bindings;
+use crate::offset_list::OffsetList;
+use napi_derive::napi;
+
+#[napi(js_name = "OffsetList")]
+pub struct NodeOffsetList {
+    list: OffsetList,
+}
+
+offset_list_bindings! {
+    /// An `Item` as a plain JS object.
+    #[napi(object)]
+    item NodeItem;
+
+    #[napi]
+    impl NodeOffsetList {
+        #[napi(constructor)]
+        pub fn new() -> NodeOffsetList {
+            NodeOffsetList {
+                list: OffsetList::new(),
+            }
+        }
+    }
+
+    methods [#[napi]], writes (&mut self);
+}
+
+impl NodeOffsetList {
+    fn list(&self) -> &OffsetList {
+        &self.list
+    }
+
+    fn list_mut(&mut self) -> &mut OffsetList {
+        &mut self.list
+    }
+}
+
+impl Default for NodeOffsetList {
+    fn default() -> Self {
+        NodeOffsetList::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeOffsetList;
+
+    #[test]
+    fn test_wraps_the_list() {
+        let mut list = NodeOffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 3, 20);
+
+        let items = list.range(0, 45, 0, 9);
+        let sizes: Vec<(u32, u32, u32)> = items
+            .iter()
+            .map(|item| (item.index, item.size, item.offset))
+            .collect();
+        assert_eq!(sizes, [(0, 10, 0), (1, 10, 10), (2, 20, 20), (3, 20, 40)]);
+        assert_eq!(list.offsets_of(vec![1, 4]), [10, 60]);
+    }
+}
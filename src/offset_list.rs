@@ -1,551 +1,3483 @@
+mod builder;
+mod cells;
+mod collapse;
+mod error;
+mod events;
+#[cfg(feature = "fenwick")]
+mod fenwick;
+mod float_list;
+mod groups;
+mod history;
+mod items;
+mod keys;
+mod merge;
+mod metrics;
+mod minimap;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod model;
+mod perf;
+mod rounding;
+#[cfg(feature = "sync")]
+mod shared;
+mod signed;
+mod size;
+mod size_tree;
+mod snapshot;
+mod store;
+mod transfer;
 mod tree_utils;
-
-use std::cmp;
-use std::collections::BTreeMap;
+mod viewport;
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::float::Float;
+use crate::log::trace;
+use crate::range_set::RangeSet;
+use crate::utils;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+pub use builder::{OffsetListBuilder, OffsetListOptions};
+use cells::{Counter, Flag, Lock, ReadGuard, Shared};
+use collapse::Collapsed;
+use core::cmp;
+pub use error::{ErrorCode, ListError};
+use events::ListListener;
+pub use events::{ListEvent, ListEventKind};
+#[cfg(feature = "fenwick")]
+pub use fenwick::FenwickTree;
+pub use float_list::FloatOffsetList;
+use groups::Groups;
+use history::History;
+#[cfg(any(test, feature = "leak-check"))]
+pub use items::{live_item_lists, live_items};
+pub use items::{Item, ItemBuffer, ItemList};
+use keys::KeyRegistry;
+pub use merge::MergePolicy;
+pub use metrics::{ListMetrics, MemoryStats};
+pub use minimap::MinimapProjection;
+pub use perf::PerfStats;
+use perf::{Operation, PerfCounters, Stopwatch};
+pub use rounding::Rounding;
+#[cfg(feature = "sync")]
+pub use shared::SharedOffsetList;
+pub use size::Size;
+pub use size_tree::SizeTree;
+pub use snapshot::OffsetListSnapshot;
+use store::SizeStore;
 use tree_utils::Range;
+pub use viewport::{CenterItem, NavIntent, NavTarget, ScrollbarThumb};
+pub use viewport::{SnapAlign, SnapTarget, VisibleSummary};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen]
-pub struct Item {
-    size: u32,
-    offset: u32,
-    index: u32,
-}
-
-#[wasm_bindgen]
+/// The sizes of the items of a list, and the offsets they add up to.
+///
+/// Index ranges are inclusive; one that starts after its end is empty, and
+/// the writes ignore it. Offsets past `u32::MAX` stay there instead of
+/// wrapping around, and so do the sizes and counts derived from them.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone)]
 pub struct OffsetList {
-    size_tree: BTreeMap<u32, u32>,
-    offset_tree: BTreeMap<u32, u32>,
-    pixel_tree: BTreeMap<u32, u32>,
+    /// Shared with the snapshots, and copied on the first write after one.
+    tree: Lock<Shared<SizeStore>>,
+    /// Set when the default size changed while the unmeasured items still
+    /// have the previous one. The next query catches up, so a burst of
+    /// measurements that each move the estimate rebuilds the sizes once.
+    stale: Flag,
+    size_rebuilds: Counter,
+    measured: Shared<RangeSet>,
+    default_size: Option<u32>,
+    item_types: BTreeMap<u32, u32>,
+    tags: BTreeMap<u32, u32>,
+    gap: u32,
+    header_height: u32,
+    footer_height: u32,
+    padding_top: u32,
+    padding_bottom: u32,
+    scale: f64,
+    rounding: Rounding,
+    pixel_step: f64,
+    type_sizes: BTreeMap<u32, u32>,
+    estimation: Option<f64>,
+    estimate: f64,
+    samples: u32,
+    version: u32,
+    size_quantum: u32,
+    prune_distance: Option<u32>,
+    history: History,
+    listener: Option<ListListener>,
+    keys: Shared<KeyRegistry>,
+    collapsed: Collapsed,
+    groups: Groups,
+    total_count: Option<u32>,
+    origin: i32,
+    /// The generation of each range while `track_generations` is on.
+    generations: Option<BTreeMap<u32, u32>>,
+    generation: u32,
+    perf: PerfCounters,
+    quantized_inserts: u32,
+    ranges_before_quantization: u32,
+    ranges_after_quantization: u32,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl OffsetList {
     pub fn new() -> OffsetList {
         OffsetList {
-            size_tree: BTreeMap::new(),
-            offset_tree: BTreeMap::new(),
-            pixel_tree: BTreeMap::new(),
+            tree: Lock::new(Shared::new(SizeStore::Ranges(SizeTree::new()))),
+            stale: Flag::new(false),
+            size_rebuilds: Counter::new(0),
+            measured: Shared::new(RangeSet::new()),
+            default_size: None,
+            item_types: BTreeMap::new(),
+            tags: BTreeMap::new(),
+            gap: 0,
+            header_height: 0,
+            footer_height: 0,
+            padding_top: 0,
+            padding_bottom: 0,
+            scale: 1.0,
+            rounding: Rounding::Round,
+            pixel_step: 1.0,
+            type_sizes: BTreeMap::new(),
+            estimation: None,
+            estimate: 0.0,
+            samples: 0,
+            version: 0,
+            size_quantum: 0,
+            prune_distance: None,
+            history: History::new(0),
+            listener: None,
+            keys: Shared::new(KeyRegistry::default()),
+            collapsed: Collapsed::default(),
+            groups: Groups::default(),
+            total_count: None,
+            origin: 0,
+            generations: None,
+            generation: 0,
+            perf: PerfCounters::default(),
+            quantized_inserts: 0,
+            ranges_before_quantization: 0,
+            ranges_after_quantization: 0,
         }
     }
 
-    pub fn update_offset_tree(&mut self, start: u32) {
-        let lte = match start {
-            0 => 0,
-            other => other - 1,
-        };
+    /// A list with room for `expected_ranges` distinct size ranges before
+    /// it has to grow. Items measured one by one take up to two each.
+    pub fn with_capacity(expected_ranges: u32) -> OffsetList {
+        let mut list = OffsetList::new();
+        list.reserve(expected_ranges);
+        list
+    }
+
+    /// A list with the settings in `options`; `OffsetListBuilder` puts them
+    /// together in Rust.
+    pub fn with_options(options: &OffsetListOptions) -> OffsetList {
+        let mut list = OffsetList::with_capacity(options.expected_count);
+        if let Some(size) = options.default_size {
+            list.set_default_size(size);
+        }
+        list.set_rounding(options.rounding, options.device_pixel_ratio);
+        list.set_gap(options.gap);
+        list
+    }
+
+    /// Makes room for `additional` more size ranges.
+    pub fn reserve(&mut self, additional: u32) {
+        Shared::make_mut(self.tree.get_mut()).reserve(additional as usize);
+    }
+
+    /// Keeps the sizes of the first `capacity` items one by one, in a binary
+    /// indexed tree, instead of in ranges. Faster when most items end up
+    /// measured individually; the items past `capacity` share a single size.
+    #[cfg(feature = "fenwick")]
+    pub fn with_dense_sizes(capacity: u32) -> OffsetList {
+        let mut list = OffsetList::new();
+        list.tree = Lock::new(Shared::new(SizeStore::Dense(FenwickTree::new(capacity))));
+        list
+    }
+
+    /// A list of `sizes.len()` measured items, `sizes[i]` large, built in one
+    /// pass instead of one `insert` each. As after those inserts, the items
+    /// past them take the first size.
+    pub fn from_sizes(sizes: &[u32]) -> OffsetList {
+        let mut list = OffsetList::new();
+        if sizes.is_empty() {
+            return list;
+        }
+
+        let mut entries: Vec<(u32, u32)> = Vec::new();
+        for (index, size) in sizes.iter().enumerate() {
+            if entries.last().map(|entry| entry.1) != Some(*size) {
+                entries.push((index as u32, *size));
+            }
+        }
+        if entries.last().map(|entry| entry.1) != Some(sizes[0]) {
+            entries.push((sizes.len() as u32, sizes[0]));
+        }
 
-        let updated = self.size_tree.range(lte..);
+        list.tree = Lock::new(Shared::new(SizeStore::Ranges(SizeTree::from_entries(
+            entries,
+        ))));
+        Shared::make_mut(&mut list.measured).insert(0, sizes.len() as u32 - 1);
+        list
+    }
 
-        let (start_index, start_size) = tree_utils::lte(&self.size_tree, lte);
+    /// A list laid out by `set_groups` in one call.
+    pub fn from_group_counts(group_counts: &[u32], header_size: u32, item_size: u32) -> OffsetList {
+        let mut list = OffsetList::new();
+        list.set_groups(group_counts, header_size, item_size);
+        list
+    }
 
-        let mut prev_offset = match self.offset_tree.get(start_index) {
-            None => 0u32,
-            Some(offset) => *offset,
+    /// A list with the measured ranges `ranges`, flattened into `[start,
+    /// end, size, start, end, size, ...]` as `diff` flattens its ranges, in
+    /// the size ranges they make up without replaying them one by one. The
+    /// items between them take the size of the first, as after inserting
+    /// them in order. The ranges may come in any order, but must not
+    /// overlap.
+    pub fn from_ranges(ranges: &[u32]) -> OffsetList {
+        let mut list = OffsetList::new();
+        let mut triples: Vec<Range> = ranges
+            .chunks_exact(3)
+            .filter(|triple| triple[0] <= triple[1])
+            .map(|triple| Range {
+                start: triple[0],
+                end: triple[1],
+                size: triple[2],
+            })
+            .collect();
+        if triples.is_empty() {
+            return list;
+        }
+        triples.sort_by_key(|range| range.start);
+
+        let fill = triples[0].size;
+        let mut entries: Vec<(u32, u32)> = Vec::with_capacity(triples.len() * 2 + 1);
+        let mut push = |start: u32, size: u32| match entries.last_mut() {
+            Some(last) if last.0 == start => last.1 = size,
+            Some(last) if last.1 == size => {}
+            _ => entries.push((start, size)),
         };
 
-        let mut prev_size = start_size;
-        let mut prev_index = start_index;
-        for (index, size) in updated {
-            let offset = (index - prev_index) * prev_size + prev_offset;
-            self.offset_tree.insert(*index, offset);
-            self.pixel_tree.insert(offset, *index);
-            prev_index = index;
-            prev_offset = offset;
-            prev_size = size;
+        push(0, fill);
+        for (position, range) in triples.iter().enumerate() {
+            push(range.start, range.size);
+            let next = triples.get(position + 1).map(|next| next.start);
+            if range.end < tree_utils::LAST_RANGE_END
+                && next.is_none_or(|next| next > range.end + 1)
+            {
+                push(range.end + 1, fill);
+            }
+            Shared::make_mut(&mut list.measured).insert(range.start, range.end);
         }
+
+        list.tree = Lock::new(Shared::new(SizeStore::Ranges(SizeTree::from_entries(
+            entries,
+        ))));
+        list
+    }
+
+    /// An independent copy of the list, for speculative layouts. Exposed to
+    /// JS as `clone()`. The sizes and measurements are shared until either
+    /// side writes to them.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = clone))]
+    pub fn fork(&self) -> OffsetList {
+        self.clone()
     }
 
-    pub fn remove_index(&mut self, index: &u32) {
-        self.size_tree.remove(index);
-        let pixel = self
-            .offset_tree
-            .remove(index)
-            .expect("offset tree should be in sync!");
+    /// A read-only view of the list as it is now, sharing its sizes; see
+    /// `OffsetListSnapshot`.
+    pub fn snapshot(&self) -> OffsetListSnapshot {
+        OffsetListSnapshot::of(self)
+    }
 
-        self.pixel_tree.remove(&pixel);
+    /// Bumped by every operation that changes the list geometry.
+    pub fn version(&self) -> u32 {
+        self.version
     }
 
-    pub fn insert_spots(&mut self, spots: Vec<u32>, size: u32) {
-        if !self.size_tree.is_empty() {
-            panic!("Trying to insert spots in non-empty size tree.");
+    /// A stable hash of the settings that shape query results.
+    pub fn config_hash(&self) -> u32 {
+        utils::config_hash(&[
+            f64::from(self.size_quantum),
+            self.default_size.map_or(-1.0, f64::from),
+            self.total_count.map_or(-1.0, f64::from),
+            f64::from(self.origin),
+            self.estimation.unwrap_or(-1.0),
+            f64::from(self.gap),
+            f64::from(self.header_height),
+            f64::from(self.footer_height),
+            f64::from(self.padding_top),
+            f64::from(self.padding_bottom),
+            self.scale,
+            self.rounding as u32 as f64,
+            self.pixel_step,
+        ])
+    }
+
+    /// A stable hash of the geometry: the size ranges, the tags and the
+    /// settings in `config_hash`. Two lists with the same checksum lay their
+    /// items out the same way, barring collisions; `equals` is exact.
+    pub fn checksum(&self) -> u32 {
+        let mut values: Vec<f64> = vec![f64::from(self.config_hash())];
+        values.extend(self.size_ranges().into_iter().map(f64::from));
+        values.push(-1.0);
+        for (start, tag) in self.tags.iter() {
+            values.push(f64::from(*start));
+            values.push(f64::from(*tag));
         }
 
-        for spot in spots.iter() {
-            self.size_tree.insert(*spot, size);
-            self.size_tree.insert(spot + 1, 0);
+        utils::config_hash(&values)
+    }
+
+    /// Whether `other` lays its items out exactly like this list. Which of
+    /// the items were measured doesn't matter.
+    pub fn equals(&self, other: &OffsetList) -> bool {
+        self.config_hash() == other.config_hash()
+            && self.tags == other.tags
+            && self.size_ranges() == other.size_ranges()
+    }
+
+    /// The items among the first `count` that `other` positions or sizes
+    /// differently, as inclusive index ranges flattened into
+    /// `[start, end, start, end, ...]`.
+    pub fn diff(&self, other: &OffsetList, count: u32) -> Vec<u32> {
+        let mut starts: Vec<u32> = self.size_ranges();
+        starts.extend(other.size_ranges());
+        let mut starts: Vec<u32> = starts
+            .into_iter()
+            .step_by(2)
+            .chain([0])
+            .filter(|start| *start < count)
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+
+        // within a run of equal sizes in both lists, the items differ if the
+        // first one or the step to the next one does
+        let probes: Vec<u32> = starts
+            .iter()
+            .flat_map(|start| [*start, start.saturating_add(1)])
+            .collect();
+        let (mine, theirs) = (self.offsets_of(&probes), other.offsets_of(&probes));
+
+        let mut result: Vec<u32> = Vec::new();
+        for (position, start) in starts.iter().enumerate() {
+            let end = starts.get(position + 1).map_or(count, |next| *next) - 1;
+            let probe = position * 2;
+            if mine[probe..probe + 2] == theirs[probe..probe + 2] {
+                continue;
+            }
+
+            match result.last_mut() {
+                Some(last) if *last + 1 == *start => *last = end,
+                _ => result.extend([*start, end]),
+            }
         }
 
-        self.update_offset_tree(0);
+        result
     }
 
-    pub fn insert(&mut self, start: u32, end: u32, size: u32) {
-        if self.size_tree.is_empty() {
-            self.size_tree.insert(0, size);
-            self.update_offset_tree(start);
+    /// Registers the key of every item, in index order. Setting the keys again
+    /// after the data was sorted or filtered moves every measurement to the
+    /// new index of its key; the measurements of the keys that are gone, or
+    /// of the items that had no key, are forgotten. Item types and tags stay
+    /// with their indices.
+    pub fn set_keys(&mut self, keys: &[u32]) {
+        let registry = KeyRegistry::new(keys);
+        if self.keys.is_empty() {
+            self.keys = Shared::new(registry);
             return;
         }
 
-        if let Some(0) = self.size_tree.get(&start) {
-            let group_size = self
-                .size_tree
-                .get(&(start - 1))
-                .expect("We must have a group size if zero sized element is present");
-
-            if *group_size == size {
-                self.size_tree = BTreeMap::new();
-                self.size_tree.insert(0, size);
-                self.offset_tree = BTreeMap::new();
-                self.offset_tree.insert(0, 0);
-                return;
-            } else {
-                for (_key, value) in self.size_tree.iter_mut() {
-                    if value == &0 {
-                        *value = size;
-                    }
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.refresh();
+
+        let mut sizes: BTreeMap<u32, u32> = BTreeMap::new();
+        for range in self.measured_sizes() {
+            for index in range.start..=cmp::min(range.end, self.keys.len().saturating_sub(1)) {
+                if let Some(key) = self.keys.key_of(index) {
+                    sizes.entry(key).or_insert(range.size);
                 }
-                self.update_offset_tree(start);
-                return;
             }
         }
 
-        let overlapping_ranges = tree_utils::ranges_within(
-            &self.size_tree,
-            match start {
-                0 => 0,
-                other => other - 1,
+        let mut measured = RangeSet::new();
+        let mut moved: Vec<Range> = Vec::new();
+        for (index, key) in registry.keys().iter().enumerate() {
+            let (index, size) = match sizes.get(key) {
+                None => continue,
+                Some(size) => (index as u32, *size),
+            };
+
+            measured.insert(index, index);
+            match moved.last_mut() {
+                Some(last) if last.end + 1 == index && last.size == size => last.end = index,
+                _ => moved.push(Range {
+                    start: index,
+                    end: index,
+                    size,
+                }),
+            }
+        }
+
+        self.measured = Shared::new(measured);
+        self.keys = Shared::new(registry);
+        self.lay_out(&moved);
+    }
+
+    pub fn key_of(&self, index: u32) -> Option<u32> {
+        self.keys.key_of(index)
+    }
+
+    pub fn index_of_key(&self, key: u32) -> Option<u32> {
+        self.keys.index_of(key)
+    }
+
+    /// The size of the items that were never measured. Without one, the
+    /// first measurement applies to all of them. Turns off size estimation.
+    pub fn set_default_size(&mut self, size: u32) {
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.estimation = None;
+        self.replace_default_size(size);
+    }
+
+    /// Estimates the size of the unmeasured items from the measurements: an
+    /// exponentially weighted moving average with the given `alpha`, or the
+    /// plain average of all measurements when `alpha` is zero. The estimate
+    /// replaces the default size whenever its rounded value changes.
+    pub fn set_size_estimation(&mut self, alpha: f64) {
+        self.version = self.version.wrapping_add(1);
+        self.estimation = Some(alpha.clamp(0.0, 1.0));
+    }
+
+    /// The current estimate, zero before the first measurement.
+    pub fn size_estimate(&self) -> f64 {
+        self.estimate
+    }
+
+    /// The default size of the items of `item_type`. Type 0 stands for the
+    /// untyped items, which use the default size.
+    pub fn set_type_size(&mut self, item_type: u32, size: u32) {
+        if item_type == 0 {
+            return self.set_default_size(size);
+        }
+
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.type_sizes.insert(item_type, size);
+        self.rebuild_sizes();
+    }
+
+    /// Marks `start..=end` as items of `item_type`, so that those of them that
+    /// were never measured get its default size. Without a default size, the
+    /// untyped items take the first size known, as usual.
+    pub fn set_index_type(&mut self, start: u32, end: u32, item_type: u32) {
+        if start > end {
+            return;
+        }
+
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+
+        if self.item_types.is_empty() {
+            self.item_types.insert(0, 0);
+        }
+        tree_utils::assign(&mut self.item_types, start, end, item_type);
+        self.rebuild_sizes();
+    }
+
+    pub fn index_type(&self, index: u32) -> u32 {
+        match self.item_types.range(..=index).next_back() {
+            None => 0,
+            Some((_, item_type)) => *item_type,
+        }
+    }
+
+    pub fn default_size(&self) -> Option<u32> {
+        self.default_size
+    }
+
+    /// `insert` that also attaches `tag` to the range, for render hints.
+    /// The tag comes back on the items of every query; zero means none.
+    pub fn insert_tagged(&mut self, start: u32, end: u32, size: u32, tag: u32) {
+        if start > end {
+            return;
+        }
+
+        self.checkpoint();
+        if self.tags.is_empty() {
+            self.tags.insert(0, 0);
+        }
+        tree_utils::assign(&mut self.tags, start, end, tag);
+        self.write(start, end, size);
+        self.emit(ListEventKind::Insert, start, end);
+    }
+
+    pub fn tag_of(&self, index: u32) -> u32 {
+        match self.tags.range(..=index).next_back() {
+            None => 0,
+            Some((_, tag)) => *tag,
+        }
+    }
+
+    /// Numbers the measurements from here on: each write, and each prune,
+    /// stamps the items it resizes with a new generation, which the query
+    /// results carry along with their keys.
+    pub fn track_generations(&mut self) {
+        if self.generations.is_none() {
+            self.generations = Some(BTreeMap::from([(0, self.generation)]));
+        }
+    }
+
+    /// The generation of the last measurement or prune.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// The generation that last resized `index`, zero without tracking.
+    pub fn generation_of(&self, index: u32) -> u32 {
+        match &self.generations {
+            None => 0,
+            Some(generations) => match generations.range(..=index).next_back() {
+                None => 0,
+                Some((_, generation)) => *generation,
             },
-            end + 1,
-        );
+        }
+    }
+
+    pub fn is_measured(&self, index: u32) -> bool {
+        self.measured.contains(index)
+    }
+
+    /// Hides `start..=end`: its items take no space until `expand` brings
+    /// them back with the sizes they were measured with. The hidden items
+    /// don't count as measured, and `range` skips over them.
+    pub fn collapse(&mut self, start: u32, end: u32) {
+        if start > end {
+            return;
+        }
+
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.refresh();
+
+        for range in self.measured_sizes() {
+            if range.end >= start && range.start <= end {
+                self.collapsed.keep(Range {
+                    start: cmp::max(range.start, start),
+                    end: cmp::min(range.end, end),
+                    size: range.size,
+                });
+            }
+        }
+        Shared::make_mut(&mut self.measured).remove(start, end);
+        self.collapsed.hide(start, end);
+
+        if self.is_sized() {
+            self.collapsed
+                .write_into(Shared::make_mut(self.tree.get_mut()));
+        }
+        self.emit(ListEventKind::Collapse, start, end);
+    }
+
+    /// Shows the collapsed items in `start..=end` again.
+    pub fn expand(&mut self, start: u32, end: u32) {
+        if start > end {
+            return;
+        }
+
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.refresh();
+
+        let mut measured = self.measured_sizes();
+        for range in self.collapsed.show(start, end) {
+            Shared::make_mut(&mut self.measured).insert(range.start, range.end);
+            measured.push(range);
+        }
+        measured.sort_by_key(|range| range.start);
+
+        self.lay_out(&measured);
+        self.emit(ListEventKind::Expand, start, end);
+    }
+
+    pub fn is_collapsed(&self, index: u32) -> bool {
+        self.collapsed.contains(index)
+    }
+
+    /// Lets `prune_measurements` forget the measurements of the items more
+    /// than `distance` items away from the viewport, so that endless feeds
+    /// don't keep one range per item they ever rendered. The forgotten items
+    /// go back to their type size, the default size or the estimate.
+    pub fn set_measurement_pruning(&mut self, distance: u32) {
+        self.prune_distance = Some(distance);
+    }
 
-        // println!("Overlapping ranges! {:?}", overlapping_ranges);
+    pub fn disable_measurement_pruning(&mut self) {
+        self.prune_distance = None;
+    }
 
-        let mut first_pass_done: bool = false;
-        let mut should_insert: bool = false;
+    /// Forgets the measurements out of reach of `first..=last`, usually the
+    /// rendered items, under the pruning policy. Returns the number of items
+    /// forgotten; nothing happens without a policy.
+    pub fn prune_measurements(&mut self, first: u32, last: u32) -> u32 {
+        let distance = match self.prune_distance {
+            None => return 0,
+            Some(distance) => distance,
+        };
+        let stopwatch = Stopwatch::start();
+        let before = self.history.is_enabled().then(|| self.clone());
 
-        for Range {
-            start: range_start,
-            end: range_end,
-            size: range_size,
-        } in overlapping_ranges
+        let mut spans: Vec<(u32, u32)> = Vec::new();
+        match first.checked_sub(distance) {
+            None | Some(0) => {}
+            Some(start) => spans.push((0, start - 1)),
+        }
+        if let Some(end) = last
+            .checked_add(distance)
+            .and_then(|end| end.checked_add(1))
         {
-            // previous range
-            if !first_pass_done {
-                should_insert = range_size != size;
-                first_pass_done = true;
-            } else {
-                // remove the range if it starts within the new range OR if
-                // it has the same value as it, in order to perfrom a merge
-                if end >= range_start || size == range_size {
-                    self.remove_index(&range_start);
-                }
+            spans.push((end, u32::MAX));
+        }
+
+        let mut pruned: u32 = 0;
+        let mut removed: Vec<(u32, u32)> = Vec::new();
+        for (start, end) in spans {
+            let count = Shared::make_mut(&mut self.measured).remove(start, end);
+            if count > 0 {
+                pruned = pruned.saturating_add(count);
+                removed.push((start, end));
+            }
+        }
+
+        if pruned > 0 {
+            trace!(
+                Info,
+                "prune: forgot {} measurements outside {}..={}",
+                pruned,
+                first,
+                last
+            );
+            if let Some(before) = before {
+                self.history.push(before);
+            }
+            self.version = self.version.wrapping_add(1);
+            self.stale.set(true);
+        }
+        for (start, end) in removed {
+            self.stamp(start, end);
+            self.emit(ListEventKind::Remove, start, end);
+        }
+        self.perf.record(Operation::Removal, stopwatch);
+        pruned
+    }
+
+    /// The indices in `first..=last` that fall back to the default size or
+    /// the estimate, because they were never measured.
+    pub fn unmeasured_indices(&self, first: u32, last: u32) -> Vec<u32> {
+        self.measured
+            .gaps(first, last)
+            .into_iter()
+            .flat_map(|(start, end)| start..=end)
+            .collect()
+    }
+
+    /// The combined size of the first `count` items, together with the
+    /// header, the footer and the padding.
+    pub fn total_size(&self, count: u32) -> u32 {
+        let items = match count {
+            0 => self.leading(),
+            count => self.positions_of(&[count])[0].saturating_sub(self.gap),
+        };
+
+        self.scaled(items.saturating_add(self.trailing()))
+    }
+
+    /// The number of items in the list. Once it is known, the range queries
+    /// stop at the last item, and `offset_range` needs no index bounds.
+    pub fn set_total_count(&mut self, count: u32) {
+        self.version = self.version.wrapping_add(1);
+        self.total_count = Some(count);
+    }
+
+    pub fn total_count(&self) -> Option<u32> {
+        self.total_count
+    }
+
+    /// The `aria-setsize` of the items: the total count, or else the number
+    /// of keys, as far as it fits. -1 tells screen readers that it is
+    /// unknown.
+    pub fn aria_set_size(&self) -> i32 {
+        match self.total_count {
+            None if self.keys.is_empty() => -1,
+            None => self.keys.len().min(i32::MAX as u32) as i32,
+            Some(count) => count.min(i32::MAX as u32) as i32,
+        }
+    }
+
+    /// The `aria-posinset` of the item at `index`, counting from one.
+    pub fn aria_pos_in_set(&self, index: u32) -> u32 {
+        index.saturating_add(1)
+    }
+
+    /// `total_size` of all the items, the unmeasured tail included. Zero
+    /// until `set_total_count`.
+    pub fn content_size(&self) -> u32 {
+        self.total_size(self.total_count.unwrap_or(0))
+    }
+
+    /// Space before the first item, taken by a header component in the
+    /// scroller. It shifts every offset.
+    pub fn set_header_height(&mut self, height: u32) {
+        self.version = self.version.wrapping_add(1);
+        self.header_height = height;
+    }
+
+    /// Space after the last item, taken by a footer component.
+    pub fn set_footer_height(&mut self, height: u32) {
+        self.version = self.version.wrapping_add(1);
+        self.footer_height = height;
+    }
+
+    /// Multiplies every size and offset the queries answer with, and divides
+    /// the offsets they take, for browser zoom or a density setting. The
+    /// stored sizes stay as they were inserted.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.version = self.version.wrapping_add(1);
+        self.scale = if scale > 0.0 { scale } else { 1.0 };
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// How scaled offsets are rounded. The whole pixel queries round to
+    /// whole pixels, the `precise_*` ones snap to device pixels, the
+    /// `1 / device_pixel_ratio` grid.
+    pub fn set_rounding(&mut self, rounding: Rounding, device_pixel_ratio: f64) {
+        self.version = self.version.wrapping_add(1);
+        self.rounding = rounding;
+        self.pixel_step = if device_pixel_ratio > 0.0 {
+            1.0 / device_pixel_ratio
+        } else {
+            1.0
+        };
+    }
+
+    /// `offsets_of`, snapped to device pixels instead of whole ones.
+    pub fn precise_offsets_of(&self, indices: &[u32]) -> Vec<f64> {
+        self.positions_of(indices)
+            .into_iter()
+            .map(|position| self.precise(position))
+            .collect()
+    }
+
+    /// The padding of the list element, between the header and the first
+    /// item and between the last item and the footer.
+    pub fn set_padding(&mut self, padding_top: u32, padding_bottom: u32) {
+        self.version = self.version.wrapping_add(1);
+        self.padding_top = padding_top;
+        self.padding_bottom = padding_bottom;
+    }
+
+    /// Space between neighbouring items, included in the offsets but not in
+    /// the item sizes, like CSS `gap`.
+    pub fn set_gap(&mut self, gap: u32) {
+        self.version = self.version.wrapping_add(1);
+        self.gap = gap;
+    }
+
+    pub fn gap(&self) -> u32 {
+        self.gap
+    }
+
+    /// Rounds every inserted size to the nearest multiple of `quantum`, so
+    /// near-identical measurements merge into a single range. Sizes already
+    /// in the list are re-bucketed right away. Zero or one turns it off.
+    pub fn set_size_quantum(&mut self, quantum: u32) {
+        self.size_quantum = quantum;
+        self.version = self.version.wrapping_add(1);
+        self.refresh();
+        let tree = Shared::make_mut(self.tree.get_mut());
+        self.ranges_before_quantization = tree.len() as u32;
+
+        if self.size_quantum > 1 && !tree.is_empty() {
+            tree.map_sizes(|size| utils::quantize(size, quantum));
+        }
+
+        self.ranges_after_quantization = tree.len() as u32;
+    }
+
+    pub fn size_quantum(&self) -> u32 {
+        self.size_quantum
+    }
+
+    pub fn metrics(&self) -> ListMetrics {
+        ListMetrics {
+            range_count: self.sizes().len() as u32,
+            quantized_inserts: self.quantized_inserts,
+            ranges_before_quantization: self.ranges_before_quantization,
+            ranges_after_quantization: self.ranges_after_quantization,
+            size_rebuilds: self.size_rebuilds.get(),
+        }
+    }
+
+    /// How much each internal structure holds, for long-lived lists that
+    /// want to watch their growth.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let sizes = self.sizes();
+        let type_entries = self.item_types.len() + self.type_sizes.len();
+
+        MemoryStats {
+            size_nodes: sizes.node_count() as u32,
+            size_bytes: sizes.allocated_bytes() as u32,
+            measured_ranges: self.measured.len() as u32,
+            measured_bytes: metrics::map_bytes::<u32, u32>(self.measured.len()) as u32,
+            type_entries: type_entries as u32,
+            type_bytes: (metrics::map_bytes::<u32, u32>(self.item_types.len())
+                + metrics::map_bytes::<u32, u32>(self.type_sizes.len()))
+                as u32,
+            tag_entries: self.tags.len() as u32,
+            tag_bytes: metrics::map_bytes::<u32, u32>(self.tags.len()) as u32,
+        }
+    }
+
+    /// The counts and times of the costly operations so far; undo and redo
+    /// don't turn them back.
+    pub fn perf_stats(&self) -> PerfStats {
+        self.perf.stats()
+    }
+
+    pub fn reset_perf_stats(&mut self) {
+        self.perf = PerfCounters::default();
+    }
+
+    /// Merges equal neighbouring sizes, repacks the size tree and drops the
+    /// type and tag maps left with nothing but their zero entry. Returns the
+    /// number of nodes and entries reclaimed; the geometry stays the same.
+    pub fn compact(&mut self) -> u32 {
+        self.refresh();
+        let mut reclaimed = Shared::make_mut(self.tree.get_mut()).compact();
+
+        for map in [&mut self.item_types, &mut self.tags] {
+            if map.len() == 1 && map.get(&0) == Some(&0) {
+                map.clear();
+                reclaimed += 1;
             }
+        }
+
+        reclaimed as u32
+    }
+
+    /// Takes the measurements of `other` into this list. The items that only
+    /// `other` measured get its sizes; for the ones measured in both, `policy`
+    /// decides. Returns the number of items whose size was taken.
+    pub fn merge(&mut self, other: &OffsetList, policy: MergePolicy) -> u32 {
+        let mut writes: Vec<Range> = Vec::new();
+
+        for (start, end) in other.measured.iter() {
+            for (range, _) in other.sizes().ranges(start, end) {
+                let (start, end) = (cmp::max(start, range.start), cmp::min(end, range.end));
+
+                if self.sizes().is_empty() {
+                    writes.push(Range {
+                        start,
+                        end,
+                        size: range.size,
+                    });
+                    continue;
+                }
 
-            // next range
-            if range_end > end && end >= range_start {
-                if range_size != size {
-                    // had an isNaN check here, we can probably use 0 for this special case
-                    self.size_tree.insert(end + 1, range_size);
+                for (own, _) in self.sizes().ranges(start, end) {
+                    let (start, end) = (cmp::max(start, own.start), cmp::min(end, own.end));
+                    let replace = match policy {
+                        MergePolicy::PreferNewer => true,
+                        MergePolicy::PreferLarger => own.size < range.size,
+                    };
+
+                    let parts = match replace {
+                        true => vec![(start, end)],
+                        false => self.measured.gaps(start, end),
+                    };
+                    for (start, end) in parts {
+                        writes.push(Range {
+                            start,
+                            end,
+                            size: range.size,
+                        });
+                    }
                 }
             }
         }
 
-        if should_insert {
-            self.size_tree.insert(start, size);
+        if !writes.is_empty() {
+            self.checkpoint();
+        }
+
+        let mut taken: u32 = 0;
+        for range in writes {
+            taken = taken.saturating_add(range.end - range.start + 1);
+            self.write(range.start, range.end, range.size);
+            self.emit(ListEventKind::Merge, range.start, range.end);
+        }
+        trace!(
+            Info,
+            "merge: took the sizes of {} items, {:?}",
+            taken,
+            policy
+        );
+        taken
+    }
+
+    /// Lays out group headers at `spots`, `size` large. Into a list with
+    /// measurements, as when a new group arrives, the spots take `size` and
+    /// every other item keeps its size.
+    pub fn insert_spots(&mut self, spots: Vec<u32>, size: u32) {
+        let sizes = vec![size; spots.len()];
+        self.insert_sized_spots(spots, sizes);
+    }
+
+    /// `insert_spots` with a size for each spot, `sizes[i]` for `spots[i]`,
+    /// for headers that differ in height. The spots past the end of `sizes`
+    /// are left out.
+    pub fn insert_sized_spots(&mut self, spots: Vec<u32>, sizes: Vec<u32>) {
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+
+        let spots: Vec<(u32, u32)> = spots.into_iter().zip(sizes).collect();
+        for (spot, _) in spots.iter() {
+            Shared::make_mut(&mut self.measured).insert(*spot, *spot);
+        }
+
+        self.refresh();
+        Shared::make_mut(self.tree.get_mut()).insert_spots(&spots);
+        for (spot, _) in spots.iter() {
+            self.emit(ListEventKind::Insert, *spot, *spot);
+        }
+    }
+
+    /// Lays out groups of `group_counts` items, each after a header of its
+    /// own: the headers are `header_size` large and the items `item_size`,
+    /// which becomes the default size. Unlike `insert_spots`, the items are
+    /// sized from the start, and measuring them or the headers later keeps
+    /// the rest as they are.
+    pub fn set_groups(&mut self, group_counts: &[u32], header_size: u32, item_size: u32) {
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.estimation = None;
+        self.groups = Groups::new(group_counts, Some(header_size));
+        self.default_size = Some(item_size);
+        self.rebuild_sizes();
+    }
+
+    /// `set_groups` before any size is known: the items get theirs from the
+    /// first measurement, and the headers from `set_group_header_size`.
+    pub fn set_group_counts(&mut self, group_counts: &[u32]) {
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.groups = Groups::new(group_counts, None);
+        self.rebuild_sizes();
+    }
+
+    /// Sizes every group header, measured or not.
+    pub fn set_group_header_size(&mut self, size: u32) {
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.groups.set_header_size(size);
+        let measured = Shared::make_mut(&mut self.measured);
+        for start in self.groups.starts() {
+            measured.remove(*start, *start);
+        }
+        self.rebuild_sizes();
+    }
+
+    pub fn group_count(&self) -> u32 {
+        self.groups.starts().len() as u32
+    }
+
+    /// The flat index of each group header, from `set_groups`.
+    pub fn group_indices(&self) -> Vec<u32> {
+        self.groups.starts().to_vec()
+    }
+
+    pub fn is_group_header(&self, index: u32) -> bool {
+        self.groups.is_header(index)
+    }
+
+    /// The group of the item at `index`, or of the header there. None before
+    /// `set_groups`.
+    pub fn group_of(&self, index: u32) -> Option<u32> {
+        self.groups.group_of(index)
+    }
+
+    /// The flat index of the header of `group_index`.
+    pub fn group_start(&self, group_index: u32) -> Option<u32> {
+        self.groups.start(group_index)
+    }
+
+    pub fn insert(&mut self, start: u32, end: u32, size: u32) {
+        let _ = self.try_insert(start, end, size);
+    }
+
+    /// `insert`, with an error for a start past the end, which `insert`
+    /// leaves out.
+    pub fn try_insert(&mut self, start: u32, end: u32, size: u32) -> Result<(), ListError> {
+        if start > end {
+            return Err(self.error(
+                ErrorCode::InvalidRange,
+                Some(start),
+                "the start index is past the end index",
+            ));
+        }
+
+        self.checkpoint();
+        self.write(start, end, size);
+        self.emit(ListEventKind::Insert, start, end);
+        Ok(())
+    }
+
+    /// Keeps the state before each of the last `limit` structural operations
+    /// (measurements, spots, types, default sizes, merges and prunes) for
+    /// `undo` and `redo`. The settings around the sizes (the gap, header
+    /// and footer, paddings, scale, rounding, total count and origin) are
+    /// not among them, and stay as they are. Zero turns the history off and
+    /// drops it. Every state keeps the sizes it had alive, so a long history
+    /// of a large list holds several copies of them.
+    pub fn enable_history(&mut self, limit: u32) {
+        self.history.set_limit(limit as usize);
+    }
+
+    /// Goes back to the state before the last recorded operation. Returns
+    /// whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let current = self.clone();
+        match self.history.undo(current) {
+            None => false,
+            Some(state) => {
+                self.restore(state);
+                true
+            }
+        }
+    }
+
+    /// Reapplies the last undone operation, unless another one came since.
+    pub fn redo(&mut self) -> bool {
+        let current = self.clone();
+        match self.history.redo(current) {
+            None => false,
+            Some(state) => {
+                self.restore(state);
+                true
+            }
         }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Calls `callback(kind, start, end)` after every structural change, with
+    /// `kind` one of `"insert"`, `"remove"` or `"merge"` and the affected
+    /// items `start..=end`. For devtools and logging; replaces the previous
+    /// callback. JS functions stay on their thread, so not with `sync`.
+    #[cfg(all(feature = "wasm", not(feature = "sync")))]
+    pub fn on_change(&mut self, callback: js_sys::Function) {
+        self.set_listener(Shared::new(move |event: &ListEvent| {
+            let _ = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from(event.kind.name()),
+                &JsValue::from(event.start),
+                &JsValue::from(event.end),
+            );
+        }));
+    }
+
+    /// Calls `callback(index, size, offset)` for each item of `offset_range`,
+    /// without allocating them, for print and export over many items.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = for_each_in_range)]
+    pub fn for_each_in_range_js(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        callback: &js_sys::Function,
+    ) {
+        self.for_each_in_range(start_offset, end_offset, |item| {
+            let _ = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from(item.index),
+                &JsValue::from(item.size),
+                &JsValue::from(item.offset),
+            );
+        });
+    }
 
-        self.update_offset_tree(start);
+    pub fn remove_listener(&mut self) {
+        self.listener = None;
     }
 
-    pub fn offset_of(self, index: u32) -> u32 {
-        let (size, offset, range_index) = self.range_size_and_offset(index);
+    /// Moves the item at `from` to `to`, shifting the items in between by one,
+    /// as a drag and drop reordering does. The measurement, type, tag and key
+    /// of every item go along with it. Returns by how much the offset of the
+    /// moved item changed; adding it to scrollTop keeps the item where it was
+    /// on the screen.
+    pub fn move_item(&mut self, from: u32, to: u32) -> i32 {
+        let before = self.offset_of(from);
+        if from == to {
+            return 0;
+        }
+
+        self.checkpoint();
+        self.version = self.version.wrapping_add(1);
+        self.refresh();
+
+        let (first, last) = (cmp::min(from, to), cmp::max(from, to));
+        let measured = tree_utils::moved(&self.measured_sizes(), from, to);
+        let mut indices = RangeSet::new();
+        for range in measured.iter() {
+            indices.insert(range.start, range.end);
+        }
+        self.measured = Shared::new(indices);
+
+        for map in [&mut self.item_types, &mut self.tags] {
+            if map.is_empty() {
+                continue;
+            }
+
+            let mut within = tree_utils::ranges_within(map, first, last);
+            for range in within.iter_mut() {
+                range.start = cmp::max(range.start, first);
+                range.end = cmp::min(range.end, last);
+            }
+            for range in tree_utils::moved(&within, from, to) {
+                tree_utils::assign(map, range.start, range.end, range.size);
+            }
+        }
+
+        if last < self.keys.len() {
+            self.keys = Shared::new(self.keys.moved(from, to));
+        }
+
+        self.lay_out(&measured);
+        self.emit(ListEventKind::Move, first, last);
 
-        return (index - range_index) * size + offset;
+        utils::delta(before, self.offset_of(to))
     }
 
-    pub fn total(self, index: u32) -> u32 {
-        let (size, offset, range_index) = self.range_size_and_offset(index);
+    /// Applies a measurement and returns by how much it moved `anchor_index`,
+    /// usually the topmost visible item. Adding the delta to scrollTop keeps
+    /// the anchor visually fixed when items above the viewport change size.
+    pub fn insert_anchored(&mut self, start: u32, end: u32, size: u32, anchor_index: u32) -> i32 {
+        let before = self.offsets_of(&[anchor_index])[0];
+        self.insert(start, end, size);
+        let after = self.offsets_of(&[anchor_index])[0];
+
+        let delta = utils::delta(before, after);
+        trace!(
+            Debug,
+            "anchor: sizing {}..={} moved item {} by {}px",
+            start,
+            end,
+            anchor_index,
+            delta
+        );
+        delta
+    }
+
+    pub fn offset_of(&self, index: u32) -> u32 {
+        let (range_index, size, offset) = self.sizes().range_at(index);
+
+        self.scaled(self.position(index, size.times(index - range_index).plus(offset)))
+    }
+
+    pub fn total(&self, index: u32) -> u32 {
+        let (range_index, size, offset) = self.sizes().range_at(index);
+
+        let end = size.times(index - range_index).plus(size).plus(offset);
+        self.scaled(self.position(index, end))
+    }
+
+    /// Resolves the offsets of many indices with a single walk of the tree,
+    /// or one prefix sum each with the `fenwick` feature. The indices can be
+    /// in any order; the result follows the input order.
+    pub fn offsets_of(&self, indices: &[u32]) -> Vec<u32> {
+        self.positions_of(indices)
+            .into_iter()
+            .map(|position| self.scaled(position))
+            .collect()
+    }
+
+    /// The inverse of `offsets_of`: resolves the index of the item under each
+    /// of the given pixel offsets, sorted once and then in one pass over the
+    /// size tree.
+    pub fn indices_at_offsets(&self, offsets: &[u32]) -> Vec<u32> {
+        let offsets: Vec<u32> = offsets
+            .iter()
+            .map(|offset| self.unscaled(*offset).saturating_sub(self.leading()))
+            .collect();
+
+        self.sizes().indices_at(&offsets, self.gap)
+    }
+
+    pub fn item_at(&self, index: u32) -> Item {
+        let (range_index, size, offset) = self.sizes().range_at(index);
+        let offset = self.position(index, size.times(index - range_index).plus(offset));
+        self.decorate(Item::new(index, size, offset))
+    }
+
+    /// The items from `start_index` to `end_index`, with their sizes but no
+    /// offsets. Nothing, for an empty list, as `range` does.
+    pub fn index_range(&self, start_index: u32, end_index: u32) -> Vec<Item> {
+        self.timed(Operation::RangeQuery, || {
+            let mut result: Vec<Item> = Vec::new();
+
+            for (range, _) in self.sizes().ranges(start_index, end_index) {
+                let start = cmp::max(start_index, range.start);
+                let end = cmp::min(range.end, end_index);
+
+                for index in start..=end {
+                    result.push(self.decorate(Item::new(index, range.size, 0)))
+                }
+            }
+
+            result
+        })
+    }
+
+    /// The items from `min_index` to `max_index` that intersect the pixels
+    /// `start_offset..=end_offset`. Nothing, for an empty list, or where
+    /// `try_range` fails.
+    pub fn range(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+    ) -> Vec<Item> {
+        self.try_range(start_offset, end_offset, min_index, max_index)
+            .unwrap_or_default()
+    }
+
+    /// `range` over all the items, for a list that knows its total count.
+    pub fn offset_range(&self, start_offset: u32, end_offset: u32) -> Vec<Item> {
+        self.range(start_offset, end_offset, 0, u32::MAX)
+    }
+
+    /// `range`, with an error where it comes back empty because the list is,
+    /// because `min_index` is past `max_index`, or because it is past the
+    /// total count. The items stop at the end offset or at `max_index`, clamped to the
+    /// total count; a start offset past them gives the last item.
+    pub fn try_range(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+    ) -> Result<Vec<Item>, ListError> {
+        let mut result: Vec<Item> = Vec::new();
+        self.visit_range(start_offset, end_offset, min_index, max_index, |item| {
+            result.push(item)
+        })?;
+
+        Ok(result)
+    }
+
+    /// `range` into `buffer`, four words an item: its index, size, offset
+    /// and tag. Reusing one buffer from frame to frame saves the allocations.
+    /// Returns the number of items.
+    pub fn range_into_buffer(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+        buffer: &mut ItemBuffer,
+    ) -> usize {
+        buffer.clear();
+        // the queries fail before the first item, if at all
+        let _ = self.visit_range(start_offset, end_offset, min_index, max_index, |item| {
+            buffer.push(&item)
+        });
+
+        buffer.len()
+    }
+
+    /// Same as `index_range`, but returns a single `ItemList` that JS frees once.
+    pub fn index_range_list(&self, start_index: u32, end_index: u32) -> ItemList {
+        ItemList::new(
+            self.index_range(start_index, end_index),
+            self.version,
+            self.config_hash(),
+        )
+    }
+
+    /// Same as `range`, but returns a single `ItemList` that JS frees once.
+    pub fn range_list(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+    ) -> ItemList {
+        ItemList::new(
+            self.range(start_offset, end_offset, min_index, max_index),
+            self.version,
+            self.config_hash(),
+        )
+    }
+}
+
+impl OffsetList {
+    /// `range` into `items`, which is cleared first, so that a buffer kept
+    /// from frame to frame saves the allocations.
+    pub fn range_into(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+        items: &mut Vec<Item>,
+    ) {
+        items.clear();
+        // the queries fail before the first item, if at all
+        let _ = self.visit_range(start_offset, end_offset, min_index, max_index, |item| {
+            items.push(item)
+        });
+    }
+
+    /// Hands the items of `offset_range` to `visit` one at a time, instead
+    /// of collecting them.
+    pub fn for_each_in_range(&self, start_offset: u32, end_offset: u32, visit: impl FnMut(Item)) {
+        // the queries fail before the first item, if at all
+        let _ = self.visit_range(start_offset, end_offset, 0, u32::MAX, visit);
+    }
+
+    /// `on_change` for Rust callers. Copies of the list share the listener.
+    pub fn set_listener(&mut self, listener: ListListener) {
+        self.listener = Some(listener);
+    }
+
+    /// The items of `try_range`, one by one.
+    fn visit_range(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+        mut visit: impl FnMut(Item),
+    ) -> Result<(), ListError> {
+        self.timed(Operation::RangeQuery, || {
+            if min_index > max_index {
+                return Err(self.error(
+                    ErrorCode::InvalidRange,
+                    Some(min_index),
+                    "the min index is past the max index",
+                ));
+            }
+            let max_index = match self.total_count {
+                Some(count) if min_index >= count => {
+                    return Err(self.error(
+                        ErrorCode::IndexOutOfBounds,
+                        Some(min_index),
+                        "the min index is past the total count",
+                    ))
+                }
+                Some(count) => cmp::min(max_index, count - 1),
+                None => max_index,
+            };
+            let leading = self.leading();
+            let start_offset = self.unscaled(start_offset).saturating_sub(leading);
+            let end_offset = self.unscaled(end_offset).saturating_sub(leading);
+
+            if self.sizes().is_empty() {
+                return Err(self.error(ErrorCode::EmptyList, None, "nothing is measured yet"));
+            }
+
+            let gap = self.gap;
+            let (start_range, _, _) = self.sizes().range_at_offset(start_offset, gap);
+            let (end_range, _, _) = self.sizes().range_at_offset(end_offset, gap);
+            // a start past the last item goes back to it
+            let first = cmp::min(start_range, max_index);
+            let last = cmp::min(cmp::max(end_range, first), max_index);
+
+            for (range, range_offset) in self.sizes().ranges(first, last) {
+                let size = range.size;
+                let step = size.plus(gap);
+                let end_index = cmp::min(range.end, max_index);
+                let mut start_index = range.start;
+                // the gaps before the range push it down, as in `position`
+                let mut offset = range_offset.plus(gap.times(range.start));
+
+                if offset < start_offset && step > 0 {
+                    start_index = start_index.saturating_add((start_offset - offset) / step);
+                    start_index = cmp::min(start_index, end_index);
+                    offset = offset.plus(step.times(start_index - range.start));
+                }
+
+                if start_index < min_index {
+                    offset = offset.plus(step.times(min_index - start_index));
+                    start_index = min_index;
+                }
+
+                if start_index > end_index {
+                    continue;
+                }
+
+                if offset > end_offset {
+                    return Ok(());
+                }
+
+                if step == 0 {
+                    let shown = self
+                        .collapsed
+                        .gaps(start_index, cmp::min(range.end, max_index));
+                    match shown.first() {
+                        // collapsed items take no space, the ones after them follow
+                        None if !self.collapsed.is_empty() => continue,
+                        None => {}
+                        Some((shown, _)) => start_index = *shown,
+                    }
+                    visit(self.decorate(Item::new(start_index, 0, offset.plus(leading))));
+
+                    return Ok(());
+                }
+
+                for index in start_index..=end_index {
+                    if offset > end_offset {
+                        return Ok(());
+                    }
+
+                    visit(self.decorate(Item::new(index, size, offset.plus(leading))));
+
+                    // past the largest offset, every item would start there
+                    if offset == u32::MAX {
+                        return Ok(());
+                    }
+                    offset = offset.plus(step);
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    fn error(&self, code: ErrorCode, index: Option<u32>, message: &str) -> ListError {
+        let ranges = self.sizes().ranges(0, tree_utils::LAST_RANGE_END);
+        ListError {
+            code,
+            index,
+            message: String::from(message),
+            tree_summary: error::summarize(&ranges, index.unwrap_or(0)),
+        }
+    }
+
+    fn timed<T>(&self, operation: Operation, run: impl FnOnce() -> T) -> T {
+        let stopwatch = Stopwatch::start();
+        let result = run();
+        self.perf.record(operation, stopwatch);
+        result
+    }
+
+    fn emit(&self, kind: ListEventKind, start: u32, end: u32) {
+        if let Some(listener) = &self.listener {
+            listener(&ListEvent { kind, start, end });
+        }
+    }
+
+    /// Records the state before a structural operation, with the history on.
+    fn checkpoint(&mut self) {
+        if self.history.is_enabled() {
+            let state = self.clone();
+            self.history.push(state);
+        }
+    }
+
+    /// Swaps in a recorded state, keeping the history and moving the version
+    /// forward, so results computed before still read as outdated.
+    fn restore(&mut self, state: OffsetList) {
+        let mut current = core::mem::replace(self, state);
+        self.history = core::mem::replace(&mut current.history, History::new(0));
+        self.listener = current.listener.take();
+        self.perf = core::mem::take(&mut current.perf);
+        self.version = current.version.wrapping_add(1);
+
+        // the settings aren't recorded operations, so they stay as they are
+        self.gap = current.gap;
+        self.header_height = current.header_height;
+        self.footer_height = current.footer_height;
+        self.padding_top = current.padding_top;
+        self.padding_bottom = current.padding_bottom;
+        self.scale = current.scale;
+        self.rounding = current.rounding;
+        self.pixel_step = current.pixel_step;
+        self.total_count = current.total_count;
+        self.origin = current.origin;
+    }
+
+    fn write(&mut self, start: u32, end: u32, size: u32) {
+        let stopwatch = Stopwatch::start();
+        self.version = self.version.wrapping_add(1);
+        Shared::make_mut(&mut self.measured).insert(start, end);
+
+        let size = match self.size_quantum {
+            0 | 1 => size,
+            quantum => {
+                let quantized = utils::quantize(size, quantum);
+                if quantized != size {
+                    self.quantized_inserts += 1;
+                }
+                quantized
+            }
+        };
+
+        self.record_sample(size);
+        self.stamp(start, end);
+        let first = self.tree.get_mut().is_empty();
+        if self.collapsed.is_empty() {
+            // the stale sizes take the write as they are, the rebuild keeps it
+            Shared::make_mut(self.tree.get_mut()).write_range(start, end, size);
+        } else {
+            // the collapsed items keep their measurements aside
+            for (start, end) in self.collapsed.within(start, end) {
+                self.collapsed.keep(Range { start, end, size });
+                Shared::make_mut(&mut self.measured).remove(start, end);
+            }
+            let tree = Shared::make_mut(self.tree.get_mut());
+            for (start, end) in self.collapsed.gaps(start, end) {
+                tree.write_range(start, end, size);
+            }
+            // the first write sizes the whole list, the collapsed items too
+            self.collapsed.write_into(tree);
+        }
+        if first && !self.groups.is_empty() {
+            // and the group headers keep theirs
+            self.rebuild_sizes();
+        }
+        self.perf.record(Operation::Insert, stopwatch);
+    }
+
+    /// Whether the items have any size yet.
+    fn is_sized(&self) -> bool {
+        !self.sizes().is_empty()
+            && (self.measured.len() > 0
+                || self.default_size.is_some()
+                || !self.type_sizes.is_empty())
+    }
+
+    /// The start and size of every size range, flattened.
+    fn size_ranges(&self) -> Vec<u32> {
+        self.sizes()
+            .ranges(0, tree_utils::LAST_RANGE_END)
+            .into_iter()
+            .flat_map(|(range, _)| [range.start, range.size])
+            .collect()
+    }
+
+    fn positions_of(&self, indices: &[u32]) -> Vec<u32> {
+        self.sizes()
+            .offsets_of(indices, self.gap)
+            .into_iter()
+            .map(|raw_offset| self.leading().saturating_add(raw_offset))
+            .collect()
+    }
+
+    fn leading(&self) -> u32 {
+        self.header_height.saturating_add(self.padding_top)
+    }
+
+    fn trailing(&self) -> u32 {
+        self.padding_bottom.saturating_add(self.footer_height)
+    }
+
+    /// Where an item starts, from its offset among the bare item sizes.
+    fn position(&self, index: u32, raw_offset: u32) -> u32 {
+        self.leading().plus(raw_offset).plus(self.gap.times(index))
+    }
+
+    fn scaled(&self, value: u32) -> u32 {
+        if self.scale == 1.0 {
+            return value;
+        }
+
+        self.rounding.to_u32(f64::from(value) * self.scale)
+    }
+
+    fn precise(&self, value: u32) -> f64 {
+        self.rounding
+            .snap(f64::from(value) * self.scale, self.pixel_step)
+    }
+
+    fn unscaled(&self, value: u32) -> u32 {
+        if self.scale == 1.0 {
+            return value;
+        }
+
+        (f64::from(value) / self.scale) as u32
+    }
+
+    /// Scales an item computed from the stored sizes and fills in what the
+    /// list knows about it beyond its geometry.
+    fn decorate(&self, item: Item) -> Item {
+        let tag = self.tag_of(item.index);
+        let offset = self.scaled(item.offset);
+        let end = self.scaled(item.offset.plus(item.size));
+
+        Item::new(item.index, end - offset, offset)
+            .with_tag(tag)
+            .with_gap(self.scaled(self.gap))
+            .with_identity(
+                self.key_of(item.index).unwrap_or(item.index),
+                self.generation_of(item.index),
+            )
+    }
+
+    /// Gives `start..=end` a new generation, while they are tracked.
+    fn stamp(&mut self, start: u32, end: u32) {
+        if let Some(generations) = self.generations.as_mut() {
+            self.generation = self.generation.wrapping_add(1);
+            tree_utils::assign(generations, start, end, self.generation);
+        }
+    }
+
+    fn replace_default_size(&mut self, size: u32) {
+        self.default_size = Some(size);
+        self.stale.set(true);
+    }
+
+    /// The sizes, brought up to date with the default size first.
+    fn sizes(&self) -> ReadGuard<'_, Shared<SizeStore>> {
+        self.refresh();
+        self.tree.borrow()
+    }
+
+    /// Rebuilds the sizes if the default size changed. The flag is checked
+    /// again and cleared under the write lock, so with `sync` a concurrent
+    /// reader either waits for the rebuild or sees it done, and only one
+    /// of them does it.
+    fn refresh(&self) {
+        if self.stale.get() {
+            let mut shared = self.tree.borrow_mut();
+            if self.stale.get() {
+                let tree = Shared::make_mut(&mut shared);
+                let measured = self.measured_in(tree);
+                self.lay_out_into(tree, &measured);
+                self.stale.set(false);
+            }
+        }
+    }
+
+    /// Keeps every measured range and resets the rest to the size of its
+    /// type, or to the default size.
+    fn rebuild_sizes(&self) {
+        let measured = self.measured_sizes();
+        self.lay_out(&measured);
+    }
+
+    /// The sizes of the measured items, as ranges.
+    fn measured_sizes(&self) -> Vec<Range> {
+        self.measured_in(&self.tree.borrow())
+    }
+
+    fn measured_in(&self, tree: &SizeStore) -> Vec<Range> {
+        let mut measured: Vec<Range> = Vec::new();
+        for (start, end) in self.measured.iter() {
+            for (range, _) in tree.ranges(start, end) {
+                measured.push(Range {
+                    start: cmp::max(start, range.start),
+                    end: cmp::min(end, range.end),
+                    size: range.size,
+                });
+            }
+        }
+
+        measured
+    }
+
+    /// Lays out the sizes from scratch: the default size, the type sizes and
+    /// the `measured` ranges over them.
+    fn lay_out(&self, measured: &[Range]) {
+        let mut shared = self.tree.borrow_mut();
+        self.lay_out_into(Shared::make_mut(&mut shared), measured);
+        self.stale.set(false);
+    }
+
+    fn lay_out_into(&self, tree: &mut SizeStore, measured: &[Range]) {
+        let stopwatch = Stopwatch::start();
+        self.size_rebuilds.bump();
+
+        tree.clear();
+        if let Some(size) = self.default_size {
+            tree.reset(size);
+        }
+
+        let mut typed: Vec<Range> = Vec::new();
+        if !self.item_types.is_empty() {
+            let last = tree_utils::LAST_RANGE_END;
+            for range in tree_utils::ranges_within(&self.item_types, 0, last) {
+                if let Some(size) = self.type_sizes.get(&range.size) {
+                    typed.push(Range {
+                        start: range.start,
+                        end: cmp::min(range.end, last - 1),
+                        size: *size,
+                    });
+                }
+            }
+        }
+
+        // without a default size, the first measurement sizes the untyped
+        // items, so it goes in before the types
+        if self.default_size.is_none() {
+            if let Some(first) = measured.first() {
+                tree.write_range(first.start, first.end, first.size);
+            }
+        }
+
+        for range in typed.iter() {
+            tree.write_range(range.start, range.end, range.size);
+        }
+        if !self.groups.is_empty() && !tree.is_empty() {
+            self.groups.write_into(tree);
+        }
+        for range in measured.iter() {
+            tree.write_range(range.start, range.end, range.size);
+        }
+        trace!(
+            Debug,
+            "rebuild: {} measured and {} typed ranges over the default size {:?}",
+            measured.len(),
+            typed.len(),
+            self.default_size
+        );
+
+        if !tree.is_empty() {
+            self.collapsed.write_into(tree);
+        }
+        self.perf.record(Operation::Rebuild, stopwatch);
+    }
+
+    fn record_sample(&mut self, size: u32) {
+        let alpha = match self.estimation {
+            None => return,
+            Some(alpha) => alpha,
+        };
+
+        self.samples = self.samples.saturating_add(1);
+        let weight = if self.samples == 1 {
+            1.0
+        } else if alpha == 0.0 {
+            1.0 / f64::from(self.samples)
+        } else {
+            alpha
+        };
+        self.estimate += (f64::from(size) - self.estimate) * weight;
+
+        let rounded = self.estimate.round() as u32;
+        if self.default_size != Some(rounded) {
+            self.replace_default_size(rounded);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes().is_empty()
+    }
+
+    /// The first and last of `count` items intersecting the pixels
+    /// `start_offset..end_offset`. Before anything is measured, that is the
+    /// first item alone.
+    pub(crate) fn visible_span(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        count: u32,
+    ) -> Option<(u32, u32)> {
+        if count == 0 {
+            return None;
+        }
+
+        if self.sizes().is_empty() {
+            return Some((0, 0));
+        }
+
+        let last_index = count - 1;
+        let indices = self.indices_at_offsets(&[start_offset, end_offset.saturating_sub(1)]);
+
+        let first = indices[0].min(last_index);
+        let last = indices[1].min(last_index).max(first);
+
+        Some((first, last))
+    }
+
+    /// The items `first..=last`, with their sizes and offsets.
+    pub fn items(&self, first: u32, last: u32) -> Vec<Item> {
+        self.timed(Operation::RangeQuery, || {
+            let mut result: Vec<Item> = Vec::new();
+
+            for (range, range_offset) in self.sizes().ranges(first, last) {
+                let start = cmp::max(first, range.start);
+                let end = cmp::min(range.end, last);
+
+                for index in start..=end {
+                    let offset = self.position(
+                        index,
+                        range.size.times(index - range.start).plus(range_offset),
+                    );
+                    result.push(self.decorate(Item::new(index, range.size, offset)));
+                }
+            }
+
+            result
+        })
+    }
+}
+
+impl Default for OffsetList {
+    fn default() -> Self {
+        OffsetList::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cells::Shared;
+    use super::items::live_items;
+    use super::MergePolicy;
+    use super::OffsetList;
+    use super::Rounding;
+    use super::{Item, ItemBuffer};
+    use super::{ListEvent, ListEventKind};
+    use super::{OffsetListBuilder, OffsetListOptions};
+    use std::sync::{Arc, Mutex};
+    #[test]
+    fn test_initial_offset_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.2)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [0]);
+        assert_eq!(keys, [0]);
+    }
+
+    #[test]
+    fn test_second_offset_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(3, 7, 20);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.2)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 3, 8]);
+        assert_eq!(values, [0, 30, 130]);
+    }
+
+    #[test]
+    fn test_in_between_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(9, 10, 2);
+        list.insert(3, 7, 3);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.2)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 3, 8, 9, 11]);
+        assert_eq!(values, [0, 3, 18, 19, 23]);
+    }
+
+    #[test]
+    fn test_overlap_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(3, 7, 2);
+        list.insert(2, 9, 3);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.2)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 2, 10]);
+        assert_eq!(values, [0, 2, 26]);
+    }
+
+    #[test]
+    fn test_initial_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [10]);
+        assert_eq!(keys, [0]);
+    }
+
+    #[test]
+    fn test_same_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(1, 1, 10);
+        list.insert(20, 21, 10);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [10]);
+        assert_eq!(keys, [0]);
+    }
+
+    #[test]
+    fn re_insert_at_start() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 5);
+        list.insert(0, 0, 10);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [10, 5]);
+        assert_eq!(keys, [0, 1]);
+    }
+
+    #[test]
+    fn test_new_insert() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(3, 5, 20);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [10, 20, 10]);
+        assert_eq!(keys, [0, 3, 6]);
+    }
+
+    #[test]
+    fn test_join_start() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(3, 5, 20);
+        list.insert(5, 7, 20);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [10, 20, 10]);
+        assert_eq!(keys, [0, 3, 8]);
+    }
+
+    #[test]
+    fn test_join_end() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(5, 7, 20);
+        list.insert(3, 5, 20);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(values, [10, 20, 10]);
+        assert_eq!(keys, [0, 3, 8]);
+    }
+
+    #[test]
+    fn test_override() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(5, 7, 20);
+        list.insert(4, 7, 30);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 4, 8]);
+        assert_eq!(values, [10, 30, 10]);
+    }
+
+    #[test]
+    fn test_join_override() {
+        let mut list: OffsetList = OffsetList::new();
+
+        list.insert(0, 0, 5);
+        list.insert(4, 5, 10);
+        list.insert(6, 7, 20);
+        list.insert(3, 8, 5);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0]);
+        assert_eq!(values, [5]);
+    }
+
+    #[test]
+    fn test_insert_sports() {
+        let mut list: OffsetList = OffsetList::new();
+
+        list.insert_spots(vec![0, 10, 20], 5);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 1, 10, 11, 20, 21]);
+        assert_eq!(values, [5, 0, 5, 0, 5, 0]);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.2)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 1, 10, 11, 20, 21]);
+        assert_eq!(values, [0, 5, 5, 10, 10, 15]);
+    }
+
+    #[test]
+    fn test_insert_size_after_spot() {
+        let mut list: OffsetList = OffsetList::new();
+
+        list.insert_spots(vec![0, 10, 20], 5);
+        list.insert(1, 5, 10);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        assert_eq!(keys, [0, 1, 10, 11, 20, 21]);
+        assert_eq!(values, [5, 10, 5, 10, 5, 10]);
+    }
+
+    #[test]
+    fn test_insert_sized_spots() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert_sized_spots(vec![0, 10, 20], vec![30, 5, 5]);
+        list.insert(1, 1, 10);
+        let entries: Vec<(u32, u32)> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| (entry.0, entry.1))
+            .collect();
+        assert_eq!(
+            entries,
+            [(0, 30), (1, 10), (10, 5), (11, 10), (20, 5), (21, 10)]
+        );
+        assert_eq!(list.offset_of(10), 120);
+
+        // a spot without a size is left out
+        list.insert_sized_spots(vec![5, 6], vec![20]);
+        assert_eq!(list.offset_of(10), 130);
+    }
+
+    #[test]
+    fn test_insert_spots_into_a_sized_list() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert_spots(vec![0, 10], 5);
+        // a header right after another one ends its empty group
+        list.insert_spots(vec![11], 5);
+        let entries: Vec<(u32, u32)> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| (entry.0, entry.1))
+            .collect();
+        assert_eq!(entries, [(0, 5), (1, 0), (10, 5), (12, 0)]);
+
+        list.insert(1, 5, 10);
+        list.insert_spots(vec![15, 30], 8);
+        let entries: Vec<(u32, u32)> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| (entry.0, entry.1))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (0, 5),
+                (1, 10),
+                (10, 5),
+                (12, 10),
+                (15, 8),
+                (16, 10),
+                (30, 8),
+                (31, 10)
+            ]
+        );
+        assert!(list.is_measured(30));
+    }
+
+    #[test]
+    fn test_set_group_counts() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_group_counts(&[2, 3]);
+        assert!(list.is_empty());
+        assert_eq!(list.group_start(1), Some(3));
+        assert_eq!(list.group_start(2), None);
+
+        // the first measurement sizes the items, not the headers
+        list.set_group_header_size(10);
+        list.insert(1, 1, 30);
+        assert_eq!(list.offsets_of(&[1, 3, 4]), [10, 70, 80]);
+
+        list.insert(3, 3, 25);
+        list.set_group_header_size(15);
+        assert_eq!(list.offsets_of(&[1, 3, 4]), [15, 75, 90]);
+        assert!(!list.is_measured(3));
+
+        let copy = OffsetList::from_transferable(&list.to_transferable()).unwrap();
+        assert!(copy.equals(&list));
+    }
+
+    #[test]
+    fn test_set_groups() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_groups(&[2, 3], 30, 10);
+        assert_eq!(list.group_indices(), [0, 3]);
+        assert_eq!(list.offsets_of(&[1, 2, 3, 4, 7]), [30, 40, 50, 80, 110]);
+
+        let sizes: Vec<u32> = list
+            .range(0, 60, 0, 10)
+            .iter()
+            .map(|item| item.size)
+            .collect();
+        assert_eq!(sizes, [30, 10, 10, 30]);
+
+        // measurements keep the sizes around them
+        list.insert(3, 3, 40);
+        list.insert(5, 5, 20);
+        assert_eq!(list.offsets_of(&[4, 6, 7]), [90, 120, 130]);
+
+        assert!(list.is_group_header(3));
+        assert!(!list.is_group_header(4));
+        assert_eq!(list.group_of(2), Some(0));
+        assert_eq!(list.group_of(3), Some(1));
+        assert_eq!(list.group_of(100), Some(1));
+
+        let copy = OffsetList::from_transferable(&list.to_transferable()).unwrap();
+        assert_eq!(copy.group_indices(), [0, 3]);
+        assert!(copy.equals(&list));
+    }
+
+    #[test]
+    fn test_from_sizes() {
+        let list = OffsetList::from_sizes(&[10, 10, 20, 5, 5, 5]);
+        assert_eq!(list.offsets_of(&[1, 2, 3, 6, 8]), [10, 20, 40, 55, 75]);
+        assert_eq!(list.tree.borrow().len(), 4);
+        assert!(list.is_measured(5));
+        assert!(!list.is_measured(6));
+
+        let mut built = OffsetList::new();
+        for (index, size) in [10, 10, 20, 5, 5, 5].iter().enumerate() {
+            built.insert(index as u32, index as u32, *size);
+        }
+        assert!(built.equals(&list));
+        assert!(OffsetList::from_sizes(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_from_ranges() {
+        let list = OffsetList::from_ranges(&[0, 1, 10, 5, 6, 20, 7, 7, 10, 2, 2, 30]);
+        let mut built = OffsetList::new();
+        built.insert(0, 1, 10);
+        built.insert(2, 2, 30);
+        built.insert(5, 6, 20);
+        built.insert(7, 7, 10);
+
+        assert!(built.equals(&list));
+        assert_eq!(list.offsets_of(&[3, 5, 7, 9]), [50, 70, 110, 130]);
+        assert!(list.is_measured(6));
+        assert!(!list.is_measured(4));
+        assert!(OffsetList::from_ranges(&[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn test_from_group_counts() {
+        let list = OffsetList::from_group_counts(&[1, 0, 2], 20, 10);
+        assert_eq!(list.group_indices(), [0, 2, 3]);
+        assert_eq!(list.offsets_of(&[1, 2, 3, 4, 6]), [20, 30, 50, 70, 90]);
+    }
+
+    #[test]
+    fn test_with_options() {
+        let list = OffsetListBuilder::new()
+            .default_size(10)
+            .gap(4)
+            .rounding(Rounding::Floor, 2.0)
+            .build();
+        assert_eq!(list.offsets_of(&[3]), [42]);
+        assert_eq!(list.default_size(), Some(10));
+        assert_eq!(list.gap(), 4);
+        assert_eq!(OffsetList::with_options(&OffsetListOptions::new()).gap(), 0);
+    }
+
+    #[test]
+    fn test_total_count() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        for index in 1..10 {
+            list.insert(index * 2, index * 2, 20);
+        }
+        list.set_total_count(25);
+        assert_eq!(list.content_size(), 340);
+
+        // past the start of the last range, up to the last item
+        let indices: Vec<u32> = list
+            .offset_range(250, 1000)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(indices, (17..25).collect::<Vec<u32>>());
+        assert_eq!(list.range(0, 1000, 22, 100).len(), 3);
+        let last: Vec<u32> = list
+            .offset_range(2000, 3000)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(last, [24]);
+
+        list.set_total_count(0);
+        assert!(list.offset_range(0, 100).is_empty());
+        assert_eq!(list.content_size(), 0);
+    }
+
+    #[test]
+    fn test_range_into() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 3, 20);
+
+        let mut items: Vec<Item> = Vec::new();
+        list.range_into(0, 40, 0, 10, &mut items);
+        let expected: Vec<u32> = list
+            .range(0, 40, 0, 10)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(
+            items.iter().map(|item| item.index).collect::<Vec<u32>>(),
+            expected
+        );
+
+        list.range_into(30, 50, 0, 10, &mut items);
+        assert_eq!(
+            items.iter().map(|item| item.index).collect::<Vec<u32>>(),
+            [2, 3]
+        );
+
+        let mut buffer = ItemBuffer::new();
+        assert_eq!(list.range_into_buffer(30, 50, 0, 10, &mut buffer), 2);
+        assert_eq!(buffer.words(), [2, 20, 20, 0, 3, 20, 40, 0]);
+        assert_eq!(list.range_into_buffer(0, 5, 0, 10, &mut buffer), 1);
+        assert_eq!(buffer.words(), [0, 10, 0, 0]);
+    }
+
+    #[test]
+    fn test_for_each_in_range() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 3, 20);
+        list.set_total_count(6);
+
+        let mut visited: Vec<(u32, u32)> = Vec::new();
+        list.for_each_in_range(15, 1000, |item| visited.push((item.index, item.offset)));
+        assert_eq!(visited, [(1, 10), (2, 20), (3, 40), (4, 60), (5, 70)]);
+    }
+
+    #[test]
+    fn test_empty_list_queries() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_gap(2);
+        assert!(list.range(0, 100, 0, 10).is_empty());
+        assert!(list.offset_range(0, 100).is_empty());
+        assert!(list.index_range(0, 10).is_empty());
+
+        let item = list.item_at(3);
+        assert_eq!((item.size, item.offset), (0, 6));
+
+        let mut items: Vec<Item> = Vec::new();
+        list.range_into(0, 100, 0, 10, &mut items);
+        assert!(items.is_empty());
+        list.for_each_in_range(0, 100, |_| panic!("no items to visit"));
+
+        // the items before the first spot take no space
+        list.insert_spots(vec![5], 10);
+        let item = list.item_at(2);
+        assert_eq!((item.size, item.offset), (0, 4));
+        assert_eq!(list.item_at(5).size, 10);
+    }
+
+    #[test]
+    fn test_end_offset_past_the_content() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(5, 5, 20);
+        let indices = |items: Vec<Item>| items.iter().map(|item| item.index).collect::<Vec<u32>>();
+
+        // the last range starts at 70
+        assert_eq!(indices(list.range(60, 70, 0, 9)), [5, 6]);
+        assert_eq!(indices(list.range(60, 75, 0, 9)), [5, 6]);
+        assert_eq!(indices(list.range(60, 10_000, 0, 9)), [5, 6, 7, 8, 9]);
+        assert_eq!(indices(list.range(200, 10_000, 0, 9)), [9]);
+        assert_eq!(list.try_range(60, 10_000, 0, u32::MAX).unwrap().len(), 995);
+        list.set_total_count(8);
+        assert_eq!(indices(list.range(60, 10_000, 0, u32::MAX)), [5, 6, 7]);
+
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        assert_eq!(list.try_range(0, 100, 0, u32::MAX).unwrap().len(), 11);
+        assert_eq!(list.offset_range(0, 100).len(), 11);
+        // a zero sized item past the end offset doesn't show either
+        list.insert(3, 3, 0);
+        assert_eq!(indices(list.range(0, 15, 0, 9)), [0, 1]);
+    }
+
+    #[test]
+    fn test_adversarial_inputs() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(5, 2, 10);
+        assert!(list.is_empty());
+
+        list.insert(0, 0, u32::MAX);
+        assert_eq!(list.offsets_of(&[0, 1, 1000]), [0, u32::MAX, u32::MAX]);
+        assert_eq!(list.total_size(10), u32::MAX);
+        assert_eq!(list.range(0, u32::MAX, 0, 100).len(), 2);
+        assert_eq!(list.indices_at_offsets(&[u32::MAX]), [1]);
+
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, u32::MAX / 2);
+        let offsets: Vec<u32> = list.items(0, 4).iter().map(|item| item.offset).collect();
+        assert_eq!(offsets, [0, u32::MAX / 2, u32::MAX - 1, u32::MAX, u32::MAX]);
+
+        let mut list: OffsetList = OffsetList::new();
+        list.insert_spots(vec![u32::MAX], 10);
+        // the first size after the spots leaves the spot as it is
+        list.insert(u32::MAX - 1, u32::MAX, 20);
+        assert_eq!(list.item_at(u32::MAX).size, 10);
+
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(10);
+        list.set_gap(u32::MAX);
+        assert_eq!(list.offsets_of(&[0, 2]), [0, u32::MAX]);
+
+        list.set_gap(0);
+        assert_eq!(list.insert_anchored(0, 0, u32::MAX, 5), i32::MAX);
+    }
+
+    #[test]
+    fn test_item_identity() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_keys(&[100, 101, 102, 103]);
+        list.track_generations();
+        list.insert(0, 3, 10);
+        let identity = |list: &OffsetList| -> Vec<(u32, u32, u32)> {
+            list.range(0, 25, 0, 3)
+                .iter()
+                .map(|item| (item.key, item.generation, item.offset))
+                .collect()
+        };
+        assert_eq!(identity(&list), [(100, 1, 0), (101, 1, 10), (102, 1, 20)]);
+
+        // item 2 only moves
+        list.insert(1, 1, 5);
+        assert_eq!(
+            identity(&list),
+            [(100, 1, 0), (101, 2, 10), (102, 1, 15), (103, 1, 25)]
+        );
+        assert_eq!(list.generation(), 2);
+
+        // without keys or tracking, the index and zero
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        assert_eq!(identity(&list)[2], (2, 0, 20));
+    }
+
+    #[test]
+    fn test_aria_attributes() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        assert_eq!(list.aria_set_size(), -1);
+        assert_eq!(list.aria_pos_in_set(0), 1);
+
+        list.set_keys(&[7, 8, 9]);
+        assert_eq!(list.aria_set_size(), 3);
+        list.set_total_count(1000);
+        assert_eq!(list.aria_set_size(), 1000);
+
+        let items = list.range_list(15, 30, 0, 999);
+        assert_eq!(items.positions_in_set(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_insert_anchored() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+
+        // growing items above the anchor pushes it down
+        assert_eq!(list.insert_anchored(2, 4, 30, 6), 60);
+        // shrinking them pulls it back up
+        assert_eq!(list.insert_anchored(3, 3, 10, 6), -20);
+        // changes at or below the anchor don't move it
+        assert_eq!(list.insert_anchored(6, 9, 50, 6), 0);
+        assert_eq!(list.offset_of(6), 100);
+    }
+
+    #[test]
+    fn test_size_quantum_merges_ranges() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_size_quantum(4);
+        list.insert(0, 0, 21);
+        list.insert(1, 1, 19);
+        list.insert(2, 2, 18);
+
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        assert_eq!(values, [20]);
+        assert_eq!(list.metrics().range_count, 1);
+        assert_eq!(list.metrics().quantized_inserts, 3);
+    }
+
+    #[test]
+    fn test_size_quantum_rebuckets_existing_sizes() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 20);
+        list.insert(1, 1, 21);
+        list.insert(2, 2, 19);
+        list.insert(3, 3, 30);
+        assert_eq!(list.metrics().range_count, 5);
+
+        let hash = list.config_hash();
+        list.set_size_quantum(4);
+        assert_ne!(list.config_hash(), hash);
+
+        let keys: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.0)
+            .collect();
+        let values: Vec<u32> = list
+            .tree
+            .borrow()
+            .entries()
+            .iter()
+            .map(|entry| entry.1)
+            .collect();
+        assert_eq!(keys, [0, 3, 4]);
+        assert_eq!(values, [20, 32, 20]);
+        assert_eq!(list.offset_of(4), 92);
+
+        let metrics = list.metrics();
+        assert_eq!(metrics.ranges_before_quantization, 5);
+        assert_eq!(metrics.ranges_after_quantization, 3);
+    }
+
+    #[test]
+    fn test_offset_of() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
+
+        assert_eq!(list.offset_of(7), 10);
+    }
+
+    #[test]
+    fn test_total() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
+
+        assert_eq!(list.total(7), 11);
+    }
+
+    #[test]
+    fn test_offsets_of() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
+
+        assert_eq!(list.offsets_of(&[7, 0, 3, 2, 7, 5]), [10, 0, 4, 2, 10, 8]);
+    }
+
+    #[test]
+    fn test_offsets_of_empty() {
+        let list: OffsetList = OffsetList::new();
+
+        assert_eq!(list.offsets_of(&[3, 1]), [0, 0]);
+        assert_eq!(list.offsets_of(&[]), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_indices_at_offsets() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
+
+        assert_eq!(
+            list.indices_at_offsets(&[10, 0, 3, 8, 9, 2]),
+            [7, 0, 2, 5, 6, 2]
+        );
+    }
+
+    #[test]
+    fn test_indices_at_offsets_with_spots() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert_spots(vec![0, 10, 20], 5);
+
+        assert_eq!(list.indices_at_offsets(&[3, 7, 12, 100]), [0, 10, 20, 21]);
+    }
+
+    #[test]
+    fn test_indices_at_offsets_empty() {
+        let list: OffsetList = OffsetList::new();
+
+        assert_eq!(list.indices_at_offsets(&[3, 1]), [0, 0]);
+    }
+
+    #[test]
+    fn test_item_at() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
+
+        let item = list.item_at(10);
+        assert_eq!(item.size, 1);
+        assert_eq!(item.offset, 13);
+        assert_eq!(item.index, 10);
+    }
+
+    #[test]
+    fn test_index_range() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
+
+        let items: Vec<Item> = list.index_range(3, 6);
+        assert_eq!(items.len(), 4);
+
+        let item = items.first().expect("Item 0 is here");
+        assert_eq!(item.index, 3);
+        assert_eq!(item.size, 2);
+
+        let item = items.get(3).expect("Item 0 is here");
+        assert_eq!(item.index, 6);
+        assert_eq!(item.size, 1);
+    }
+
+    #[test]
+    fn test_range_list() {
+        let items_before = live_items();
+
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 4, 20);
 
-        return (index - range_index + 1) * size + offset;
-    }
+        let items = list.range_list(13, 79, 0, u32::MAX);
+        assert_eq!(items.indices(), [1, 2, 3, 4]);
+        assert_eq!(items.offsets(), [10, 20, 40, 60]);
+        assert_eq!(live_items(), items_before + 4);
 
-    pub fn item_at(self, index: u32) -> Item {
-        let (size, offset, range_index) = self.range_size_and_offset(index);
-        return Item {
-            index: index,
-            size: *size,
-            offset: (index - range_index) * size + offset,
-        };
+        drop(items);
+        assert_eq!(live_items(), items_before);
     }
 
-    pub fn index_range(&self, start_index: u32, end_index: u32) -> Vec<Item> {
-        if self.size_tree.is_empty() {
-            return vec![Item {
-                index: 0,
-                size: 0,
-                offset: 0,
-            }];
-        }
-
-        let ranges = tree_utils::ranges_within(&self.size_tree, start_index, end_index);
-        let mut result: Vec<Item> = Vec::new();
-
-        for range in ranges {
-            let start = std::cmp::max(start_index, range.start);
-            let end = std::cmp::min(range.end, end_index);
+    #[test]
+    fn test_results_are_stamped() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
 
-            for index in start..=end {
-                result.push(Item {
-                    index,
-                    size: range.size,
-                    offset: 0,
-                })
-            }
-        }
+        let items = list.index_range_list(0, 2);
+        assert_eq!(items.version(), list.version());
+        assert_eq!(items.config_hash(), list.config_hash());
+        assert!(items.is_current(&list));
 
-        return result;
+        list.insert(1, 1, 20);
+        assert!(!items.is_current(&list));
     }
 
-    pub fn range(
-        &self,
-        start_offset: u32,
-        end_offset: u32,
-        min_index: u32,
-        max_index: u32,
-    ) -> Vec<Item> {
-        let (_, start_index) = tree_utils::lte(&self.pixel_tree, start_offset);
+    #[test]
+    fn test_queries_do_not_consume_the_list() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 1);
+        list.insert(2, 4, 2);
 
-        let (_, end_index) = self
-            .pixel_tree
-            .range(end_offset..)
-            .next()
-            .expect("we should find such end index");
+        assert_eq!(list.offset_of(7), 10);
+        assert_eq!(list.total(7), 11);
+        assert_eq!(list.item_at(7).offset, 10);
+    }
 
-        let mut result: Vec<Item> = Vec::new();
+    #[test]
+    fn test_range() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 4, 20);
 
-        for range in tree_utils::ranges_within(&self.offset_tree, *start_index, *end_index) {
-            let mut offset = range.size;
-            let mut start_index = range.start;
-            let size = self
-                .size_tree
-                .get(&start_index)
-                .expect("tree should be in sync");
-
-            if range.size < start_offset {
-                start_index += (start_offset - range.size) as u32 / size;
-                offset += (start_index - range.start) * size;
-            }
+        let items: Vec<Item> = list.range(13, 79, 0, u32::MAX);
+        assert_eq!(items.len(), 4);
 
-            if start_index < min_index {
-                offset += (min_index - start_index) * size;
-                start_index = min_index;
-            }
+        let item = items.first().expect("Item is here");
+        assert_eq!(item.index, 1);
+        assert_eq!(item.size, 10);
+        assert_eq!(item.offset, 10);
 
-            if size == &0u32 {
-                result.push(Item {
-                    index: start_index,
-                    size: 0,
-                    offset,
-                });
+        let item = items.get(1).expect("Item is here");
+        assert_eq!(item.index, 2);
+        assert_eq!(item.size, 20);
+        assert_eq!(item.offset, 20);
 
-                return result;
-            }
+        let item = items.get(2).expect("Item is here");
+        assert_eq!(item.index, 3);
+        assert_eq!(item.size, 20);
+        assert_eq!(item.offset, 40);
 
-            let end_index = cmp::min(range.end, max_index);
+        let item = items.get(3).expect("Item is here");
+        assert_eq!(item.index, 4);
+        assert_eq!(item.size, 20);
+        assert_eq!(item.offset, 60);
+    }
 
-            for index in start_index..=end_index {
-                if offset > end_offset {
-                    break;
-                }
+    #[test]
+    fn test_range_after_a_merge() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(5, 5, 20);
+        // merges the ranges back, nothing starts at 50 or 70 any more
+        list.insert(5, 5, 10);
+        list.insert(100, 100, 30);
+
+        let items: Vec<u32> = list
+            .range(60, 95, 0, u32::MAX)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(items, [6, 7, 8, 9]);
+    }
 
-                result.push(Item {
-                    index: index,
-                    size: *size,
-                    offset: offset,
-                });
+    #[test]
+    fn test_range_matches_the_offsets() {
+        let mut list: OffsetList = OffsetList::new();
+        let mut seed: u32 = 3;
 
-                offset += size;
-            }
+        list.insert(0, 0, 10);
+        list.insert(10_000, 10_000, 7);
+        for _ in 0..300 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let start = (seed >> 8) % 400;
+            let end = start + (seed >> 4) % 20;
+            list.insert(start, end, 5 + (seed >> 20) % 4 * 5);
+
+            let start_offset = (seed >> 12) % 3000;
+            let end_offset = start_offset + 200;
+            let indices = list.indices_at_offsets(&[start_offset, end_offset]);
+
+            let expected: Vec<(u32, u32)> = list
+                .items(indices[0], indices[1])
+                .iter()
+                .map(|item| (item.index, item.offset))
+                .collect();
+            let actual: Vec<(u32, u32)> = list
+                .range(start_offset, end_offset, 0, u32::MAX)
+                .iter()
+                .map(|item| (item.index, item.offset))
+                .collect();
+            assert_eq!(actual, expected);
         }
-
-        return result;
     }
 
-    fn range_size_and_offset(&self, index: u32) -> (&u32, &u32, &u32) {
-        let (range_index, _) = tree_utils::lte(&self.size_tree, index);
-        let size = self
-            .size_tree
-            .get(range_index)
-            .expect("size tree should include the found index");
-        let offset = self
-            .offset_tree
-            .get(range_index)
-            .expect("offset tree should mirror the size tree");
+    #[test]
+    fn test_default_size() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(50);
+        assert_eq!(list.total_size(10), 500);
 
-        return (&size, &offset, &range_index);
+        // the measurement stays local to its index
+        list.insert(2, 2, 20);
+        assert_eq!(list.offsets_of(&[3, 4]), [120, 170]);
+        assert_eq!(list.total_size(10), 470);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::Item;
-    use super::OffsetList;
     #[test]
-    fn test_initial_offset_insert() {
+    fn test_default_size_after_measurements() {
         let mut list: OffsetList = OffsetList::new();
         list.insert(0, 0, 10);
+        list.insert(5, 6, 30);
+        assert_eq!(list.total_size(10), 140);
 
-        let values: Vec<u32> = list.offset_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.offset_tree.keys().cloned().collect();
-        assert_eq!(values, [0]);
-        assert_eq!(keys, [0]);
+        list.set_default_size(40);
+        assert_eq!(list.offsets_of(&[1, 5, 7]), [10, 170, 230]);
+        assert_eq!(list.total_size(10), 350);
+        assert_eq!(list.default_size(), Some(40));
     }
 
     #[test]
-    fn test_second_offset_insert() {
+    fn test_average_size_estimation() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 10);
-        list.insert(3, 7, 20);
+        list.set_size_estimation(0.0);
 
-        let values: Vec<u32> = list.offset_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.offset_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 3, 8]);
-        assert_eq!(values, [0, 30, 130]);
+        list.insert(0, 0, 10);
+        assert_eq!(list.total_size(10), 100);
+
+        list.insert(1, 1, 30);
+        list.insert(2, 2, 50);
+        assert_eq!(list.size_estimate(), 30.0);
+        assert_eq!(list.default_size(), Some(30));
+        // 10 + 30 + 50 measured, 7 estimated
+        assert_eq!(list.total_size(10), 300);
     }
 
     #[test]
-    fn test_in_between_insert() {
+    fn test_weighted_size_estimation() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 1);
-        list.insert(9, 10, 2);
-        list.insert(3, 7, 3);
+        list.set_size_estimation(0.5);
+
+        list.insert(0, 0, 100);
+        list.insert(1, 1, 50);
+        assert_eq!(list.size_estimate(), 75.0);
+        list.insert(2, 2, 50);
+        assert_eq!(list.size_estimate(), 62.5);
+        assert_eq!(list.offsets_of(&[3, 4]), [200, 263]);
+    }
 
-        let values: Vec<u32> = list.offset_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.offset_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 3, 8, 9, 11]);
-        assert_eq!(values, [0, 3, 18, 19, 23]);
+    #[test]
+    fn test_with_capacity() {
+        let mut list: OffsetList = OffsetList::with_capacity(100);
+        list.reserve(1000);
+        list.insert(0, 0, 10);
+        for index in 1..=400 {
+            list.insert(index * 2, index * 2, 20);
+        }
+
+        assert_eq!(list.metrics().range_count, 801);
+        assert_eq!(list.offsets_of(&[3]), [40]);
     }
 
     #[test]
-    fn test_overlap_insert() {
+    fn test_estimate_rebuilds_on_query() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 1);
-        list.insert(3, 7, 2);
-        list.insert(2, 9, 3);
+        list.set_size_estimation(0.0);
 
-        let values: Vec<u32> = list.offset_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.offset_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 2, 10]);
-        assert_eq!(values, [0, 2, 26]);
+        for index in 0..10 {
+            list.insert(index, index, 10 + index * 10);
+        }
+        assert_eq!(list.metrics().size_rebuilds, 1);
+
+        // the estimate is 55, 560 past the measured items
+        assert_eq!(list.offsets_of(&[10, 12]), [550, 660]);
+        assert_eq!(list.metrics().size_rebuilds, 1);
+
+        list.insert(10, 10, 0);
+        assert_eq!(list.offsets_of(&[12]), [600]);
+        assert_eq!(list.metrics().size_rebuilds, 2);
     }
 
     #[test]
-    fn test_initial_insert() {
+    fn test_perf_stats() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 10);
+        list.enable_history(10);
+        list.set_default_size(10);
+        list.insert(0, 0, 20);
+        list.insert(5, 9, 30);
+        list.set_measurement_pruning(2);
+        list.prune_measurements(0, 0);
+
+        list.range(0, 10, 0, 99);
+        list.index_range(0, 5);
+        list.items(0, 5);
+
+        let stats = list.perf_stats();
+        assert_eq!(
+            (stats.inserts, stats.removals, stats.range_queries),
+            (2, 1, 3)
+        );
+        assert_eq!(stats.rebuilds, list.metrics().size_rebuilds);
+        assert!(stats.insert_ms >= 0.0 && stats.range_query_ms >= 0.0);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(values, [10]);
-        assert_eq!(keys, [0]);
+        // undo turns the list back, not the counters
+        assert!(list.undo());
+        assert_eq!(list.perf_stats(), stats);
+
+        list.reset_perf_stats();
+        assert_eq!(list.perf_stats().inserts, 0);
     }
 
     #[test]
-    fn test_same_insert() {
+    fn test_unmeasured_indices() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 10);
-        list.insert(1, 1, 10);
-        list.insert(20, 21, 10);
+        list.set_default_size(20);
+        assert_eq!(list.unmeasured_indices(0, 3), [0, 1, 2, 3]);
+
+        list.insert(1, 2, 30);
+        list.insert(5, 5, 30);
+        assert!(list.is_measured(2));
+        assert!(!list.is_measured(3));
+        assert_eq!(list.unmeasured_indices(0, 6), [0, 3, 4, 6]);
+        assert!(list.unmeasured_indices(1, 2).is_empty());
+    }
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(values, [10]);
-        assert_eq!(keys, [0]);
+    #[test]
+    fn test_type_sizes() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(100);
+        list.set_type_size(1, 20);
+        list.set_index_type(2, 3, 1);
+        list.set_index_type(7, 7, 1);
+
+        assert_eq!(list.index_type(3), 1);
+        assert_eq!(list.index_type(4), 0);
+        assert_eq!(list.offsets_of(&[2, 4, 7, 8]), [200, 240, 540, 560]);
+
+        // measurements win over the type size
+        list.insert(2, 2, 50);
+        assert_eq!(list.offsets_of(&[4]), [270]);
+
+        list.set_type_size(1, 30);
+        assert_eq!(list.offsets_of(&[4, 8]), [280, 610]);
     }
 
     #[test]
-    fn re_insert_at_start() {
+    fn test_type_sizes_without_default() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 5);
         list.insert(0, 0, 10);
+        list.set_type_size(1, 50);
+        list.set_index_type(5, 9, 1);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(values, [10, 5]);
-        assert_eq!(keys, [0, 1]);
+        assert_eq!(list.offsets_of(&[5, 10, 11]), [50, 300, 310]);
     }
 
     #[test]
-    fn test_new_insert() {
+    fn test_tags() {
         let mut list: OffsetList = OffsetList::new();
         list.insert(0, 0, 10);
-        list.insert(3, 5, 20);
+        list.insert_tagged(2, 3, 20, 7);
+        list.insert_tagged(3, 3, 20, 8);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(values, [10, 20, 10]);
-        assert_eq!(keys, [0, 3, 6]);
+        assert_eq!(list.tag_of(1), 0);
+        assert_eq!(list.tag_of(2), 7);
+        assert_eq!(list.item_at(3).tag, 8);
+
+        let tags: Vec<u32> = list.items(1, 4).iter().map(|item| item.tag).collect();
+        assert_eq!(tags, [0, 7, 8, 0]);
+        assert_eq!(list.range_list(0, 50, 0, u32::MAX).tags(), [0, 0, 7, 8]);
+
+        // measuring without a tag keeps it
+        list.insert(2, 2, 30);
+        assert_eq!(list.tag_of(2), 7);
     }
 
     #[test]
-    fn test_join_start() {
+    fn test_gap() {
         let mut list: OffsetList = OffsetList::new();
         list.insert(0, 0, 10);
-        list.insert(3, 5, 20);
-        list.insert(5, 7, 20);
+        list.insert(3, 3, 30);
+        list.set_gap(5);
+
+        assert_eq!(list.offsets_of(&[1, 3, 4]), [15, 45, 80]);
+        assert_eq!(list.offset_of(4), 80);
+        assert_eq!(list.total(3), 75);
+        // the gap after the last item isn't part of the list
+        assert_eq!(list.total_size(5), 90);
+        assert_eq!(list.total_size(0), 0);
+
+        // an offset in a gap belongs to the item before it
+        assert_eq!(
+            list.indices_at_offsets(&[12, 15, 44, 76, 80]),
+            [0, 1, 2, 3, 4]
+        );
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(values, [10, 20, 10]);
-        assert_eq!(keys, [0, 3, 8]);
+        let items = list.items(2, 4);
+        let offsets: Vec<u32> = items.iter().map(|item| item.offset).collect();
+        assert_eq!(offsets, [30, 45, 80]);
+        assert_eq!(items[1].size, 30);
+        assert_eq!(items[1].gap, 5);
+
+        // the ranges by offset agree with `offset_of`
+        let items = list.range(0, 50, 0, 9);
+        let offsets: Vec<u32> = items.iter().map(|item| item.offset).collect();
+        assert_eq!(offsets, [0, 15, 30, 45]);
+        let indices: Vec<u32> = list
+            .range(40, 85, 0, 9)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(indices, [2, 3, 4]);
+        for item in list.range(0, 200, 0, 9) {
+            assert_eq!(item.offset, list.offset_of(item.index));
+        }
     }
 
     #[test]
-    fn test_join_end() {
+    fn test_header_and_footer() {
         let mut list: OffsetList = OffsetList::new();
         list.insert(0, 0, 10);
-        list.insert(5, 7, 20);
-        list.insert(3, 5, 20);
+        list.set_header_height(50);
+        list.set_footer_height(20);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(values, [10, 20, 10]);
-        assert_eq!(keys, [0, 3, 8]);
+        assert_eq!(list.offsets_of(&[0, 2]), [50, 70]);
+        assert_eq!(list.offset_of(2), 70);
+        assert_eq!(list.item_at(2).offset, 70);
+        assert_eq!(list.total_size(10), 170);
+        assert_eq!(list.total_size(0), 70);
+
+        // offsets within the header resolve to the first item
+        assert_eq!(list.indices_at_offsets(&[10, 59, 60]), [0, 0, 1]);
     }
 
     #[test]
-    fn test_override() {
+    fn test_padding() {
         let mut list: OffsetList = OffsetList::new();
         list.insert(0, 0, 10);
-        list.insert(5, 7, 20);
-        list.insert(4, 7, 30);
+        list.set_header_height(50);
+        list.set_padding(8, 12);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 4, 8]);
-        assert_eq!(values, [10, 30, 10]);
+        assert_eq!(list.offsets_of(&[0, 1]), [58, 68]);
+        assert_eq!(list.total_size(3), 100);
+        assert_eq!(list.indices_at_offsets(&[57, 68]), [0, 1]);
     }
 
     #[test]
-    fn test_join_override() {
+    fn test_scale() {
         let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 2, 25);
+        list.set_scale(1.5);
 
-        list.insert(0, 0, 5);
-        list.insert(4, 5, 10);
-        list.insert(6, 7, 20);
-        list.insert(3, 8, 5);
+        assert_eq!(list.offsets_of(&[1, 2, 3]), [15, 30, 68]);
+        assert_eq!(list.total_size(4), 83);
+        assert_eq!(list.indices_at_offsets(&[29, 30, 67, 68]), [1, 2, 2, 3]);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(keys, [0]);
-        assert_eq!(values, [5]);
+        let items = list.items(2, 3);
+        assert_eq!(items[0].size, 38);
+        assert_eq!(items[1].offset, 68);
+
+        // the stored sizes are left alone
+        list.set_scale(1.0);
+        assert_eq!(list.offsets_of(&[3]), [45]);
     }
 
     #[test]
-    fn test_insert_sports() {
+    fn test_rounding() {
         let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.set_scale(1.25);
 
-        list.insert_spots(vec![0, 10, 20], 5);
+        assert_eq!(list.offsets_of(&[1, 2, 3]), [13, 25, 38]);
+        assert_eq!(list.precise_offsets_of(&[1, 3]), [13.0, 38.0]);
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 1, 10, 11, 20, 21]);
-        assert_eq!(values, [5, 0, 5, 0, 5, 0]);
+        list.set_rounding(Rounding::Floor, 2.0);
+        assert_eq!(list.offsets_of(&[1, 3]), [12, 37]);
+        assert_eq!(list.precise_offsets_of(&[1, 3]), [12.5, 37.5]);
 
-        let values: Vec<u32> = list.offset_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.offset_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 1, 10, 11, 20, 21]);
-        assert_eq!(values, [0, 5, 5, 10, 10, 15]);
+        list.set_rounding(Rounding::None, 1.0);
+        assert_eq!(list.precise_offsets_of(&[1]), [12.5]);
     }
 
+    #[cfg(feature = "fenwick")]
     #[test]
-    fn test_insert_size_after_spot() {
-        let mut list: OffsetList = OffsetList::new();
-
-        list.insert_spots(vec![0, 10, 20], 5);
-        list.insert(1, 5, 10);
+    fn test_dense_sizes() {
+        let mut ranges: OffsetList = OffsetList::new();
+        let mut dense: OffsetList = OffsetList::with_dense_sizes(100);
+
+        for list in [&mut ranges, &mut dense] {
+            list.set_gap(4);
+            list.set_header_height(50);
+            list.insert(0, 0, 30);
+            list.insert(10, 19, 20);
+            list.insert(12, 12, 60);
+        }
 
-        let values: Vec<u32> = list.size_tree.values().cloned().collect();
-        let keys: Vec<u32> = list.size_tree.keys().cloned().collect();
-        assert_eq!(keys, [0, 1, 10, 11, 20, 21]);
-        assert_eq!(values, [5, 10, 5, 10, 5, 10]);
+        let indices = [0, 5, 11, 12, 13, 40];
+        assert_eq!(dense.offsets_of(&indices), ranges.offsets_of(&indices));
+        assert_eq!(
+            dense.indices_at_offsets(&[60, 400, 700]),
+            ranges.indices_at_offsets(&[60, 400, 700])
+        );
+        assert_eq!(dense.total_size(200), ranges.total_size(200));
+        let geometry = |list: &OffsetList| -> Vec<(u32, u32)> {
+            list.items(9, 14)
+                .iter()
+                .map(|item| (item.offset, item.size))
+                .collect()
+        };
+        assert_eq!(geometry(&dense), geometry(&ranges));
     }
 
     #[test]
-    fn test_offset_of() {
+    fn test_memory_stats() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 1);
-        list.insert(2, 4, 2);
+        let empty = list.memory_stats();
+        assert_eq!(empty.size_nodes, 0);
+        assert_eq!(empty.total_bytes(), 0);
 
-        assert_eq!(list.offset_of(7), 10);
+        list.insert(0, 0, 10);
+        for index in 1..=50 {
+            list.insert_tagged(index * 2, index * 2, 20, 1);
+        }
+        let stats = list.memory_stats();
+
+        assert_eq!(stats.size_nodes, 101);
+        assert_eq!(stats.measured_ranges, 51);
+        assert_eq!(stats.tag_entries, 101);
+        assert_eq!(stats.type_entries, 0);
+        assert!(stats.size_bytes > stats.measured_bytes);
+        assert!(stats.total_bytes() > empty.total_bytes());
     }
 
     #[test]
-    fn test_total() {
+    fn test_compact() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 1);
-        list.insert(2, 4, 2);
+        list.insert(0, 0, 10);
+        for index in 1..=50 {
+            list.insert(index * 2, index * 2, 20);
+        }
+        list.insert_tagged(0, 0, 10, 1);
+        list.insert_tagged(0, 0, 10, 0);
+        list.insert(1, 200, 10);
+        let offsets = list.offsets_of(&[5, 150, 300]);
+
+        assert_eq!(list.metrics().range_count, 1);
+        assert!(list.compact() > 50);
+        assert_eq!(list.memory_stats().tag_entries, 0);
+        assert_eq!(list.offsets_of(&[5, 150, 300]), offsets);
+        assert_eq!(list.compact(), 0);
+    }
 
-        assert_eq!(list.total(7), 11);
+    #[test]
+    fn test_prune_measurements() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(10);
+        for index in 0..1000 {
+            list.insert(index, index, 20 + index % 2);
+        }
+        assert_eq!(list.prune_measurements(400, 410), 0);
+
+        list.set_measurement_pruning(50);
+        assert_eq!(list.prune_measurements(400, 410), 889);
+        assert!(!list.is_measured(349));
+        assert!(list.is_measured(350));
+        assert!(list.is_measured(460));
+        assert!(!list.is_measured(461));
+        assert_eq!(list.metrics().range_count, 113);
+        assert_eq!(list.offsets_of(&[350]), [3500]);
+        assert_eq!(list.prune_measurements(400, 410), 0);
     }
 
     #[test]
-    fn test_item_at() {
+    fn test_fork() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 1);
-        list.insert(2, 4, 2);
+        list.insert(0, 0, 10);
+        list.insert_tagged(1, 1, 20, 3);
 
-        let item = list.item_at(10);
-        assert_eq!(item.size, 1);
-        assert_eq!(item.offset, 13);
-        assert_eq!(item.index, 10);
+        let mut fork = list.fork();
+        fork.insert(2, 2, 50);
+        fork.set_gap(5);
+
+        assert_eq!(list.offsets_of(&[3]), [40]);
+        assert_eq!(fork.offsets_of(&[3]), [95]);
+        assert_eq!(fork.tag_of(1), 3);
+        assert!(!list.is_measured(2));
     }
 
     #[test]
-    fn test_index_range() {
+    fn test_checksum_and_equals() {
         let mut list: OffsetList = OffsetList::new();
-        list.insert(0, 0, 1);
-        list.insert(2, 4, 2);
+        list.insert(0, 0, 10);
+        list.insert(5, 9, 20);
+
+        let mut other: OffsetList = OffsetList::new();
+        other.insert(0, 0, 10);
+        other.insert(5, 6, 20);
+        assert!(!list.equals(&other));
+        assert_ne!(list.checksum(), other.checksum());
+
+        other.insert(7, 9, 20);
+        assert!(list.equals(&other));
+        assert_eq!(list.checksum(), other.checksum());
+
+        other.insert_tagged(3, 3, 10, 1);
+        assert!(!list.equals(&other));
+        assert_ne!(list.checksum(), other.checksum());
+
+        list.insert_tagged(3, 3, 10, 1);
+        list.set_gap(4);
+        assert!(!list.equals(&other));
+        assert_ne!(list.checksum(), other.checksum());
+    }
 
-        let items: Vec<Item> = list.index_range(3, 6);
-        assert_eq!(items.len(), 4);
+    #[test]
+    fn test_diff() {
+        let mut list: OffsetList = OffsetList::new();
+        list.insert(0, 0, 10);
+        let mut other = list.fork();
+        assert!(list.diff(&other, 100).is_empty());
 
-        let item = items.get(0).expect("Item 0 is here");
-        assert_eq!(item.index, 3);
-        assert_eq!(item.size, 2);
+        other.insert(20, 20, 30);
+        assert_eq!(list.diff(&other, 100), [20, 99]);
 
-        let item = items.get(3).expect("Item 0 is here");
-        assert_eq!(item.index, 6);
-        assert_eq!(item.size, 1);
+        list.insert(20, 20, 30);
+        other.insert(50, 51, 5);
+        other.insert(52, 52, 20);
+        assert_eq!(list.diff(&other, 100), [50, 52]);
+        assert_eq!(list.diff(&other, 51), [50, 50]);
+        assert!(list.diff(&other, 0).is_empty());
+
+        other.set_gap(1);
+        assert_eq!(list.diff(&other, 100), [0, 99]);
     }
 
     #[test]
-    fn test_range() {
+    fn test_merge() {
+        let mut server: OffsetList = OffsetList::new();
+        server.set_default_size(10);
+        server.insert(0, 9, 30);
+
+        let mut client: OffsetList = OffsetList::new();
+        client.set_default_size(50);
+        client.insert(5, 5, 20);
+        client.insert(6, 6, 40);
+        client.insert(20, 21, 25);
+
+        let mut newer = server.fork();
+        assert_eq!(newer.merge(&client, MergePolicy::PreferNewer), 4);
+        assert_eq!(
+            newer.offsets_of(&[5, 6, 7, 20, 22, 23]),
+            [150, 170, 210, 400, 450, 460]
+        );
+        assert!(newer.is_measured(21));
+        assert!(!newer.is_measured(22));
+
+        let mut larger = server.fork();
+        assert_eq!(larger.merge(&client, MergePolicy::PreferLarger), 3);
+        assert_eq!(larger.offsets_of(&[6, 7, 20]), [180, 220, 410]);
+    }
+
+    #[test]
+    fn test_undo_redo() {
         let mut list: OffsetList = OffsetList::new();
         list.insert(0, 0, 10);
-        list.insert(2, 4, 20);
+        assert!(!list.undo());
+
+        list.enable_history(2);
+        list.insert(1, 1, 20);
+        list.insert_tagged(2, 2, 30, 1);
+        list.insert(3, 3, 40);
+        assert_eq!(list.offsets_of(&[4]), [100]);
+
+        assert!(list.undo());
+        assert!(list.undo());
+        assert!(!list.undo());
+        assert_eq!(list.offsets_of(&[4]), [50]);
+        assert_eq!(list.tag_of(2), 0);
+        assert!(!list.is_measured(2));
+
+        let version = list.version();
+        assert!(list.redo());
+        assert_eq!(list.offsets_of(&[4]), [70]);
+        assert_eq!(list.tag_of(2), 1);
+        assert!(list.version() > version);
+
+        list.set_index_type(0, 0, 1);
+        assert!(!list.can_redo());
+        assert!(list.can_undo());
+
+        list.enable_history(0);
+        assert!(!list.can_undo());
+    }
 
-        let items: Vec<Item> = list.range(13, 79, 0, std::u32::MAX);
-        assert_eq!(items.len(), 4);
+    #[test]
+    fn test_undo_keeps_the_settings() {
+        let mut list: OffsetList = OffsetList::new();
+        list.enable_history(4);
+        list.insert(0, 0, 10);
+        list.insert(1, 1, 20);
+        list.set_gap(5);
+        list.set_scale(2.0);
+        list.set_total_count(3);
+
+        assert!(list.undo());
+        assert_eq!(list.gap(), 5);
+        assert_eq!(list.scale(), 2.0);
+        assert_eq!(list.total_count(), Some(3));
+        // 10 + 10 + 2 gaps, scaled
+        assert_eq!(list.offsets_of(&[2]), [60]);
+
+        assert!(list.redo());
+        assert_eq!(list.gap(), 5);
+        assert_eq!(list.offsets_of(&[2]), [80]);
+    }
 
-        let item = items.get(0).expect("Item is here");
-        assert_eq!(item.index, 1);
-        assert_eq!(item.size, 10);
-        assert_eq!(item.offset, 10);
+    #[test]
+    fn test_change_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
 
-        let item = items.get(1).expect("Item is here");
-        assert_eq!(item.index, 2);
-        assert_eq!(item.size, 20);
-        assert_eq!(item.offset, 20);
+        let mut list: OffsetList = OffsetList::new();
+        list.set_listener(Shared::new(move |event: &ListEvent| {
+            recorded.lock().unwrap().push(*event)
+        }));
+        list.insert(0, 0, 10);
+        list.insert_tagged(5, 9, 20, 1);
+
+        let mut other: OffsetList = OffsetList::new();
+        other.insert(100, 100, 30);
+        list.merge(&other, MergePolicy::PreferNewer);
+
+        list.set_measurement_pruning(10);
+        list.prune_measurements(100, 100);
+
+        let event = |kind, start, end| ListEvent { kind, start, end };
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                event(ListEventKind::Insert, 0, 0),
+                event(ListEventKind::Insert, 5, 9),
+                event(ListEventKind::Merge, 100, 100),
+                event(ListEventKind::Remove, 0, 89),
+            ]
+        );
+    }
 
-        let item = items.get(2).expect("Item is here");
-        assert_eq!(item.index, 3);
-        assert_eq!(item.size, 20);
-        assert_eq!(item.offset, 40);
+    #[test]
+    fn test_keys_follow_a_reorder() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(10);
+        list.set_keys(&[100, 101, 102, 103, 104]);
+        list.insert(0, 0, 50);
+        list.insert(1, 1, 20);
+        list.insert(3, 3, 30);
+        assert_eq!(list.index_of_key(103), Some(3));
+
+        list.set_keys(&[103, 104, 101, 100, 105]);
+        assert_eq!(list.offsets_of(&[1, 2, 3, 4, 5]), [30, 40, 60, 110, 120]);
+        assert!(list.is_measured(0));
+        assert!(!list.is_measured(1));
+        assert_eq!(list.key_of(4), Some(105));
+        assert_eq!(list.index_of_key(102), None);
+    }
 
-        let item = items.get(3).expect("Item is here");
-        assert_eq!(item.index, 4);
-        assert_eq!(item.size, 20);
-        assert_eq!(item.offset, 60);
+    #[test]
+    fn test_move_item() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(10);
+        list.set_keys(&[100, 101, 102, 103, 104]);
+        list.insert_tagged(1, 1, 50, 7);
+        list.insert(3, 3, 30);
+
+        assert_eq!(list.move_item(1, 3), 40);
+        assert_eq!(list.offsets_of(&[1, 2, 3, 4]), [10, 20, 50, 100]);
+        assert_eq!(list.tag_of(3), 7);
+        assert_eq!(list.tag_of(1), 0);
+        assert!(list.is_measured(2));
+        assert!(!list.is_measured(1));
+        assert_eq!(list.index_of_key(101), Some(3));
+
+        assert_eq!(list.move_item(3, 0), -50);
+        assert_eq!(list.offsets_of(&[1, 2, 3]), [50, 60, 70]);
+    }
+
+    #[test]
+    fn test_collapse_and_expand() {
+        let mut list: OffsetList = OffsetList::new();
+        list.set_default_size(10);
+        list.insert(2, 2, 50);
+        list.insert(6, 6, 30);
+
+        list.collapse(2, 4);
+        assert_eq!(list.offsets_of(&[2, 5, 6, 7]), [20, 20, 30, 60]);
+        assert!(list.is_collapsed(3));
+        assert!(!list.is_measured(2));
+
+        let indices: Vec<u32> = list
+            .range(0, 35, 0, 20)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(indices, [0, 1, 5, 6]);
+
+        list.insert(3, 3, 40);
+        assert_eq!(list.offsets_of(&[5]), [20]);
+
+        list.expand(0, 10);
+        assert_eq!(list.offsets_of(&[3, 4, 5, 6, 7]), [70, 110, 120, 130, 160]);
+        assert!(list.is_measured(2));
+        assert!(!list.is_collapsed(3));
     }
 }
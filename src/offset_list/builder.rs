@@ -0,0 +1,97 @@
+use super::{OffsetList, Rounding};
+use crate::axis::{Axis, Direction};
+#[cfg(feature = "std")]
+use crate::list_engine::ListEngine;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The settings a list starts with, for `OffsetList::with_options` and
+/// `ListEngine::with_options`. JS fills in the fields it cares about on
+/// `OffsetListOptions.new()`. The axis and direction only matter to the
+/// engine, and so does `expected_count`, which it takes as its item count;
+/// the list reserves room for that many measured items.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OffsetListOptions {
+    pub default_size: Option<u32>,
+    pub expected_count: u32,
+    pub axis: Axis,
+    pub direction: Direction,
+    pub rounding: Rounding,
+    pub device_pixel_ratio: f64,
+    pub gap: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OffsetListOptions {
+    pub fn new() -> OffsetListOptions {
+        OffsetListOptions {
+            default_size: None,
+            expected_count: 0,
+            axis: Axis::Vertical,
+            direction: Direction::Forward,
+            rounding: Rounding::Round,
+            device_pixel_ratio: 1.0,
+            gap: 0,
+        }
+    }
+}
+
+impl Default for OffsetListOptions {
+    fn default() -> Self {
+        OffsetListOptions::new()
+    }
+}
+
+/// Sets up `OffsetListOptions` one call at a time, then builds the list or
+/// the engine from them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OffsetListBuilder {
+    options: OffsetListOptions,
+}
+
+impl OffsetListBuilder {
+    pub fn new() -> OffsetListBuilder {
+        OffsetListBuilder::default()
+    }
+
+    pub fn default_size(mut self, size: u32) -> OffsetListBuilder {
+        self.options.default_size = Some(size);
+        self
+    }
+
+    pub fn expected_count(mut self, count: u32) -> OffsetListBuilder {
+        self.options.expected_count = count;
+        self
+    }
+
+    pub fn axis(mut self, axis: Axis, direction: Direction) -> OffsetListBuilder {
+        self.options.axis = axis;
+        self.options.direction = direction;
+        self
+    }
+
+    pub fn rounding(mut self, rounding: Rounding, device_pixel_ratio: f64) -> OffsetListBuilder {
+        self.options.rounding = rounding;
+        self.options.device_pixel_ratio = device_pixel_ratio;
+        self
+    }
+
+    pub fn gap(mut self, gap: u32) -> OffsetListBuilder {
+        self.options.gap = gap;
+        self
+    }
+
+    pub fn options(&self) -> OffsetListOptions {
+        self.options
+    }
+
+    pub fn build(&self) -> OffsetList {
+        OffsetList::with_options(&self.options)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn build_engine(&self, overscan: u32) -> ListEngine {
+        ListEngine::with_options(&self.options, overscan)
+    }
+}
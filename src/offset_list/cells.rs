@@ -0,0 +1,223 @@
+//! The pointers and cells behind an `OffsetList`. By default, they are `Rc`,
+//! `RefCell` and `Cell`, all the single threaded wasm module needs. The `sync`
+//! feature swaps in `Arc`, an `RwLock` and atomics, which makes the list
+//! `Send + Sync`.
+
+#[cfg(not(feature = "sync"))]
+pub use alloc::rc::Rc as Shared;
+#[cfg(feature = "sync")]
+pub use std::sync::Arc as Shared;
+
+#[cfg(not(feature = "sync"))]
+pub type ReadGuard<'a, T> = core::cell::Ref<'a, T>;
+#[cfg(feature = "sync")]
+pub type ReadGuard<'a, T> = std::sync::RwLockReadGuard<'a, T>;
+
+#[cfg(not(feature = "sync"))]
+pub type WriteGuard<'a, T> = core::cell::RefMut<'a, T>;
+#[cfg(feature = "sync")]
+pub type WriteGuard<'a, T> = std::sync::RwLockWriteGuard<'a, T>;
+
+/// A value written behind a shared reference: a `RefCell`, or an `RwLock`
+/// with `sync`. A panic while holding the lock leaves the value as it was,
+/// so a poisoned lock is taken over.
+#[derive(Debug, Default)]
+pub struct Lock<T> {
+    #[cfg(not(feature = "sync"))]
+    value: core::cell::RefCell<T>,
+    #[cfg(feature = "sync")]
+    value: std::sync::RwLock<T>,
+}
+
+impl<T> Lock<T> {
+    pub fn new(value: T) -> Lock<T> {
+        Lock {
+            value: value.into(),
+        }
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn borrow(&self) -> ReadGuard<'_, T> {
+        self.value.borrow()
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn borrow(&self) -> ReadGuard<'_, T> {
+        self.value
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn borrow_mut(&self) -> WriteGuard<'_, T> {
+        self.value.borrow_mut()
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn borrow_mut(&self) -> WriteGuard<'_, T> {
+        self.value
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl<T: Clone> Clone for Lock<T> {
+    fn clone(&self) -> Lock<T> {
+        Lock::new(self.borrow().clone())
+    }
+}
+
+/// A `Cell<bool>`, or an `AtomicBool` with `sync`.
+#[derive(Debug, Default)]
+pub struct Flag {
+    #[cfg(not(feature = "sync"))]
+    value: core::cell::Cell<bool>,
+    #[cfg(feature = "sync")]
+    value: core::sync::atomic::AtomicBool,
+}
+
+impl Flag {
+    pub fn new(value: bool) -> Flag {
+        Flag {
+            value: value.into(),
+        }
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn get(&self) -> bool {
+        self.value.get()
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get(&self) -> bool {
+        self.value.load(core::sync::atomic::Ordering::Acquire)
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn set(&self, value: bool) {
+        self.value.set(value)
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn set(&self, value: bool) {
+        self.value
+            .store(value, core::sync::atomic::Ordering::Release)
+    }
+}
+
+impl Clone for Flag {
+    fn clone(&self) -> Flag {
+        Flag::new(self.get())
+    }
+}
+
+/// A `Cell<u32>`, or an `AtomicU32` with `sync`.
+#[derive(Debug, Default)]
+pub struct Counter {
+    #[cfg(not(feature = "sync"))]
+    value: core::cell::Cell<u32>,
+    #[cfg(feature = "sync")]
+    value: core::sync::atomic::AtomicU32,
+}
+
+impl Counter {
+    pub fn new(value: u32) -> Counter {
+        Counter {
+            value: value.into(),
+        }
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn get(&self) -> u32 {
+        self.value.get()
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get(&self) -> u32 {
+        self.value.load(core::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Adds one, wrapping around.
+    #[cfg(not(feature = "sync"))]
+    pub fn bump(&self) {
+        self.value.set(self.value.get().wrapping_add(1))
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn bump(&self) {
+        self.value
+            .fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+impl Clone for Counter {
+    fn clone(&self) -> Counter {
+        Counter::new(self.get())
+    }
+}
+
+/// A `Cell<f64>` running total, or its bits in an `AtomicU64` with `sync`.
+#[derive(Debug, Default)]
+pub struct Total {
+    #[cfg(not(feature = "sync"))]
+    value: core::cell::Cell<f64>,
+    #[cfg(feature = "sync")]
+    value: core::sync::atomic::AtomicU64,
+}
+
+impl Total {
+    #[cfg(not(feature = "sync"))]
+    pub fn new(value: f64) -> Total {
+        Total {
+            value: value.into(),
+        }
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn new(value: f64) -> Total {
+        Total {
+            value: value.to_bits().into(),
+        }
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn get(&self) -> f64 {
+        self.value.get()
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.value.load(core::sync::atomic::Ordering::Relaxed))
+    }
+
+    #[cfg(not(feature = "sync"))]
+    pub fn add(&self, amount: f64) {
+        self.value.set(self.value.get() + amount)
+    }
+
+    #[cfg(feature = "sync")]
+    pub fn add(&self, amount: f64) {
+        use core::sync::atomic::Ordering::Relaxed;
+        let _ = self.value.fetch_update(Relaxed, Relaxed, |bits| {
+            Some((f64::from_bits(bits) + amount).to_bits())
+        });
+    }
+}
+
+impl Clone for Total {
+    fn clone(&self) -> Total {
+        Total::new(self.get())
+    }
+}
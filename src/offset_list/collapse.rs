@@ -0,0 +1,139 @@
+use super::store::SizeStore;
+use super::transfer::{ascending, Reader, Writer};
+use super::tree_utils::Range;
+use crate::range_set::RangeSet;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cmp;
+
+/// The collapsed spans of a list, and the measurements of their items, put
+/// aside until the spans are expanded again.
+#[derive(Clone, Debug, Default)]
+pub struct Collapsed {
+    spans: RangeSet,
+    /// Disjoint measured ranges, as `start => (end, size)`.
+    sizes: BTreeMap<u32, (u32, u32)>,
+}
+
+impl Collapsed {
+    pub fn is_empty(&self) -> bool {
+        self.spans.len() == 0
+    }
+
+    pub fn contains(&self, index: u32) -> bool {
+        self.spans.contains(index)
+    }
+
+    /// The parts of `start..=end` that are not collapsed.
+    pub fn gaps(&self, start: u32, end: u32) -> Vec<(u32, u32)> {
+        self.spans.gaps(start, end)
+    }
+
+    pub fn hide(&mut self, start: u32, end: u32) {
+        self.spans.insert(start, end);
+    }
+
+    /// The collapsed parts of `start..=end`.
+    pub fn within(&self, start: u32, end: u32) -> Vec<(u32, u32)> {
+        self.spans.within(start, end)
+    }
+
+    /// Keeps the measurement of a collapsed range, over the one it had.
+    pub fn keep(&mut self, range: Range) {
+        self.take(range.start, range.end);
+        self.sizes.insert(range.start, (range.end, range.size));
+    }
+
+    /// Expands `start..=end` and returns the measurements kept for it.
+    pub fn show(&mut self, start: u32, end: u32) -> Vec<Range> {
+        self.spans.remove(start, end);
+        self.take(start, end)
+    }
+
+    /// Writes the collapsed spans into `tree` as zero sized. The parts that
+    /// are zero sized already are left alone, as writing into them is taken
+    /// for the first size after the group spots.
+    pub fn write_into(&self, tree: &mut SizeStore) {
+        let mut sized: Vec<(u32, u32)> = Vec::new();
+        for (start, end) in self.spans.iter() {
+            for (range, _) in tree.ranges(start, end) {
+                if range.size != 0 {
+                    sized.push((cmp::max(range.start, start), cmp::min(range.end, end)));
+                }
+            }
+        }
+
+        for (start, end) in sized {
+            tree.write_range(start, end, 0);
+        }
+    }
+
+    /// The spans, then the kept measurements, for `to_transferable`.
+    pub fn write(&self, writer: &mut Writer) {
+        let spans: Vec<u32> = self
+            .spans
+            .iter()
+            .flat_map(|(start, end)| [start, end])
+            .collect();
+        writer.words(&spans);
+
+        let sizes: Vec<u32> = self
+            .sizes
+            .iter()
+            .flat_map(|(start, (end, size))| [*start, *end, *size])
+            .collect();
+        writer.words(&sizes);
+    }
+
+    pub fn read(reader: &mut Reader) -> Option<Collapsed> {
+        let spans = reader.spans()?;
+        let sizes = reader.words(3)?;
+        if sizes.chunks(3).any(|range| range[0] > range[1])
+            || !ascending(sizes.chunks(3).map(|range| range[0]))
+        {
+            return None;
+        }
+
+        let mut collapsed = Collapsed::default();
+        for (start, end) in spans {
+            collapsed.hide(start, end);
+        }
+        for range in sizes.chunks(3) {
+            collapsed.sizes.insert(range[0], (range[1], range[2]));
+        }
+
+        Some(collapsed)
+    }
+
+    fn take(&mut self, start: u32, end: u32) -> Vec<Range> {
+        let first = match self.sizes.range(..=start).next_back() {
+            None => start,
+            Some((range_start, _)) => *range_start,
+        };
+        let overlapping: Vec<(u32, u32, u32)> = self
+            .sizes
+            .range(first..=end)
+            .filter(|(_, (range_end, _))| *range_end >= start)
+            .map(|(range_start, (range_end, size))| (*range_start, *range_end, *size))
+            .collect();
+
+        let mut taken = Vec::new();
+        for (range_start, range_end, size) in overlapping {
+            self.sizes.remove(&range_start);
+            if range_start < start {
+                self.sizes.insert(range_start, (start - 1, size));
+            }
+            if range_end > end {
+                self.sizes.insert(end + 1, (range_end, size));
+            }
+
+            taken.push(Range {
+                start: cmp::max(range_start, start),
+                end: cmp::min(range_end, end),
+                size,
+            });
+        }
+
+        taken
+    }
+}
@@ -0,0 +1,184 @@
+use super::tree_utils::{Range, LAST_RANGE_END};
+use alloc::string::String;
+use core::cmp;
+use core::fmt;
+use core::fmt::Write;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The ranges listed on each side of the failing index.
+const SUMMARY_RANGES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorCode {
+    /// A query that needs sizes, before anything was measured.
+    EmptyList,
+    /// A range whose start is past its end.
+    InvalidRange,
+    /// An index past the total count.
+    IndexOutOfBounds,
+    /// A buffer that isn't a list from `to_transferable`, or a corrupt one.
+    CorruptBuffer,
+}
+
+impl ErrorCode {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorCode::EmptyList => "empty_list",
+            ErrorCode::InvalidRange => "invalid_range",
+            ErrorCode::IndexOutOfBounds => "index_out_of_bounds",
+            ErrorCode::CorruptBuffer => "corrupt_buffer",
+        }
+    }
+}
+
+/// Why an `OffsetList` call failed, with the sizes around the failure, so
+/// that a bug report carries the state that led to it. In JS it is thrown
+/// as an `Error` with `code`, `index` and `tree_summary` next to `message`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListError {
+    pub code: ErrorCode,
+    /// The item the call failed at, when there is one.
+    pub index: Option<u32>,
+    pub message: String,
+    /// The size ranges around `index`, as `start-end:size@offset`.
+    pub tree_summary: String,
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}: {}", self.code.name(), self.message)?;
+        if let Some(index) = self.index {
+            write!(formatter, " at {}", index)?;
+        }
+        write!(formatter, " [{}]", self.tree_summary)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ListError {}
+
+#[cfg(feature = "wasm")]
+impl From<ListError> for JsValue {
+    fn from(error: ListError) -> JsValue {
+        let object = js_sys::Error::new(&error.message);
+        let index = error.index.map_or(JsValue::UNDEFINED, JsValue::from);
+        for (key, value) in [
+            ("code", JsValue::from(error.code.name())),
+            ("index", index),
+            ("tree_summary", JsValue::from(error.tree_summary)),
+        ] {
+            let _ = js_sys::Reflect::set(&object, &JsValue::from(key), &value);
+        }
+        object.into()
+    }
+}
+
+/// The ranges next to the one holding `index`, and how many there are. The
+/// last range runs to the end of the list, as `start-:size@offset`.
+pub fn summarize(ranges: &[(Range, u32)], index: u32) -> String {
+    if ranges.is_empty() {
+        return String::from("no ranges");
+    }
+
+    let at = ranges
+        .iter()
+        .rposition(|(range, _)| range.start <= index)
+        .unwrap_or(0);
+    let first = at.saturating_sub(SUMMARY_RANGES);
+    let last = cmp::min(at + SUMMARY_RANGES, ranges.len() - 1);
+
+    let mut summary = String::new();
+    for (range, offset) in &ranges[first..=last] {
+        let _ = match range.end {
+            LAST_RANGE_END => write!(summary, "{}-:{}@{} ", range.start, range.size, offset),
+            end => write!(
+                summary,
+                "{}-{}:{}@{} ",
+                range.start, end, range.size, offset
+            ),
+        };
+    }
+    let _ = write!(summary, "({} ranges)", ranges.len());
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCode;
+    use crate::offset_list::tree_utils::LAST_RANGE_END;
+    use crate::offset_list::OffsetList;
+
+    #[test]
+    fn test_range_errors() {
+        let list = OffsetList::new();
+        let error = list.try_range(0, 100, 0, 10).err().unwrap();
+        assert_eq!(error.code, ErrorCode::EmptyList);
+        assert_eq!(error.tree_summary, "no ranges");
+
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        for index in 1..10 {
+            list.insert(index * 2, index * 2, 20);
+        }
+        assert!(list.try_range(0, 1000, 0, 100).is_ok());
+        assert!(list.try_range(0, 1000, 0, u32::MAX).is_ok());
+
+        let error = list.try_range(0, 1000, 5, 4).err().unwrap();
+        assert_eq!(
+            (error.code, error.index),
+            (ErrorCode::InvalidRange, Some(5))
+        );
+        assert!(list.range(0, 1000, 5, 4).is_empty());
+
+        let mut counted = list.clone();
+        counted.set_total_count(10);
+        let error = counted.try_range(0, 1000, 10, 20).err().unwrap();
+        assert_eq!(
+            (error.code, error.index),
+            (ErrorCode::IndexOutOfBounds, Some(10))
+        );
+        assert_eq!(counted.try_range(0, 1000, 9, 20).unwrap().len(), 1);
+
+        let ranges = list.sizes().ranges(0, LAST_RANGE_END);
+        assert_eq!(
+            super::summarize(&ranges, 19),
+            "16-16:20@230 17-17:10@250 18-18:20@260 19-:10@280 (19 ranges)"
+        );
+
+        let error = list.error(ErrorCode::EmptyList, Some(19), "nothing is measured yet");
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "empty_list: nothing is measured yet at 19 [{}]",
+                error.tree_summary
+            )
+        );
+    }
+
+    #[test]
+    fn test_insert_errors() {
+        let mut list = OffsetList::new();
+        let error = list.try_insert(5, 4, 10).unwrap_err();
+        assert_eq!(
+            (error.code, error.index),
+            (ErrorCode::InvalidRange, Some(5))
+        );
+        assert!(list.try_insert(4, 5, 10).is_ok());
+        assert_eq!(list.offsets_of(&[6]), [60]);
+    }
+
+    #[test]
+    fn test_transfer_errors() {
+        let mut list = OffsetList::new();
+        list.insert(0, 3, 10);
+        let bytes = list.to_transferable();
+        assert!(OffsetList::try_from_transferable(&bytes).is_ok());
+
+        let error = OffsetList::try_from_transferable(&bytes[..bytes.len() - 1])
+            .err()
+            .unwrap();
+        assert_eq!((error.code, error.index), (ErrorCode::CorruptBuffer, None));
+        assert_eq!(error.tree_summary, "no ranges");
+    }
+}
@@ -0,0 +1,43 @@
+use super::cells::Shared;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListEventKind {
+    /// Measurements written into the list, spots included.
+    Insert,
+    /// Measurements forgotten by pruning.
+    Remove,
+    /// Measurements taken from another list by `merge`.
+    Merge,
+    /// Items reordered by `move_item`, with their measurements.
+    Move,
+    /// Items hidden by `collapse`.
+    Collapse,
+    /// Items shown again by `expand`.
+    Expand,
+}
+
+impl ListEventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ListEventKind::Insert => "insert",
+            ListEventKind::Remove => "remove",
+            ListEventKind::Merge => "merge",
+            ListEventKind::Move => "move",
+            ListEventKind::Collapse => "collapse",
+            ListEventKind::Expand => "expand",
+        }
+    }
+}
+
+/// A structural change of an `OffsetList`, over the items `start..=end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ListEvent {
+    pub kind: ListEventKind,
+    pub start: u32,
+    pub end: u32,
+}
+
+#[cfg(not(feature = "sync"))]
+pub type ListListener = Shared<dyn Fn(&ListEvent)>;
+#[cfg(feature = "sync")]
+pub type ListListener = Shared<dyn Fn(&ListEvent) + Send + Sync>;
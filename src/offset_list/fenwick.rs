@@ -0,0 +1,377 @@
+use super::size::Size;
+use super::tree_utils::{Range, LAST_RANGE_END};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Per index sizes with a binary indexed tree over them, for lists where most
+/// items end up measured one by one. Every write is a point update, where the
+/// range tree would split its ranges further with each of them.
+///
+/// The indices `0..capacity` are stored densely; the ones past it share a
+/// single tail size, which a write reaching past the capacity replaces.
+#[derive(Clone, Debug)]
+pub struct FenwickTree<S: Size> {
+    sizes: Vec<S>,
+    /// 1-based; `sums[i]` holds the sizes of the `i & -i` indices up to `i`.
+    sums: Vec<S>,
+    tail: S,
+    empty: bool,
+    /// Set by `insert_spots` until the items get their first size.
+    grouped: bool,
+}
+
+impl<S: Size> FenwickTree<S> {
+    pub fn new(capacity: u32) -> FenwickTree<S> {
+        FenwickTree {
+            sizes: vec![S::ZERO; capacity as usize],
+            sums: vec![S::ZERO; capacity as usize + 1],
+            tail: S::ZERO,
+            empty: true,
+            grouped: false,
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.sizes.len() as u32
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        (self.sizes.capacity() + self.sums.capacity()) * core::mem::size_of::<S>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+
+    /// The number of runs of equally sized items.
+    pub fn len(&self) -> usize {
+        if self.empty {
+            return 0;
+        }
+
+        self.runs(0, LAST_RANGE_END).len()
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(S::ZERO);
+        self.empty = true;
+        self.grouped = false;
+    }
+
+    /// Makes every item `size` large.
+    pub fn reset(&mut self, size: S) {
+        self.fill(size);
+        self.empty = false;
+        self.grouped = false;
+    }
+
+    /// Spots added to sized items take their size alone, as in the range
+    /// tree.
+    pub fn insert_spots(&mut self, spots: &[(u32, S)]) {
+        if !self.empty {
+            let capacity = self.capacity();
+            for (spot, size) in spots.iter().filter(|(spot, _)| *spot < capacity) {
+                self.set(*spot, *size);
+            }
+            return;
+        }
+
+        for (spot, size) in spots.iter() {
+            if let Some(slot) = self.sizes.get_mut(*spot as usize) {
+                *slot = *size;
+            }
+        }
+
+        self.rebuild();
+        self.empty = false;
+        self.grouped = true;
+    }
+
+    /// Sets the size of `start..=end`, with the same rules as the range tree:
+    /// the first write sizes all items, as does the first one after the spots.
+    pub fn write_range(&mut self, start: u32, end: u32, size: S) {
+        if self.empty {
+            self.reset(size);
+            return;
+        }
+
+        if self.grouped && self.size(start) == S::ZERO {
+            self.grouped = false;
+            self.map_sizes(|value| if value == S::ZERO { size } else { value });
+            return;
+        }
+
+        let capacity = self.capacity();
+        if start < capacity {
+            for index in start..=end.min(capacity - 1) {
+                self.set(index, size);
+            }
+        }
+
+        if end >= capacity {
+            self.tail = size;
+        }
+    }
+
+    pub fn map_sizes(&mut self, map: impl Fn(S) -> S) {
+        for size in self.sizes.iter_mut() {
+            *size = map(*size);
+        }
+        self.tail = map(self.tail);
+        self.rebuild();
+    }
+
+    /// The index itself, its size and offset; every index starts a range of
+    /// its own here.
+    pub fn range_at(&self, index: u32) -> (u32, S, S) {
+        (index, self.size(index), self.offset(index))
+    }
+
+    /// The last index starting at or before `offset`, with `gap` between each
+    /// two items.
+    pub fn range_at_offset(&self, offset: S, gap: S) -> (u32, S, S) {
+        let (index, index_offset) =
+            self.last_before(|sum, count| sum.plus(gap.times(count)) <= offset);
+        (index, self.size(index), index_offset)
+    }
+
+    /// The runs of equally sized items in `first..=last`, starting at
+    /// `first`, with their offsets. The tail is reported as running to the
+    /// end of the list.
+    pub fn ranges(&self, first: u32, last: u32) -> Vec<(Range<S>, S)> {
+        if self.empty {
+            return Vec::new();
+        }
+
+        self.runs(first, last)
+            .into_iter()
+            .map(|range| {
+                let offset = self.offset(range.start);
+                (range, offset)
+            })
+            .collect()
+    }
+
+    /// Every run as `(start, size, offset)`, in order.
+    pub fn entries(&self) -> Vec<(u32, S, S)> {
+        self.ranges(0, LAST_RANGE_END)
+            .into_iter()
+            .map(|(range, offset)| (range.start, range.size, offset))
+            .collect()
+    }
+
+    pub fn offsets_of(&self, indices: &[u32], gap: S) -> Vec<S> {
+        indices
+            .iter()
+            .map(|index| self.offset(*index).plus(gap.times(*index)))
+            .collect()
+    }
+
+    pub fn indices_at(&self, offsets: &[S], gap: S) -> Vec<u32> {
+        if self.empty {
+            return vec![0u32; offsets.len()];
+        }
+
+        offsets
+            .iter()
+            .map(|offset| {
+                let (start, size, range_offset) = self.range_at_offset(*offset, gap);
+                let range_offset = range_offset.plus(gap.times(start));
+
+                if size == S::ZERO || *offset < range_offset {
+                    start
+                } else {
+                    start.saturating_add((*offset - range_offset).fits(size.plus(gap)))
+                }
+            })
+            .collect()
+    }
+
+    fn size(&self, index: u32) -> S {
+        match self.sizes.get(index as usize) {
+            None => self.tail,
+            Some(size) => *size,
+        }
+    }
+
+    fn offset(&self, index: u32) -> S {
+        let capacity = self.capacity();
+        if index <= capacity {
+            return self.prefix(index);
+        }
+
+        self.prefix(capacity)
+            .plus(self.tail.times(index - capacity))
+    }
+
+    /// The sum of the sizes before `count`, up to the capacity.
+    fn prefix(&self, count: u32) -> S {
+        let mut position = count as usize;
+        let mut sum = S::ZERO;
+
+        while position > 0 {
+            sum = sum.plus(self.sums[position]);
+            position &= position - 1;
+        }
+
+        sum
+    }
+
+    fn set(&mut self, index: u32, size: S) {
+        let previous = self.sizes[index as usize];
+        if previous == size {
+            return;
+        }
+        self.sizes[index as usize] = size;
+
+        let mut position = index as usize + 1;
+        while position < self.sums.len() {
+            // a saturated sum holds at least `previous`
+            self.sums[position] = (self.sums[position] - previous).plus(size);
+            position += position & position.wrapping_neg();
+        }
+    }
+
+    /// The largest number of items, up to the capacity, whose sizes still
+    /// pass `accept(sum, count)`, and their sum.
+    fn last_before(&self, accept: impl Fn(S, u32) -> bool) -> (u32, S) {
+        let length = self.sizes.len();
+        let mut step = match length {
+            0 => 0,
+            length => 1usize << (usize::BITS - 1 - length.leading_zeros()),
+        };
+        let mut position = 0usize;
+        let mut sum = S::ZERO;
+
+        while step > 0 {
+            let next = position + step;
+            if next <= length {
+                let candidate = sum.plus(self.sums[next]);
+                if accept(candidate, next as u32) {
+                    position = next;
+                    sum = candidate;
+                }
+            }
+            step >>= 1;
+        }
+
+        (position as u32, sum)
+    }
+
+    fn runs(&self, first: u32, last: u32) -> Vec<Range<S>> {
+        let capacity = self.capacity();
+        let mut result: Vec<Range<S>> = Vec::new();
+
+        for index in first..=last.min(capacity.saturating_sub(1)) {
+            if index >= capacity {
+                break;
+            }
+
+            let size = self.sizes[index as usize];
+            match result.last_mut() {
+                Some(run) if run.size == size => run.end = index,
+                _ => result.push(Range {
+                    start: index,
+                    end: index,
+                    size,
+                }),
+            }
+        }
+
+        if last >= capacity {
+            match result.last_mut() {
+                Some(run) if run.size == self.tail => run.end = LAST_RANGE_END,
+                _ => result.push(Range {
+                    start: first.max(capacity),
+                    end: LAST_RANGE_END,
+                    size: self.tail,
+                }),
+            }
+        }
+
+        result
+    }
+
+    fn fill(&mut self, size: S) {
+        for slot in self.sizes.iter_mut() {
+            *slot = size;
+        }
+        self.tail = size;
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let length = self.sizes.len();
+        self.sums[0] = S::ZERO;
+        for position in 1..=length {
+            self.sums[position] = self.sizes[position - 1];
+        }
+
+        for position in 1..=length {
+            let parent = position + (position & position.wrapping_neg());
+            if parent <= length {
+                self.sums[parent] = self.sums[parent].plus(self.sums[position]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FenwickTree;
+    use crate::offset_list::SizeTree;
+
+    #[test]
+    fn test_point_writes() {
+        let mut tree: FenwickTree<u32> = FenwickTree::new(100);
+        tree.write_range(0, 0, 10);
+        tree.write_range(3, 3, 30);
+        tree.write_range(5, 6, 20);
+
+        assert_eq!(tree.offsets_of(&[3, 4, 7, 150], 0), [30, 60, 110, 1540]);
+        assert_eq!(
+            tree.indices_at(&[29, 30, 59, 60, 119, 1550], 0),
+            [2, 3, 3, 4, 7, 151]
+        );
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn test_writes_past_the_capacity() {
+        let mut tree: FenwickTree<u32> = FenwickTree::new(10);
+        tree.write_range(0, 0, 10);
+        tree.write_range(5, 20, 20);
+
+        assert_eq!(tree.offsets_of(&[10, 12], 0), [150, 190]);
+        assert_eq!(tree.indices_at(&[189], 0), [11]);
+    }
+
+    #[test]
+    fn test_matches_the_range_tree() {
+        let mut dense: FenwickTree<u32> = FenwickTree::new(1000);
+        let mut ranges: SizeTree<u32> = SizeTree::new();
+        let mut seed: u32 = 11;
+
+        for _ in 0..500 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let start = (seed >> 8) % 900;
+            let end = start + (seed >> 4) % 50;
+            let size = 10 + (seed >> 20) % 5;
+
+            dense.write_range(start, end, size);
+            ranges.write_range(start, end, size);
+        }
+
+        let indices: Vec<u32> = (0..1200).step_by(7).collect();
+        assert_eq!(
+            dense.offsets_of(&indices, 3),
+            ranges.offsets_of(&indices, 3)
+        );
+
+        let offsets: Vec<u32> = (0..20000).step_by(13).collect();
+        assert_eq!(
+            dense.indices_at(&offsets, 3),
+            ranges.indices_at(&offsets, 3)
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use super::size_tree::SizeTree;
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// An offset list over `f64` sizes, for measurements that keep the fractions
+/// `getBoundingClientRect` reports. Rounding them to whole pixels first lets
+/// the error add up over many items.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FloatOffsetList {
+    tree: SizeTree<f64>,
+    gap: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FloatOffsetList {
+    pub fn new() -> FloatOffsetList {
+        FloatOffsetList {
+            tree: SizeTree::new(),
+            gap: 0.0,
+        }
+    }
+
+    pub fn insert(&mut self, start: u32, end: u32, size: f64) {
+        self.tree.write_range(start, end, size);
+    }
+
+    pub fn set_gap(&mut self, gap: f64) {
+        self.gap = gap;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn offsets_of(&self, indices: &[u32]) -> Vec<f64> {
+        self.tree.offsets_of(indices, self.gap)
+    }
+
+    pub fn indices_at_offsets(&self, offsets: &[f64]) -> Vec<u32> {
+        self.tree.indices_at(offsets, self.gap)
+    }
+
+    pub fn total_size(&self, count: u32) -> f64 {
+        match count {
+            0 => 0.0,
+            count => self.tree.offsets_of(&[count], self.gap)[0] - self.gap,
+        }
+    }
+}
+
+impl Default for FloatOffsetList {
+    fn default() -> Self {
+        FloatOffsetList::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FloatOffsetList;
+
+    #[test]
+    fn test_fractional_sizes_add_up() {
+        let mut list = FloatOffsetList::new();
+        list.insert(0, 0, 20.4);
+
+        assert_eq!(list.offsets_of(&[5]), [102.0]);
+        assert_eq!(list.total_size(10), 204.0);
+        assert_eq!(list.indices_at_offsets(&[101.9, 102.0]), [4, 5]);
+    }
+
+    #[test]
+    fn test_gap() {
+        let mut list = FloatOffsetList::new();
+        list.insert(0, 0, 10.5);
+        list.set_gap(1.5);
+
+        assert_eq!(list.offsets_of(&[2]), [24.0]);
+        assert_eq!(list.total_size(2), 22.5);
+        assert_eq!(list.indices_at_offsets(&[11.0, 12.0]), [0, 1]);
+    }
+}
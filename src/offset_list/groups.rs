@@ -0,0 +1,85 @@
+use super::store::SizeStore;
+use super::transfer::{ascending, Reader, Writer};
+use alloc::vec::Vec;
+
+/// The groups `set_groups` laid out: the flat index of each header, and the
+/// size the headers get until they are measured, once it is known.
+#[derive(Clone, Debug, Default)]
+pub struct Groups {
+    starts: Vec<u32>,
+    header_size: Option<u32>,
+}
+
+impl Groups {
+    /// Groups of `group_counts` items each, every one after its header.
+    pub fn new(group_counts: &[u32], header_size: Option<u32>) -> Groups {
+        let mut starts: Vec<u32> = Vec::with_capacity(group_counts.len());
+        let mut index = 0u32;
+        for count in group_counts {
+            starts.push(index);
+            index = index.saturating_add(count.saturating_add(1));
+        }
+
+        Groups {
+            starts,
+            header_size,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    pub fn starts(&self) -> &[u32] {
+        &self.starts
+    }
+
+    /// The flat index of the header of `group`.
+    pub fn start(&self, group: u32) -> Option<u32> {
+        self.starts.get(group as usize).copied()
+    }
+
+    pub fn set_header_size(&mut self, size: u32) {
+        self.header_size = Some(size);
+    }
+
+    pub fn is_header(&self, index: u32) -> bool {
+        self.starts.binary_search(&index).is_ok()
+    }
+
+    /// The group `index` belongs to, its header included.
+    pub fn group_of(&self, index: u32) -> Option<u32> {
+        match self.starts.partition_point(|start| *start <= index) {
+            0 => None,
+            after => Some(after as u32 - 1),
+        }
+    }
+
+    /// Sizes the headers, over the item sizes written before.
+    pub fn write_into(&self, tree: &mut SizeStore) {
+        if let Some(size) = self.header_size {
+            for start in self.starts.iter() {
+                tree.write_range(*start, *start, size);
+            }
+        }
+    }
+
+    /// The header size, then the headers, for `to_transferable`.
+    pub fn write(&self, writer: &mut Writer) {
+        writer.option(self.header_size);
+        writer.words(&self.starts);
+    }
+
+    pub fn read(reader: &mut Reader) -> Option<Groups> {
+        let header_size = reader.option()?;
+        let starts = reader.words(1)?;
+        if !ascending(starts.iter().copied()) {
+            return None;
+        }
+
+        Some(Groups {
+            starts,
+            header_size,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use super::OffsetList;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// The states of an `OffsetList` before its last structural operations, for
+/// `undo` and `redo`. The states share their sizes with the list until it
+/// writes to them, so every entry holds on to at most one copy of the sizes.
+pub struct History {
+    limit: usize,
+    undo: VecDeque<OffsetList>,
+    redo: Vec<OffsetList>,
+}
+
+impl History {
+    pub fn new(limit: usize) -> History {
+        History {
+            limit,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Keeps the last `limit` states; zero turns the history off.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.undo.len() > limit {
+            self.undo.pop_front();
+        }
+        self.redo.truncate(limit);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Records the state before an operation, which makes the undone ones
+    /// unreachable.
+    pub fn push(&mut self, state: OffsetList) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.undo.push_back(state);
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    /// The state to go back to, if any; `current` becomes the one to redo.
+    pub fn undo(&mut self, current: OffsetList) -> Option<OffsetList> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    pub fn redo(&mut self, current: OffsetList) -> Option<OffsetList> {
+        let state = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(state)
+    }
+}
+
+/// A copy of a list starts with a history of its own, empty.
+impl Clone for History {
+    fn clone(&self) -> History {
+        History::new(self.limit)
+    }
+}
@@ -0,0 +1,317 @@
+//! Query results handed to JS.
+//!
+//! Every `Item` is a wasm-owned object: JS has to call `free()` on it, or the
+//! memory stays allocated for the lifetime of the module. Returning a
+//! `Vec<Item>` therefore means one `free()` per element. The `*_list`
+//! queries return a single `ItemList` instead, which owns its items and is
+//! released with one `free()`. Its `indices()`, `sizes()`, `offsets()`,
+//! `tags()`, `keys()`, `generations()` and `positions_in_set()` accessors
+//! copy the data into plain typed arrays that need no freeing.
+//!
+//! `OffsetList::range_into_buffer` goes further and writes into an
+//! `ItemBuffer` that JS keeps around, which allocates nothing once it is
+//! large enough. Its `view()` reads the words in place, until the next call
+//! into the module.
+//!
+//! An `ItemList` is stamped with the list `version` and `config_hash` it was
+//! computed from; `is_current` tells whether it is still valid.
+//!
+//! With the `leak-check` feature, `live_items()` and `live_item_lists()`
+//! count the objects that are still allocated, so tests on the JS side can
+//! assert that nothing leaks.
+
+use super::OffsetList;
+use alloc::vec::Vec;
+#[cfg(any(test, all(feature = "leak-check", feature = "std")))]
+use core::cell::Cell;
+#[cfg(all(feature = "leak-check", not(any(feature = "std", test))))]
+use core::sync::atomic::{AtomicU32, Ordering};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(any(test, all(feature = "leak-check", feature = "std")))]
+std::thread_local! {
+    static LIVE_ITEMS: Cell<u32> = const { Cell::new(0) };
+    static LIVE_ITEM_LISTS: Cell<u32> = const { Cell::new(0) };
+}
+
+// without threads, nothing to keep apart
+#[cfg(all(feature = "leak-check", not(any(feature = "std", test))))]
+static LIVE_ITEMS: AtomicU32 = AtomicU32::new(0);
+#[cfg(all(feature = "leak-check", not(any(feature = "std", test))))]
+static LIVE_ITEM_LISTS: AtomicU32 = AtomicU32::new(0);
+
+/// Adds `delta` to a count of live objects and returns the new count.
+#[cfg(any(test, all(feature = "leak-check", feature = "std")))]
+fn count(counter: &'static std::thread::LocalKey<Cell<u32>>, delta: i32) -> u32 {
+    counter.with(|count| {
+        count.set(count.get().wrapping_add_signed(delta));
+        count.get()
+    })
+}
+
+#[cfg(all(feature = "leak-check", not(any(feature = "std", test))))]
+fn count(counter: &AtomicU32, delta: i32) -> u32 {
+    let delta = delta as u32;
+    counter
+        .fetch_add(delta, Ordering::Relaxed)
+        .wrapping_add(delta)
+}
+
+#[cfg(any(test, feature = "leak-check"))]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn live_items() -> u32 {
+    count(&LIVE_ITEMS, 0)
+}
+
+#[cfg(any(test, feature = "leak-check"))]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn live_item_lists() -> u32 {
+    count(&LIVE_ITEM_LISTS, 0)
+}
+
+/// `tag` is the value attached to the item's range with
+/// `OffsetList::insert_tagged`, zero for none. `gap` is the space after the
+/// item, which `size` leaves out.
+///
+/// `key` is the key `OffsetList::set_keys` gave the item, or its index.
+/// `generation` is the measurement that last sized it, while the list
+/// tracks them; an item that comes back with the same key and generation
+/// has only moved.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Item {
+    pub size: u32,
+    pub offset: u32,
+    pub index: u32,
+    pub tag: u32,
+    pub gap: u32,
+    pub key: u32,
+    pub generation: u32,
+}
+
+impl Item {
+    pub fn new(index: u32, size: u32, offset: u32) -> Item {
+        #[cfg(any(test, feature = "leak-check"))]
+        count(&LIVE_ITEMS, 1);
+        Item {
+            size,
+            offset,
+            index,
+            tag: 0,
+            gap: 0,
+            key: index,
+            generation: 0,
+        }
+    }
+
+    pub fn with_tag(mut self, tag: u32) -> Item {
+        self.tag = tag;
+        self
+    }
+
+    pub fn with_gap(mut self, gap: u32) -> Item {
+        self.gap = gap;
+        self
+    }
+
+    pub fn with_identity(mut self, key: u32, generation: u32) -> Item {
+        self.key = key;
+        self.generation = generation;
+        self
+    }
+
+    /// A copy of the item, moved to `offset`.
+    pub fn at_offset(&self, offset: u32) -> Item {
+        Item::new(self.index, self.size, offset)
+            .with_tag(self.tag)
+            .with_gap(self.gap)
+            .with_identity(self.key, self.generation)
+    }
+}
+
+#[cfg(any(test, feature = "leak-check"))]
+impl Drop for Item {
+    fn drop(&mut self) {
+        count(&LIVE_ITEMS, -1);
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ItemList {
+    items: Vec<Item>,
+    version: u32,
+    config_hash: u32,
+}
+
+impl ItemList {
+    pub fn new(items: Vec<Item>, version: u32, config_hash: u32) -> ItemList {
+        #[cfg(any(test, feature = "leak-check"))]
+        count(&LIVE_ITEM_LISTS, 1);
+        ItemList {
+            items,
+            version,
+            config_hash,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "leak-check"))]
+impl Drop for ItemList {
+    fn drop(&mut self) {
+        count(&LIVE_ITEM_LISTS, -1);
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ItemList {
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn config_hash(&self) -> u32 {
+        self.config_hash
+    }
+
+    /// Whether `list` is still in the state this result was computed from.
+    pub fn is_current(&self, list: &OffsetList) -> bool {
+        self.version == list.version() && self.config_hash == list.config_hash()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// A copy of the item at `position`. The copy is a separate wasm object
+    /// that has to be freed on its own.
+    pub fn get(&self, position: usize) -> Option<Item> {
+        self.items
+            .get(position)
+            .map(|item| item.at_offset(item.offset))
+    }
+
+    pub fn indices(&self) -> Vec<u32> {
+        self.items.iter().map(|item| item.index).collect()
+    }
+
+    pub fn sizes(&self) -> Vec<u32> {
+        self.items.iter().map(|item| item.size).collect()
+    }
+
+    pub fn offsets(&self) -> Vec<u32> {
+        self.items.iter().map(|item| item.offset).collect()
+    }
+
+    pub fn tags(&self) -> Vec<u32> {
+        self.items.iter().map(|item| item.tag).collect()
+    }
+
+    pub fn keys(&self) -> Vec<u32> {
+        self.items.iter().map(|item| item.key).collect()
+    }
+
+    pub fn generations(&self) -> Vec<u32> {
+        self.items.iter().map(|item| item.generation).collect()
+    }
+
+    /// The `aria-posinset` of each item, its index plus one; the set size
+    /// is `OffsetList::aria_set_size`.
+    pub fn positions_in_set(&self) -> Vec<u32> {
+        self.items
+            .iter()
+            .map(|item| item.index.saturating_add(1))
+            .collect()
+    }
+}
+
+/// The words of the items a query wrote, four an item: the index, size,
+/// offset and tag. The buffer keeps its memory between queries.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Default)]
+pub struct ItemBuffer {
+    words: Vec<u32>,
+}
+
+impl ItemBuffer {
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    pub fn push(&mut self, item: &Item) {
+        self.words
+            .extend_from_slice(&[item.index, item.size, item.offset, item.tag]);
+    }
+
+    pub fn words(&self) -> &[u32] {
+        &self.words
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ItemBuffer {
+    pub fn new() -> ItemBuffer {
+        ItemBuffer::default()
+    }
+
+    /// The number of items.
+    pub fn len(&self) -> usize {
+        self.words.len() / 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// A `Uint32Array` over the words in wasm memory, without a copy. Any
+    /// allocation in the module can grow the memory, which detaches the view
+    /// and leaves it empty, and any call into the module can allocate, the
+    /// next query included. So read it before the next call into the module,
+    /// or keep a copy with `slice()`.
+    #[cfg(feature = "wasm")]
+    pub fn view(&self) -> js_sys::Uint32Array {
+        // SAFETY: nothing allocates between making the view and handing it to
+        // JS, and past that, JS is told to read it before calling in again
+        unsafe { js_sys::Uint32Array::view(&self.words) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{live_item_lists, live_items, Item, ItemList};
+
+    #[test]
+    fn test_items_are_counted() {
+        let before = live_items();
+        let items = vec![Item::new(0, 10, 0), Item::new(1, 10, 10)];
+        assert_eq!(live_items(), before + 2);
+
+        drop(items);
+        assert_eq!(live_items(), before);
+    }
+
+    #[test]
+    fn test_list_owns_its_items() {
+        let items_before = live_items();
+        let lists_before = live_item_lists();
+
+        let list = ItemList::new(vec![Item::new(0, 10, 0), Item::new(1, 20, 10)], 0, 0);
+        assert_eq!(live_item_lists(), lists_before + 1);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.indices(), [0, 1]);
+        assert_eq!(list.sizes(), [10, 20]);
+        assert_eq!(list.offsets(), [0, 10]);
+
+        let copy = list.get(1).expect("second item is here");
+        assert_eq!(copy.offset, 10);
+        assert!(list.get(2).is_none());
+        assert_eq!(live_items(), items_before + 3);
+
+        drop(copy);
+        drop(list);
+        assert_eq!(live_items(), items_before);
+        assert_eq!(live_item_lists(), lists_before);
+    }
+}
@@ -0,0 +1,55 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// The keys of the items, in index order, to follow the items through a
+/// reordering of the data.
+#[derive(Clone, Debug, Default)]
+pub struct KeyRegistry {
+    keys: Vec<u32>,
+    /// The first index of every key.
+    indices: BTreeMap<u32, u32>,
+}
+
+impl KeyRegistry {
+    pub fn new(keys: &[u32]) -> KeyRegistry {
+        let mut indices = BTreeMap::new();
+        for (index, key) in keys.iter().enumerate() {
+            indices.entry(*key).or_insert(index as u32);
+        }
+
+        KeyRegistry {
+            keys: keys.to_vec(),
+            indices,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.keys.len() as u32
+    }
+
+    pub fn keys(&self) -> &[u32] {
+        &self.keys
+    }
+
+    pub fn key_of(&self, index: u32) -> Option<u32> {
+        self.keys.get(index as usize).copied()
+    }
+
+    pub fn index_of(&self, key: u32) -> Option<u32> {
+        self.indices.get(&key).copied()
+    }
+
+    /// The keys after the item at `from` moved to `to`. Both have to be
+    /// within the keys.
+    pub fn moved(&self, from: u32, to: u32) -> KeyRegistry {
+        let mut keys = self.keys.clone();
+        let key = keys.remove(from as usize);
+        keys.insert(to as usize, key);
+
+        KeyRegistry::new(&keys)
+    }
+}
@@ -0,0 +1,13 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Which measurement wins when `OffsetList::merge` finds an item measured
+/// in both lists. `PreferNewer` takes the merged-in one, as the latest;
+/// `PreferLarger` keeps the larger of the two, which never collapses an item
+/// under its content.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergePolicy {
+    PreferNewer,
+    PreferLarger,
+}
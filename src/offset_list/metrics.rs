@@ -0,0 +1,56 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How fragmented the size tree is. With a size quantum set,
+/// `ranges_before_quantization` and `ranges_after_quantization` report the
+/// range count around the last re-bucketing of the existing sizes.
+/// `size_rebuilds` counts how often the sizes of the unmeasured items were
+/// reset, after a change of the default size, the estimate or the types.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ListMetrics {
+    pub range_count: u32,
+    pub quantized_inserts: u32,
+    pub ranges_before_quantization: u32,
+    pub ranges_after_quantization: u32,
+    pub size_rebuilds: u32,
+}
+
+/// The entries kept by each internal structure of an `OffsetList`, with the
+/// bytes they take up. The byte counts are estimates: the size nodes count
+/// their whole arena, spare slots included; the maps count their entries
+/// packed into B-tree nodes, without the allocator overhead.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryStats {
+    pub size_nodes: u32,
+    pub size_bytes: u32,
+    pub measured_ranges: u32,
+    pub measured_bytes: u32,
+    pub type_entries: u32,
+    pub type_bytes: u32,
+    pub tag_entries: u32,
+    pub tag_bytes: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MemoryStats {
+    pub fn total_bytes(&self) -> u32 {
+        self.size_bytes
+            .saturating_add(self.measured_bytes)
+            .saturating_add(self.type_bytes)
+            .saturating_add(self.tag_bytes)
+    }
+}
+
+/// The entries a `BTreeMap` node holds at most.
+const MAP_NODE_ENTRIES: usize = 11;
+
+/// Roughly what a `BTreeMap<K, V>` of `entries` entries allocates: its nodes
+/// full, each with the parent link and lengths next to the entries.
+pub fn map_bytes<K, V>(entries: usize) -> usize {
+    let node = MAP_NODE_ENTRIES * (core::mem::size_of::<K>() + core::mem::size_of::<V>())
+        + core::mem::size_of::<usize>()
+        + 2 * core::mem::size_of::<u16>();
+    entries.div_ceil(MAP_NODE_ENTRIES) * node
+}
@@ -0,0 +1,120 @@
+use super::OffsetList;
+use alloc::vec::Vec;
+use core::cmp;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The list squeezed into a strip of `height` pixels, for editor-style
+/// minimaps. `bands` are runs of equally sized items, flattened into
+/// `[first_index, last_index, top, height, ...]`; runs under a pixel tall
+/// merge into the next one. The viewport indicator is at `viewport_top`,
+/// `viewport_height` tall, in the pixels of the strip.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinimapProjection {
+    pub scale: f64,
+    pub viewport_top: f64,
+    pub viewport_height: f64,
+    bands: Vec<f64>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MinimapProjection {
+    pub fn bands(&self) -> Vec<f64> {
+        self.bands.clone()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OffsetList {
+    /// Projects `count` items and the viewport onto a strip `height` pixels
+    /// tall. Short lists are not stretched to fill it.
+    pub fn minimap(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        height: f64,
+        count: u32,
+    ) -> MinimapProjection {
+        let total = f64::from(self.total_size(count));
+        let scale = match total > height {
+            true => height / total,
+            false => 1.0,
+        };
+
+        let mut bands: Vec<f64> = Vec::new();
+        if count > 0 && !self.sizes().is_empty() {
+            let runs: Vec<(u32, u32)> = self
+                .sizes()
+                .ranges(0, count - 1)
+                .into_iter()
+                .map(|(range, _)| (range.start, cmp::min(range.end, count - 1)))
+                .filter(|(start, end)| start <= end)
+                .collect();
+            let edges: Vec<u32> = runs
+                .iter()
+                .flat_map(|(start, end)| [*start, end + 1])
+                .collect();
+            let offsets = self.offsets_of(&edges);
+
+            let mut band_start: Option<(u32, f64)> = None;
+            for (position, (start, end)) in runs.iter().enumerate() {
+                let (first, top) =
+                    band_start.unwrap_or((*start, f64::from(offsets[position * 2]) * scale));
+                let bottom = f64::from(offsets[position * 2 + 1]) * scale;
+
+                if bottom - top < 1.0 && position + 1 < runs.len() {
+                    band_start = Some((first, top));
+                    continue;
+                }
+                band_start = None;
+                bands.extend([f64::from(first), f64::from(*end), top, bottom - top]);
+            }
+        }
+
+        MinimapProjection {
+            scale,
+            viewport_top: scroll_top.clamp(0.0, total) * scale,
+            viewport_height: viewport_height.min(total) * scale,
+            bands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetList;
+
+    #[test]
+    fn test_minimap() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(100, 199, 30);
+
+        let minimap = list.minimap(1000.0, 500.0, 100.0, 300);
+        assert_eq!(minimap.scale, 0.02);
+        assert_eq!(
+            (minimap.viewport_top, minimap.viewport_height),
+            (20.0, 10.0)
+        );
+        assert_eq!(
+            minimap.bands(),
+            [0.0, 99.0, 0.0, 20.0, 100.0, 199.0, 20.0, 60.0, 200.0, 299.0, 80.0, 20.0]
+        );
+    }
+
+    #[test]
+    fn test_minimap_merges_thin_bands() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        for index in (1..100).step_by(2) {
+            list.insert(index, index, 20);
+        }
+
+        let minimap = list.minimap(0.0, 100.0, 15.0, 100);
+        assert_eq!(minimap.viewport_height, 1.0);
+        assert_eq!(minimap.bands().len(), 4 * 15);
+        assert_eq!(minimap.bands()[0..2], [0.0, 6.0]);
+        assert_eq!(minimap.bands()[minimap.bands().len() - 3], 99.0);
+    }
+}
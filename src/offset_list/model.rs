@@ -0,0 +1,118 @@
+//! Differential tests: `OffsetList` against a plain `Vec` of sizes, over
+//! random sequences of measurements. The joins and overrides of the size
+//! tree only have to agree with writing the sizes one by one.
+
+use super::OffsetList;
+use proptest::prelude::*;
+
+/// The model tracks the items `0..ITEMS`. The writes stop short of the last
+/// one, so that it stands for all the items after it.
+const ITEMS: u32 = 120;
+
+#[derive(Default)]
+struct Model {
+    sizes: Vec<u32>,
+}
+
+impl Model {
+    fn insert(&mut self, start: u32, end: u32, size: u32) {
+        // the first measurement sizes every item
+        if self.sizes.is_empty() {
+            self.sizes = vec![size; ITEMS as usize];
+            return;
+        }
+
+        for slot in &mut self.sizes[start as usize..=end as usize] {
+            *slot = size;
+        }
+    }
+
+    fn size(&self, index: u32) -> u32 {
+        self.sizes[index.min(ITEMS - 1) as usize]
+    }
+
+    fn offset_of(&self, index: u32) -> u32 {
+        (0..index).map(|index| self.size(index)).sum()
+    }
+
+    /// The items from the one holding `start_offset`, or `min_index` when
+    /// it comes later, up to the last one starting at or before
+    /// `end_offset`, `max_index` at most; a start past `max_index` starts
+    /// there.
+    fn range(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+    ) -> Vec<(u32, u32, u32)> {
+        let mut index = 0;
+        while index < max_index && self.offset_of(index + 1) <= start_offset {
+            index += 1;
+        }
+
+        let mut result = Vec::new();
+        for index in index.max(min_index)..=max_index {
+            let offset = self.offset_of(index);
+            if offset > end_offset {
+                break;
+            }
+            result.push((index, self.size(index), offset));
+        }
+        result
+    }
+}
+
+fn inserts() -> impl Strategy<Value = Vec<(u32, u32, u32)>> {
+    let insert = (0..ITEMS - 1, 0..20u32, 1..50u32)
+        .prop_map(|(start, length, size)| (start, (start + length).min(ITEMS - 2), size));
+    proptest::collection::vec(insert, 1..40)
+}
+
+fn replay(inserts: &[(u32, u32, u32)]) -> (OffsetList, Model) {
+    let mut list = OffsetList::new();
+    let mut model = Model::default();
+    for (start, end, size) in inserts {
+        list.insert(*start, *end, *size);
+        model.insert(*start, *end, *size);
+    }
+    (list, model)
+}
+
+proptest! {
+    #[test]
+    fn test_offsets_match(inserts in inserts()) {
+        let (list, model) = replay(&inserts);
+
+        let indices: Vec<u32> = (0..ITEMS + 10).collect();
+        let expected: Vec<u32> = indices.iter().map(|index| model.offset_of(*index)).collect();
+        prop_assert_eq!(list.offsets_of(&indices), expected);
+
+        for index in indices {
+            prop_assert_eq!(list.offset_of(index), model.offset_of(index));
+            prop_assert_eq!(list.item_at(index).size, model.size(index));
+        }
+    }
+
+    #[test]
+    fn test_ranges_match(
+        inserts in inserts(),
+        start_offset in 0..3000u32,
+        length in 0..500u32,
+        min_index in prop_oneof![Just(0), 0..ITEMS],
+        span in prop_oneof![Just(ITEMS), 0..ITEMS],
+    ) {
+        let (list, model) = replay(&inserts);
+        let end_offset = start_offset + length;
+        let max_index = min_index + span;
+
+        // only an empty list is an error, with the indices in order
+        if let Ok(items) = list.try_range(start_offset, end_offset, min_index, max_index) {
+            let items: Vec<(u32, u32, u32)> = items
+                .iter()
+                .map(|item| (item.index, item.size, item.offset))
+                .collect();
+            prop_assert_eq!(items, model.range(start_offset, end_offset, min_index, max_index));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use super::cells::{Counter, Total};
+
+/// How often an `OffsetList` did the work that costs it the most, and the
+/// milliseconds it spent on it, for telling a slow sizing engine from a slow
+/// render. `inserts` counts the measurements written, merges included;
+/// `removals` the pruning passes; `rebuilds` the full lay outs of the sizes;
+/// `range_queries` the calls for the items of a range. The times stay at zero
+/// where there is no clock, in `no_std` builds.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PerfStats {
+    pub inserts: u32,
+    pub insert_ms: f64,
+    pub removals: u32,
+    pub removal_ms: f64,
+    pub rebuilds: u32,
+    pub rebuild_ms: f64,
+    pub range_queries: u32,
+    pub range_query_ms: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    Insert,
+    Removal,
+    Rebuild,
+    RangeQuery,
+}
+
+/// The counts and times behind `PerfStats`, kept in cells so that a query
+/// records itself without taking a lock, and concurrent reads with `sync`
+/// don't queue up behind one another.
+#[derive(Clone, Debug, Default)]
+pub struct PerfCounters {
+    counts: [Counter; 4],
+    totals: [Total; 4],
+}
+
+impl PerfCounters {
+    pub fn record(&self, operation: Operation, stopwatch: Stopwatch) {
+        let slot = operation as usize;
+        self.counts[slot].bump();
+        self.totals[slot].add(stopwatch.elapsed_ms());
+    }
+
+    pub fn stats(&self) -> PerfStats {
+        let count = |operation: Operation| self.counts[operation as usize].get();
+        let total = |operation: Operation| self.totals[operation as usize].get();
+        PerfStats {
+            inserts: count(Operation::Insert),
+            insert_ms: total(Operation::Insert),
+            removals: count(Operation::Removal),
+            removal_ms: total(Operation::Removal),
+            rebuilds: count(Operation::Rebuild),
+            rebuild_ms: total(Operation::Rebuild),
+            range_queries: count(Operation::RangeQuery),
+            range_query_ms: total(Operation::RangeQuery),
+        }
+    }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance)]
+    fn now() -> f64;
+}
+
+/// Measures one operation: `performance.now()` in the browser, `Instant`
+/// on native targets.
+#[derive(Clone, Copy, Debug)]
+pub struct Stopwatch {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    started: f64,
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    started: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub fn start() -> Stopwatch {
+        Stopwatch {
+            #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+            started: now(),
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            started: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn elapsed_ms(&self) -> f64 {
+        now() - self.started
+    }
+
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.started.elapsed().as_secs_f64() * 1000.0
+    }
+
+    #[cfg(not(any(
+        all(feature = "wasm", target_arch = "wasm32"),
+        all(feature = "std", not(target_arch = "wasm32"))
+    )))]
+    pub fn elapsed_ms(&self) -> f64 {
+        0.0
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(not(any(feature = "std", test)))]
+use crate::float::Float;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How fractional offsets, from scaling, are snapped to the pixel grid.
+/// Snapping every edge the same way keeps the neighbouring items flush.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    Round,
+    Floor,
+    Ceil,
+    None,
+}
+
+impl Rounding {
+    /// Snaps `value` to a multiple of `step`. `None` leaves it as is.
+    pub fn snap(self, value: f64, step: f64) -> f64 {
+        let steps = value / step;
+        let snapped = match self {
+            Rounding::Round => steps.round(),
+            Rounding::Floor => steps.floor(),
+            Rounding::Ceil => steps.ceil(),
+            Rounding::None => return value,
+        };
+
+        snapped * step
+    }
+
+    pub fn to_u32(self, value: f64) -> u32 {
+        match self {
+            Rounding::Round => value.round() as u32,
+            Rounding::Floor | Rounding::None => value.floor() as u32,
+            Rounding::Ceil => value.ceil() as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rounding;
+
+    #[test]
+    fn test_snap() {
+        assert_eq!(Rounding::Round.snap(10.3, 0.5), 10.5);
+        assert_eq!(Rounding::Floor.snap(10.3, 0.5), 10.0);
+        assert_eq!(Rounding::Ceil.snap(10.1, 1.0), 11.0);
+        assert_eq!(Rounding::None.snap(10.3, 0.5), 10.3);
+    }
+
+    #[test]
+    fn test_to_u32() {
+        assert_eq!(Rounding::Round.to_u32(10.5), 11);
+        assert_eq!(Rounding::Floor.to_u32(10.5), 10);
+        assert_eq!(Rounding::Ceil.to_u32(10.1), 11);
+        assert_eq!(Rounding::None.to_u32(10.9), 10);
+    }
+}
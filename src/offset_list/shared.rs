@@ -0,0 +1,90 @@
+use super::OffsetList;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// An `OffsetList` behind a read/write lock, for a measurement thread to
+/// write the sizes while a render thread queries the ranges. Clones share the
+/// list; any number of readers go in at once, a writer waits for them.
+#[derive(Clone, Default)]
+pub struct SharedOffsetList {
+    list: Arc<RwLock<OffsetList>>,
+}
+
+impl SharedOffsetList {
+    pub fn new(list: OffsetList) -> SharedOffsetList {
+        SharedOffsetList {
+            list: Arc::new(RwLock::new(list)),
+        }
+    }
+
+    /// The list for queries, held until the guard is dropped.
+    pub fn read(&self) -> RwLockReadGuard<'_, OffsetList> {
+        self.list.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The list for writes, once the readers are done with it.
+    pub fn write(&self) -> RwLockWriteGuard<'_, OffsetList> {
+        self.list.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedOffsetList;
+    use crate::offset_list::OffsetList;
+    use std::thread;
+
+    #[test]
+    fn test_the_list_is_send_and_sync() {
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<OffsetList>();
+        assert_sync::<SharedOffsetList>();
+    }
+
+    #[test]
+    fn test_measures_while_another_thread_reads() {
+        let mut list = OffsetList::new();
+        list.set_default_size(10);
+        let shared = SharedOffsetList::new(list);
+
+        let measuring = shared.clone();
+        let writer = thread::spawn(move || {
+            for index in 0..100 {
+                measuring.write().insert(index, index, 20);
+            }
+        });
+
+        let reading = shared.clone();
+        let reader = thread::spawn(move || {
+            for _ in 0..100 {
+                let items = reading.read().range(0, 200, 0, 999);
+                assert!(items.iter().all(|item| item.size == 10 || item.size == 20));
+            }
+        });
+
+        writer.join().unwrap();
+        reader.join().unwrap();
+        assert_eq!(shared.read().offsets_of(&[100]), [2000]);
+    }
+
+    #[test]
+    fn test_concurrent_readers_rebuild_the_sizes_once() {
+        let mut list = OffsetList::new();
+        list.set_default_size(10);
+        list.insert(5, 5, 20);
+        list.set_default_size(30);
+        let rebuilds = list.perf_stats().rebuilds;
+        let shared = SharedOffsetList::new(list);
+
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let reading = shared.clone();
+                thread::spawn(move || reading.read().offsets_of(&[6]))
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), [170]);
+        }
+
+        assert_eq!(shared.read().perf_stats().rebuilds, rebuilds + 1);
+    }
+}
@@ -0,0 +1,119 @@
+//! Offsets that can go negative: a rubber-band overscroll above the first
+//! item, a pull-to-refresh zone, or items prepended above it before the
+//! scroll position catches up. `set_origin` puts item 0 somewhere other than
+//! zero, and the `signed_*` queries take and answer `i32` offsets from the
+//! top of the scroller instead of wrapping around.
+
+use super::{Item, OffsetList};
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OffsetList {
+    /// Where item 0 starts for the `signed_*` queries; negative while the
+    /// items prepended above it push it up.
+    pub fn set_origin(&mut self, origin: i32) {
+        self.version = self.version.wrapping_add(1);
+        self.origin = origin;
+    }
+
+    pub fn origin(&self) -> i32 {
+        self.origin
+    }
+
+    /// `offsets_of`, from the origin.
+    pub fn signed_offsets_of(&self, indices: &[u32]) -> Vec<i32> {
+        self.offsets_of(indices)
+            .into_iter()
+            .map(|offset| self.signed(offset))
+            .collect()
+    }
+
+    /// `indices_at_offsets` for offsets from the origin. The ones before
+    /// item 0 belong to it.
+    pub fn signed_indices_at_offsets(&self, offsets: &[i32]) -> Vec<u32> {
+        let offsets: Vec<u32> = offsets
+            .iter()
+            .map(|offset| self.unsigned(*offset).unwrap_or(0))
+            .collect();
+
+        self.indices_at_offsets(&offsets)
+    }
+
+    /// `range` for offsets from the origin, empty when all of them are
+    /// before item 0. The items keep the offsets `range` gives them; add
+    /// the origin for where they sit.
+    pub fn signed_range(
+        &self,
+        start_offset: i32,
+        end_offset: i32,
+        min_index: u32,
+        max_index: u32,
+    ) -> Vec<Item> {
+        let end_offset = match self.unsigned(end_offset) {
+            None => return Vec::new(),
+            Some(end_offset) => end_offset,
+        };
+        let start_offset = self.unsigned(start_offset).unwrap_or(0);
+
+        self.range(start_offset, end_offset, min_index, max_index)
+    }
+}
+
+impl OffsetList {
+    fn signed(&self, offset: u32) -> i32 {
+        let offset = i64::from(offset) + i64::from(self.origin);
+        offset.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+    }
+
+    /// The unsigned offset of a signed one, none before item 0.
+    fn unsigned(&self, offset: i32) -> Option<u32> {
+        let offset = i64::from(offset) - i64::from(self.origin);
+        if offset < 0 {
+            return None;
+        }
+
+        Some(offset.min(i64::from(u32::MAX)) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetList;
+
+    #[test]
+    fn test_signed_offsets() {
+        let mut list = OffsetList::new();
+        list.set_default_size(10);
+        list.set_origin(-25);
+
+        assert_eq!(list.signed_offsets_of(&[0, 2, 5]), [-25, -5, 25]);
+        assert_eq!(
+            list.signed_indices_at_offsets(&[-100, -25, -16, 0]),
+            [0, 0, 0, 2]
+        );
+
+        let indices: Vec<u32> = list
+            .signed_range(-10, 10, 0, 100)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(indices, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_overscroll() {
+        let mut list = OffsetList::new();
+        list.set_default_size(10);
+
+        // pulled 40px past the top, with a 30px viewport
+        assert!(list.signed_range(-40, -10, 0, 100).is_empty());
+        let indices: Vec<u32> = list
+            .signed_range(-20, 10, 0, 100)
+            .iter()
+            .map(|item| item.index)
+            .collect();
+        assert_eq!(indices, [0, 1]);
+    }
+}
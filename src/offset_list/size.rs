@@ -0,0 +1,92 @@
+#[cfg(not(any(feature = "std", test)))]
+use crate::float::Float;
+use core::fmt::Debug;
+use core::ops::{Add, Sub};
+
+/// The numeric type sizes and offsets are kept in. Integers give exact sums;
+/// `f64` takes fractional measurements as they come from the DOM. Integer
+/// offsets past the largest value stop there instead of wrapping around.
+///
+/// The tree adds sizes with `plus` and `times` rather than `+`, so that sizes
+/// coming from JS can't overflow it.
+pub trait Size:
+    Copy + Debug + PartialEq + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Send + Sync
+{
+    const ZERO: Self;
+
+    /// `count` items of this size, back to back.
+    fn times(self, count: u32) -> Self;
+
+    /// `self + other`, saturating for the integers.
+    fn plus(self, other: Self) -> Self;
+
+    /// How many whole items of `size` fit into `self`. `size` isn't zero.
+    fn fits(self, size: Self) -> u32;
+}
+
+impl Size for u32 {
+    const ZERO: u32 = 0;
+
+    fn times(self, count: u32) -> u32 {
+        self.saturating_mul(count)
+    }
+
+    fn plus(self, other: u32) -> u32 {
+        self.saturating_add(other)
+    }
+
+    fn fits(self, size: u32) -> u32 {
+        self / size
+    }
+}
+
+impl Size for u64 {
+    const ZERO: u64 = 0;
+
+    fn times(self, count: u32) -> u64 {
+        self.saturating_mul(u64::from(count))
+    }
+
+    fn plus(self, other: u64) -> u64 {
+        self.saturating_add(other)
+    }
+
+    fn fits(self, size: u64) -> u32 {
+        (self / size).min(u64::from(u32::MAX)) as u32
+    }
+}
+
+impl Size for f64 {
+    const ZERO: f64 = 0.0;
+
+    fn times(self, count: u32) -> f64 {
+        self * f64::from(count)
+    }
+
+    fn plus(self, other: f64) -> f64 {
+        self + other
+    }
+
+    fn fits(self, size: f64) -> u32 {
+        (self / size).floor() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Size;
+
+    #[test]
+    fn test_fits() {
+        assert_eq!(25u32.fits(10), 2);
+        assert_eq!(25u64.fits(10), 2);
+        assert_eq!(25.5f64.fits(10.5), 2);
+    }
+
+    #[test]
+    fn test_saturates() {
+        assert_eq!(u32::MAX.times(2), u32::MAX);
+        assert_eq!(u32::MAX.plus(1), u32::MAX);
+        assert_eq!(u64::MAX.plus(1), u64::MAX);
+    }
+}
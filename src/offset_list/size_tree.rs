@@ -0,0 +1,924 @@
+use super::size::Size;
+use super::tree_utils::{Range, LAST_RANGE_END};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use core::mem;
+
+/// The id of the missing child.
+const NIL: u32 = u32::MAX;
+
+/// The bulk builds of smaller subtrees stay on the calling thread.
+#[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+const PARALLEL_NODES: usize = 1 << 14;
+
+/// A range of equally sized items, starting at `start` and running up to the
+/// next node. The rest of the fields aggregate the subtree under the node.
+#[derive(Clone, Copy, Debug)]
+struct Node<S: Size> {
+    start: u32,
+    size: S,
+    level: u8,
+    left: u32,
+    right: u32,
+    /// The start of the leftmost range in the subtree.
+    first: u32,
+    /// The start of the rightmost range in the subtree and its size.
+    last: u32,
+    last_size: S,
+    /// The distance from the start of `first` to the start of `last`.
+    span: S,
+    count: u32,
+}
+
+impl<S: Size> Node<S> {
+    fn new(start: u32, size: S) -> Node<S> {
+        Node {
+            start,
+            size,
+            level: 1,
+            left: NIL,
+            right: NIL,
+            first: start,
+            last: start,
+            last_size: size,
+            span: S::ZERO,
+            count: 1,
+        }
+    }
+
+    /// The distance from the start of the subtree to the start of the node.
+    fn left_span(&self, left: Option<&Node<S>>) -> S {
+        match left {
+            None => S::ZERO,
+            Some(left) => left.span.plus(left.last_size.times(self.start - left.last)),
+        }
+    }
+
+    /// Recomputes the aggregates from the children.
+    fn aggregate(&mut self, left: Option<&Node<S>>, right: Option<&Node<S>>) {
+        let mut span = self.left_span(left);
+        let mut count = 1;
+
+        let first = match left {
+            None => self.start,
+            Some(left) => {
+                count += left.count;
+                left.first
+            }
+        };
+
+        let (last, last_size) = match right {
+            None => (self.start, self.size),
+            Some(right) => {
+                span = span
+                    .plus(self.size.times(right.first - self.start))
+                    .plus(right.span);
+                count += right.count;
+                (right.last, right.last_size)
+            }
+        };
+
+        self.first = first;
+        self.last = last;
+        self.last_size = last_size;
+        self.span = span;
+        self.count = count;
+    }
+}
+
+/// Links `nodes`, sorted by their starts, into a balanced tree and answers
+/// the id of its root, `base` being the id of the first node. The middle
+/// node roots each span, with the smaller half on its left, so the left
+/// child is always one level below and no two right links stay level.
+fn build<S: Size>(nodes: &mut [Node<S>], base: u32) -> u32 {
+    if nodes.is_empty() {
+        return NIL;
+    }
+
+    let middle = (nodes.len() - 1) / 2;
+    let (left, rest) = nodes.split_at_mut(middle);
+    let (node, right) = rest.split_first_mut().expect("the span isn't empty");
+    let right_base = base + middle as u32 + 1;
+    let (left_root, right_root) = join(
+        left.len() + right.len(),
+        || build(left, base),
+        || build(right, right_base),
+    );
+
+    let left_node = left.get(left_root.wrapping_sub(base) as usize);
+    let right_node = right.get(right_root.wrapping_sub(right_base) as usize);
+    node.left = left_root;
+    node.right = right_root;
+    node.level = left_node.map_or(0, |left| left.level) + 1;
+    node.aggregate(left_node, right_node);
+
+    base + middle as u32
+}
+
+/// Builds the two halves of `nodes` nodes, on the rayon pool once they
+/// are large enough.
+#[cfg(all(feature = "rayon", not(target_arch = "wasm32")))]
+fn join<A: Send, B: Send>(
+    nodes: usize,
+    left: impl FnOnce() -> A + Send,
+    right: impl FnOnce() -> B + Send,
+) -> (A, B) {
+    match nodes >= PARALLEL_NODES {
+        true => rayon::join(left, right),
+        false => (left(), right()),
+    }
+}
+
+#[cfg(not(all(feature = "rayon", not(target_arch = "wasm32"))))]
+fn join<A, B>(_nodes: usize, left: impl FnOnce() -> A, right: impl FnOnce() -> B) -> (A, B) {
+    (left(), right())
+}
+
+/// The sizes of a list as ranges of equally sized items, generic over the
+/// numeric type. `OffsetList` layers its configuration (gap, header,
+/// padding, scale) over a `SizeTree<u32>`.
+///
+/// The ranges are kept in an AA tree keyed by their first index; a range runs
+/// up to the next one, and the last one to `u32::MAX`. Every node aggregates
+/// the extent of its subtree, so offsets are resolved on the way down instead
+/// of being stored per range.
+#[derive(Clone, Debug)]
+pub struct SizeTree<S: Size> {
+    /// The nodes live in one arena and refer to each other by position, so
+    /// a burst of measurements reuses the freed slots instead of going to
+    /// the allocator for each range.
+    nodes: Vec<Node<S>>,
+    free: Vec<u32>,
+    root: u32,
+}
+
+impl<S: Size> Default for SizeTree<S> {
+    fn default() -> Self {
+        SizeTree::new()
+    }
+}
+
+impl<S: Size> SizeTree<S> {
+    pub fn new() -> SizeTree<S> {
+        SizeTree::with_capacity(0)
+    }
+
+    /// A tree of the `(start, size)` ranges, in order, as `entries` lists
+    /// them.
+    pub fn from_entries(entries: impl IntoIterator<Item = (u32, S)>) -> SizeTree<S> {
+        let mut tree = SizeTree::new();
+        tree.rebuild(entries);
+        tree
+    }
+
+    /// A tree with room for `ranges` ranges before it has to grow.
+    pub fn with_capacity(ranges: usize) -> SizeTree<S> {
+        SizeTree {
+            nodes: Vec::with_capacity(ranges),
+            free: Vec::new(),
+            root: NIL,
+        }
+    }
+
+    /// Makes room for `additional` more ranges.
+    pub fn reserve(&mut self, additional: usize) {
+        let available = self.free.len() + (self.nodes.capacity() - self.nodes.len());
+        if additional > available {
+            self.nodes.reserve(additional - self.free.len());
+        }
+    }
+
+    /// How many ranges fit without growing.
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity() - self.nodes.len() + self.free.len() + self.len()
+    }
+
+    /// The bytes held by the arena, the free slots and spare capacity
+    /// included.
+    pub fn allocated_bytes(&self) -> usize {
+        self.nodes.capacity() * mem::size_of::<Node<S>>()
+            + self.free.capacity() * mem::size_of::<u32>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root == NIL
+    }
+
+    /// The number of ranges.
+    pub fn len(&self) -> usize {
+        self.node(self.root).map_or(0, |root| root.count as usize)
+    }
+
+    /// Drops the ranges, keeping the memory for the next ones.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = NIL;
+    }
+
+    /// Makes every item `size` large.
+    pub fn reset(&mut self, size: S) {
+        self.clear();
+        self.root = self.allocate(0, size);
+    }
+
+    /// Lays out groups: each spot gets its size, and the items after it stay
+    /// zero sized until the first `write_range` gives them a size. Spots
+    /// added to a sized tree take their size alone, the other items keep
+    /// theirs.
+    pub fn insert_spots(&mut self, spots: &[(u32, S)]) {
+        if !self.is_empty() {
+            for (spot, size) in spots.iter() {
+                self.overwrite(*spot, *spot, *size);
+            }
+            return;
+        }
+
+        for (spot, size) in spots.iter() {
+            self.put(*spot, *size);
+            if let Some(next) = spot.checked_add(1) {
+                self.put(next, S::ZERO);
+            }
+        }
+    }
+
+    /// Sets the size of `start..=end`. The first write into an empty tree
+    /// sizes all items.
+    pub fn write_range(&mut self, start: u32, end: u32, size: S) {
+        if self.is_empty() {
+            self.reset(size);
+            return;
+        }
+
+        // the first size after the spots goes to every item between them
+        if self.get(start) == Some(S::ZERO) {
+            self.map_sizes(|range_size| match range_size == S::ZERO {
+                true => size,
+                false => range_size,
+            });
+            return;
+        }
+
+        self.overwrite(start, end, size);
+    }
+
+    /// Sets the size of `start..=end`, merging it with equal neighbours.
+    fn overwrite(&mut self, start: u32, end: u32, size: S) {
+        let overlapping_ranges = self.ranges(
+            match start {
+                0 => 0,
+                other => other - 1,
+            },
+            end.saturating_add(1),
+        );
+
+        let mut first_pass_done: bool = false;
+        let mut should_insert: bool = false;
+
+        for (
+            Range {
+                start: range_start,
+                end: range_end,
+                size: range_size,
+            },
+            _,
+        ) in overlapping_ranges
+        {
+            // previous range
+            if !first_pass_done {
+                should_insert = range_size != size;
+                first_pass_done = true;
+            } else {
+                // remove the range if it starts within the new range OR if
+                // it has the same value as it, in order to perfrom a merge
+                if end >= range_start || size == range_size {
+                    self.remove(range_start);
+                }
+            }
+
+            // next range
+            if range_end > end && end >= range_start && range_size != size {
+                // had an isNaN check here, we can probably use 0 for this special case
+                self.put(end + 1, range_size);
+            }
+        }
+
+        if should_insert {
+            self.put(start, size);
+        }
+    }
+
+    /// Replaces every size with `map(size)`, merging the ranges that end up
+    /// equal to the one before them.
+    pub fn map_sizes(&mut self, map: impl Fn(S) -> S) {
+        let mut previous: Option<S> = None;
+        let mut entries: Vec<(u32, S)> = Vec::new();
+
+        for (start, size, _) in self.entries() {
+            let size = map(size);
+            if previous != Some(size) {
+                entries.push((start, size));
+            }
+            previous = Some(size);
+        }
+
+        self.rebuild(entries);
+    }
+
+    /// Merges the equal neighbours and lays the ranges out again in a fresh
+    /// arena, without free slots or spare capacity. Returns the number of
+    /// arena slots released.
+    pub fn compact(&mut self) -> usize {
+        let slots = self.nodes.len();
+        self.map_sizes(|size| size);
+        self.nodes.shrink_to_fit();
+        self.free = Vec::new();
+        slots - self.nodes.len()
+    }
+
+    /// The size of the range starting at `start`, if there is one.
+    pub fn get(&self, start: u32) -> Option<S> {
+        let mut link = self.node(self.root);
+
+        while let Some(node) = link {
+            link = match start.cmp(&node.start) {
+                cmp::Ordering::Less => self.node(node.left),
+                cmp::Ordering::Greater => self.node(node.right),
+                cmp::Ordering::Equal => return Some(node.size),
+            };
+        }
+
+        None
+    }
+
+    /// The start, size and offset of the range containing `index`.
+    pub fn range_at(&self, index: u32) -> (u32, S, S) {
+        let mut link = self.node(self.root);
+        let mut base = S::ZERO;
+        let mut found = None;
+
+        while let Some(node) = link {
+            if index < node.start {
+                link = self.node(node.left);
+                continue;
+            }
+
+            let offset = base.plus(self.left_span(node));
+            found = Some((node.start, node.size, offset));
+
+            match self.node(node.right) {
+                Some(right) if right.first <= index => {
+                    base = offset.plus(node.size.times(right.first - node.start));
+                    link = Some(right);
+                }
+                _ => break,
+            }
+        }
+
+        // the items before the first range, if any, take no space
+        found.unwrap_or((0, S::ZERO, S::ZERO))
+    }
+
+    /// The last range starting at or before `offset`, with `gap` between each
+    /// two items, or the first range when they all start after it.
+    pub fn range_at_offset(&self, offset: S, gap: S) -> (u32, S, S) {
+        let mut link = self.node(self.root);
+        let mut base = S::ZERO;
+        let mut found = None;
+
+        while let Some(node) = link {
+            let node_offset = base.plus(self.left_span(node));
+            if node_offset.plus(gap.times(node.start)) > offset {
+                link = self.node(node.left);
+                continue;
+            }
+
+            found = Some((node.start, node.size, node_offset));
+
+            match self.node(node.right) {
+                Some(right) => {
+                    let right_offset = node_offset.plus(node.size.times(right.first - node.start));
+                    if right_offset.plus(gap.times(right.first)) > offset {
+                        break;
+                    }
+                    base = right_offset;
+                    link = Some(right);
+                }
+                None => break,
+            }
+        }
+
+        found.unwrap_or_else(|| {
+            let (start, size) = self.leftmost(self.root);
+            (start, size, S::ZERO)
+        })
+    }
+
+    /// The ranges overlapping `first..=last`, with their offsets. The last one
+    /// is reported as running to the end of the list.
+    pub fn ranges(&self, first: u32, last: u32) -> Vec<(Range<S>, S)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let (from, _, _) = self.range_at(first);
+        let mut entries = Vec::new();
+        self.collect(self.root, S::ZERO, from, last, &mut entries);
+
+        let mut ends: Vec<u32> = entries.iter().skip(1).map(|entry| entry.0 - 1).collect();
+        ends.push(LAST_RANGE_END);
+
+        entries
+            .into_iter()
+            .zip(ends)
+            .map(|((start, size, offset), end)| (Range { start, end, size }, offset))
+            .collect()
+    }
+
+    /// Every range as `(start, size, offset)`, in order.
+    pub fn entries(&self) -> Vec<(u32, S, S)> {
+        let mut result = Vec::with_capacity(self.len());
+        self.collect(self.root, S::ZERO, 0, u32::MAX, &mut result);
+        result
+    }
+
+    /// The offsets of the indices, with `gap` between each two items. Zero
+    /// for all when the tree is empty. The indices get sorted once and
+    /// resolved in a single in-order walk over the ranges they fall in.
+    pub fn offsets_of(&self, indices: &[u32], gap: S) -> Vec<S> {
+        let mut result = vec![S::ZERO; indices.len()];
+        if self.is_empty() || indices.is_empty() {
+            return result;
+        }
+
+        let mut order: Vec<usize> = (0..indices.len()).collect();
+        order.sort_unstable_by_key(|position| indices[*position]);
+        let first = indices[order[0]];
+        let last = indices[order[order.len() - 1]];
+
+        let mut ranges = self
+            .ranges(first, last)
+            .into_iter()
+            .map(|(range, offset)| (range.start, range.size, offset))
+            .peekable();
+        let (mut start, mut size, mut offset) = match ranges.next() {
+            None => return result,
+            Some(range) => range,
+        };
+
+        for position in order {
+            let index = indices[position];
+            while let Some(next) = ranges.next_if(|(next_start, _, _)| *next_start <= index) {
+                (start, size, offset) = next;
+            }
+
+            result[position] = size
+                .times(index.saturating_sub(start))
+                .plus(offset)
+                .plus(gap.times(index));
+        }
+
+        result
+    }
+
+    /// The inverse of `offsets_of`: the index of the item under each offset,
+    /// where an offset in a gap belongs to the item before it. Resolved in
+    /// one walk too, like `offsets_of`.
+    pub fn indices_at(&self, offsets: &[S], gap: S) -> Vec<u32> {
+        let mut result = vec![0u32; offsets.len()];
+        if self.is_empty() || offsets.is_empty() {
+            return result;
+        }
+
+        let mut order: Vec<usize> = (0..offsets.len()).collect();
+        order.sort_unstable_by(|a, b| {
+            offsets[*a]
+                .partial_cmp(&offsets[*b])
+                .unwrap_or(cmp::Ordering::Equal)
+        });
+        let (first, _, _) = self.range_at_offset(offsets[order[0]], gap);
+        let (last, _, _) = self.range_at_offset(offsets[order[order.len() - 1]], gap);
+
+        // the offsets where the ranges start, the gaps before them included
+        let mut ranges = self
+            .ranges(first, cmp::max(first, last))
+            .into_iter()
+            .map(|(range, offset)| (range.start, range.size, offset.plus(gap.times(range.start))))
+            .peekable();
+        let (mut start, mut size, mut range_offset) = match ranges.next() {
+            None => return result,
+            Some(range) => range,
+        };
+
+        for position in order {
+            let offset = offsets[position];
+            while let Some(next) = ranges.next_if(|(_, _, next_offset)| *next_offset <= offset) {
+                (start, size, range_offset) = next;
+            }
+
+            result[position] = if size == S::ZERO || offset < range_offset {
+                start
+            } else {
+                start.saturating_add((offset - range_offset).fits(size.plus(gap)))
+            };
+        }
+
+        result
+    }
+
+    fn put(&mut self, start: u32, size: S) {
+        self.root = self.put_below(self.root, start, size);
+    }
+
+    fn remove(&mut self, start: u32) {
+        self.root = self.remove_below(self.root, start);
+    }
+
+    /// Replaces the ranges with `entries`, linked up in one pass instead of
+    /// inserted one by one. Out of order entries get sorted first; of the
+    /// ones sharing a start, the first stays.
+    fn rebuild(&mut self, entries: impl IntoIterator<Item = (u32, S)>) {
+        self.clear();
+        self.nodes.extend(
+            entries
+                .into_iter()
+                .map(|(start, size)| Node::new(start, size)),
+        );
+
+        if !self
+            .nodes
+            .windows(2)
+            .all(|pair| pair[0].start < pair[1].start)
+        {
+            self.nodes.sort_by_key(|node| node.start);
+            self.nodes.dedup_by_key(|node| node.start);
+        }
+
+        self.root = build(&mut self.nodes, 0);
+    }
+
+    fn node(&self, id: u32) -> Option<&Node<S>> {
+        match id {
+            NIL => None,
+            id => Some(&self.nodes[id as usize]),
+        }
+    }
+
+    fn level(&self, id: u32) -> u8 {
+        self.node(id).map_or(0, |node| node.level)
+    }
+
+    fn allocate(&mut self, start: u32, size: S) -> u32 {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id as usize] = Node::new(start, size);
+                id
+            }
+            None => {
+                self.nodes.push(Node::new(start, size));
+                self.nodes.len() as u32 - 1
+            }
+        }
+    }
+
+    fn left_span(&self, node: &Node<S>) -> S {
+        node.left_span(self.node(node.left))
+    }
+
+    fn update(&mut self, id: u32) {
+        let node = &self.nodes[id as usize];
+        let left = self.node(node.left).copied();
+        let right = self.node(node.right).copied();
+        self.nodes[id as usize].aggregate(left.as_ref(), right.as_ref());
+    }
+
+    fn skew(&mut self, id: u32) -> u32 {
+        let left = self.nodes[id as usize].left;
+        if self.level(left) != self.nodes[id as usize].level {
+            return id;
+        }
+
+        self.nodes[id as usize].left = self.nodes[left as usize].right;
+        self.update(id);
+        self.nodes[left as usize].right = id;
+        self.update(left);
+        left
+    }
+
+    fn split(&mut self, id: u32) -> u32 {
+        let right = self.nodes[id as usize].right;
+        let next = match self.node(right) {
+            None => return id,
+            Some(right) => right.right,
+        };
+        if self.level(next) != self.nodes[id as usize].level {
+            return id;
+        }
+
+        self.nodes[id as usize].right = self.nodes[right as usize].left;
+        self.update(id);
+        self.nodes[right as usize].left = id;
+        self.nodes[right as usize].level += 1;
+        self.update(right);
+        right
+    }
+
+    fn put_below(&mut self, id: u32, start: u32, size: S) -> u32 {
+        if id == NIL {
+            return self.allocate(start, size);
+        }
+
+        let node = &self.nodes[id as usize];
+        match start.cmp(&node.start) {
+            cmp::Ordering::Less => {
+                let left = self.put_below(node.left, start, size);
+                self.nodes[id as usize].left = left;
+            }
+            cmp::Ordering::Greater => {
+                let right = self.put_below(node.right, start, size);
+                self.nodes[id as usize].right = right;
+            }
+            cmp::Ordering::Equal => self.nodes[id as usize].size = size,
+        }
+
+        self.update(id);
+        let id = self.skew(id);
+        self.split(id)
+    }
+
+    fn remove_below(&mut self, id: u32, start: u32) -> u32 {
+        let node = match self.node(id) {
+            None => return NIL,
+            Some(node) => *node,
+        };
+
+        match start.cmp(&node.start) {
+            cmp::Ordering::Less => {
+                self.nodes[id as usize].left = self.remove_below(node.left, start);
+            }
+            cmp::Ordering::Greater => {
+                self.nodes[id as usize].right = self.remove_below(node.right, start);
+            }
+            cmp::Ordering::Equal => match (node.left, node.right) {
+                (NIL, NIL) => {
+                    self.free.push(id);
+                    return NIL;
+                }
+                (NIL, right) => {
+                    let (successor, size) = self.leftmost(right);
+                    self.nodes[id as usize].right = self.remove_below(right, successor);
+                    self.nodes[id as usize].start = successor;
+                    self.nodes[id as usize].size = size;
+                }
+                (left, _) => {
+                    let (predecessor, size) = self.rightmost(left);
+                    self.nodes[id as usize].left = self.remove_below(left, predecessor);
+                    self.nodes[id as usize].start = predecessor;
+                    self.nodes[id as usize].size = size;
+                }
+            },
+        }
+
+        let (left, right) = (self.nodes[id as usize].left, self.nodes[id as usize].right);
+        let expected_level = cmp::min(self.level(left), self.level(right)) + 1;
+        if expected_level < self.nodes[id as usize].level {
+            self.nodes[id as usize].level = expected_level;
+            if right != NIL {
+                let right = &mut self.nodes[right as usize];
+                right.level = cmp::min(right.level, expected_level);
+            }
+        }
+        self.update(id);
+
+        let id = self.skew(id);
+        let right = self.nodes[id as usize].right;
+        if right != NIL {
+            let right = self.skew(right);
+            let next = self.nodes[right as usize].right;
+            if next != NIL {
+                self.nodes[right as usize].right = self.skew(next);
+                self.update(right);
+            }
+            self.nodes[id as usize].right = right;
+            self.update(id);
+        }
+
+        let id = self.split(id);
+        let right = self.nodes[id as usize].right;
+        if right != NIL {
+            self.nodes[id as usize].right = self.split(right);
+            self.update(id);
+        }
+
+        id
+    }
+
+    fn leftmost(&self, id: u32) -> (u32, S) {
+        let mut node = self.node(id).expect("tree should not be empty");
+        while let Some(left) = self.node(node.left) {
+            node = left;
+        }
+        (node.start, node.size)
+    }
+
+    fn rightmost(&self, id: u32) -> (u32, S) {
+        let mut node = self.node(id).expect("tree should not be empty");
+        while let Some(right) = self.node(node.right) {
+            node = right;
+        }
+        (node.start, node.size)
+    }
+
+    /// Pushes the `(start, size, offset)` of the ranges starting in
+    /// `lo..=hi`, in order. `base` is the offset of the start of the subtree.
+    fn collect(&self, id: u32, base: S, lo: u32, hi: u32, result: &mut Vec<(u32, S, S)>) {
+        let node = match self.node(id) {
+            None => return,
+            Some(node) => node,
+        };
+        let offset = base.plus(self.left_span(node));
+
+        if lo < node.start {
+            self.collect(node.left, base, lo, hi, result);
+        }
+
+        if lo <= node.start && node.start <= hi {
+            result.push((node.start, node.size, offset));
+        }
+
+        if node.start < hi {
+            if let Some(right) = self.node(node.right) {
+                let right_offset = offset.plus(node.size.times(right.first - node.start));
+                self.collect(node.right, right_offset, lo, hi, result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Size, SizeTree};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_float_sizes() {
+        let mut tree: SizeTree<f64> = SizeTree::new();
+        tree.write_range(0, 0, 10.5);
+        tree.write_range(2, 3, 20.25);
+
+        assert_eq!(tree.offsets_of(&[1, 2, 4], 0.0), [10.5, 21.0, 61.5]);
+        assert_eq!(
+            tree.indices_at(&[10.4, 21.0, 61.4, 61.5], 0.0),
+            [0, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_wide_sizes() {
+        let mut tree: SizeTree<u64> = SizeTree::new();
+        tree.write_range(0, 0, 1 << 20);
+
+        assert_eq!(tree.offsets_of(&[1 << 20], 0), [1 << 40]);
+        assert_eq!(tree.indices_at(&[(1 << 40) + 1], 0), [1 << 20]);
+    }
+
+    /// Checks the AA tree invariants and the aggregates below `id`, and
+    /// answers its depth.
+    fn check<S: Size>(tree: &SizeTree<S>, id: u32) -> usize {
+        let node = match tree.node(id) {
+            None => return 0,
+            Some(node) => node,
+        };
+
+        let right = tree.node(node.right);
+        assert_eq!(tree.level(node.left) + 1, node.level);
+        assert!(tree.level(node.right) + 1 >= node.level);
+        if let Some(right) = right {
+            assert!(tree.level(right.right) < node.level);
+        }
+
+        let mut copy = tree.clone();
+        copy.update(id);
+        assert_eq!(copy.nodes[id as usize].span, node.span);
+        assert_eq!(copy.nodes[id as usize].count, node.count);
+
+        1 + check(tree, node.left).max(check(tree, node.right))
+    }
+
+    #[test]
+    fn test_matches_a_plain_map() {
+        let mut tree: SizeTree<u32> = SizeTree::new();
+        let mut reference: BTreeMap<u32, u32> = BTreeMap::new();
+        let mut seed: u32 = 7;
+
+        for round in 0..2000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let start = (seed >> 8) % 500;
+            let size = (seed >> 20) % 4 * 10;
+
+            if round % 3 == 2 && start != 0 {
+                tree.remove(start);
+                reference.remove(&start);
+            } else {
+                tree.put(start, size);
+                reference.insert(start, size);
+            }
+        }
+        tree.put(0, 10);
+        reference.insert(0, 10);
+
+        let depth = check(&tree, tree.root);
+        assert!(depth <= 2 * (usize::BITS - tree.len().leading_zeros()) as usize);
+
+        let mut offset = 0;
+        let mut previous = (0, 0);
+        let mut expected = Vec::new();
+        for (start, size) in reference.iter() {
+            offset += (start - previous.0) * previous.1;
+            expected.push((*start, *size, offset));
+            previous = (*start, *size);
+        }
+
+        assert_eq!(tree.entries(), expected);
+        for (start, _, offset) in expected {
+            assert_eq!(tree.offsets_of(&[start], 0), [offset]);
+        }
+
+        // the removed ranges left their slots to the later ones
+        assert_eq!(tree.nodes.len() - tree.free.len(), tree.len());
+        assert!(tree.nodes.len() <= 500);
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut tree: SizeTree<u32> = SizeTree::new();
+        tree.write_range(0, 0, 10);
+        for index in 1..=20 {
+            tree.write_range(index * 3, index * 3, 20);
+        }
+        tree.write_range(0, 100, 10);
+        assert_eq!(tree.len(), 1);
+
+        assert_eq!(tree.compact(), 40);
+        assert_eq!(tree.capacity(), 1);
+        assert_eq!(tree.offsets_of(&[7], 0), [70]);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut tree: SizeTree<u32> = SizeTree::with_capacity(10);
+        assert!(tree.capacity() >= 10);
+
+        tree.reserve(1000);
+        assert!(tree.capacity() >= 1000);
+
+        tree.write_range(0, 0, 10);
+        for index in 1..500 {
+            tree.write_range(index * 2, index * 2, 20);
+        }
+        tree.clear();
+        assert!(tree.capacity() >= 1000);
+    }
+
+    #[test]
+    fn test_offset_lookups() {
+        let mut tree: SizeTree<u32> = SizeTree::new();
+        tree.write_range(0, 0, 10);
+        tree.write_range(5, 9, 20);
+
+        // ranges start at 0, 50 and 150
+        assert_eq!(tree.range_at_offset(149, 0), (5, 20, 50));
+        assert_eq!(tree.range_at_offset(150, 0), (10, 10, 150));
+
+        // in any order, resolved in one walk
+        assert_eq!(tree.offsets_of(&[12, 0, 6, 5, 6], 0), [170, 0, 70, 50, 70]);
+        assert_eq!(tree.offsets_of(&[6, 1], 2), [82, 12]);
+        assert_eq!(
+            tree.indices_at(&[175, 0, 69, 50, 1000], 0),
+            [12, 0, 5, 5, 95]
+        );
+        assert_eq!(tree.indices_at(&[82, 11, 12], 2), [6, 0, 1]);
+    }
+
+    #[test]
+    fn test_from_entries() {
+        for count in (0..70).chain([1 << 15, (1 << 15) + 17]) {
+            let entries: Vec<(u32, u32)> = (0..count).map(|index| (index * 3, index % 7)).collect();
+            let tree = SizeTree::from_entries(entries.iter().copied());
+
+            if count < 70 {
+                check(&tree, tree.root);
+            }
+            let listed: Vec<(u32, u32)> = tree
+                .entries()
+                .into_iter()
+                .map(|(start, size, _)| (start, size))
+                .collect();
+            assert_eq!(listed, entries);
+        }
+
+        let tree: SizeTree<u32> = SizeTree::from_entries([(10, 2), (0, 1), (10, 3)]);
+        assert_eq!(tree.entries(), [(0, 1, 0), (10, 2, 10)]);
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Item, ItemList, OffsetList};
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A read-only view of an `OffsetList` as it was when the snapshot was taken.
+/// It shares the sizes and the measured ranges with the list instead of
+/// copying them; the list copies them on its first write after the snapshot,
+/// once, however many snapshots share them. A renderer can keep reading a
+/// consistent frame while measurements land in the live list.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct OffsetListSnapshot {
+    list: OffsetList,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OffsetListSnapshot {
+    /// The `version` of the list the snapshot was taken from.
+    pub fn version(&self) -> u32 {
+        self.list.version()
+    }
+
+    pub fn config_hash(&self) -> u32 {
+        self.list.config_hash()
+    }
+
+    /// See `OffsetList::checksum`.
+    pub fn checksum(&self) -> u32 {
+        self.list.checksum()
+    }
+
+    /// The items the live `list` now lays out differently, see
+    /// `OffsetList::diff`.
+    pub fn diff(&self, list: &OffsetList, count: u32) -> Vec<u32> {
+        self.list.diff(list, count)
+    }
+
+    pub fn total_size(&self, count: u32) -> u32 {
+        self.list.total_size(count)
+    }
+
+    pub fn offset_of(&self, index: u32) -> u32 {
+        self.list.offset_of(index)
+    }
+
+    pub fn offsets_of(&self, indices: &[u32]) -> Vec<u32> {
+        self.list.offsets_of(indices)
+    }
+
+    pub fn indices_at_offsets(&self, offsets: &[u32]) -> Vec<u32> {
+        self.list.indices_at_offsets(offsets)
+    }
+
+    pub fn item_at(&self, index: u32) -> Item {
+        self.list.item_at(index)
+    }
+
+    pub fn index_range_list(&self, start_index: u32, end_index: u32) -> ItemList {
+        self.list.index_range_list(start_index, end_index)
+    }
+
+    pub fn range_list(
+        &self,
+        start_offset: u32,
+        end_offset: u32,
+        min_index: u32,
+        max_index: u32,
+    ) -> ItemList {
+        self.list
+            .range_list(start_offset, end_offset, min_index, max_index)
+    }
+}
+
+impl OffsetListSnapshot {
+    /// Brings the sizes up to date first, so the snapshot never rebuilds
+    /// them on its own.
+    pub(crate) fn of(list: &OffsetList) -> OffsetListSnapshot {
+        list.refresh();
+        OffsetListSnapshot { list: list.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetList;
+
+    #[test]
+    fn test_snapshot_keeps_its_view() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        let snapshot = list.snapshot();
+
+        list.insert(2, 2, 30);
+        list.set_default_size(20);
+
+        assert_eq!(snapshot.offsets_of(&[3, 5]), [30, 50]);
+        assert_eq!(list.offsets_of(&[3, 5]), [60, 100]);
+        assert_ne!(snapshot.version(), list.version());
+    }
+
+    #[test]
+    fn test_snapshot_shares_the_sizes() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        let first = list.snapshot();
+        let second = list.snapshot();
+
+        assert!(std::ptr::eq(
+            &**first.list.tree.borrow(),
+            &**second.list.tree.borrow()
+        ));
+        list.insert(1, 1, 20);
+        assert!(!std::ptr::eq(
+            &**first.list.tree.borrow(),
+            &**list.tree.borrow()
+        ));
+        assert_eq!(first.total_size(3), 30);
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(feature = "fenwick")]
+use super::fenwick::FenwickTree;
+use super::size_tree::SizeTree;
+use super::tree_utils::Range;
+use alloc::vec::Vec;
+
+/// Where an `OffsetList` keeps its sizes. The range tree is the default; the
+/// dense one is picked at construction, with the `fenwick` feature.
+#[derive(Clone, Debug)]
+pub enum SizeStore {
+    Ranges(SizeTree<u32>),
+    #[cfg(feature = "fenwick")]
+    Dense(FenwickTree<u32>),
+}
+
+macro_rules! dispatch {
+    ($store:expr, $tree:ident => $call:expr) => {
+        match $store {
+            SizeStore::Ranges($tree) => $call,
+            #[cfg(feature = "fenwick")]
+            SizeStore::Dense($tree) => $call,
+        }
+    };
+}
+
+impl SizeStore {
+    pub fn is_empty(&self) -> bool {
+        dispatch!(self, tree => tree.is_empty())
+    }
+
+    pub fn len(&self) -> usize {
+        dispatch!(self, tree => tree.len())
+    }
+
+    /// The nodes of the range tree, or the slots of the dense store.
+    pub fn node_count(&self) -> usize {
+        match self {
+            SizeStore::Ranges(tree) => tree.len(),
+            #[cfg(feature = "fenwick")]
+            SizeStore::Dense(tree) => tree.capacity() as usize,
+        }
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        dispatch!(self, tree => tree.allocated_bytes())
+    }
+
+    /// Makes room for `additional` more ranges. The dense store is sized
+    /// once, at construction.
+    pub fn reserve(&mut self, additional: usize) {
+        match self {
+            SizeStore::Ranges(tree) => tree.reserve(additional),
+            #[cfg(feature = "fenwick")]
+            SizeStore::Dense(_) => {}
+        }
+    }
+
+    /// Compacts the range tree; the dense store has nothing to reclaim.
+    pub fn compact(&mut self) -> usize {
+        match self {
+            SizeStore::Ranges(tree) => tree.compact(),
+            #[cfg(feature = "fenwick")]
+            SizeStore::Dense(_) => 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        dispatch!(self, tree => tree.clear())
+    }
+
+    pub fn reset(&mut self, size: u32) {
+        dispatch!(self, tree => tree.reset(size))
+    }
+
+    pub fn insert_spots(&mut self, spots: &[(u32, u32)]) {
+        dispatch!(self, tree => tree.insert_spots(spots))
+    }
+
+    pub fn write_range(&mut self, start: u32, end: u32, size: u32) {
+        dispatch!(self, tree => tree.write_range(start, end, size))
+    }
+
+    pub fn map_sizes(&mut self, map: impl Fn(u32) -> u32) {
+        dispatch!(self, tree => tree.map_sizes(map))
+    }
+
+    pub fn range_at(&self, index: u32) -> (u32, u32, u32) {
+        dispatch!(self, tree => tree.range_at(index))
+    }
+
+    pub fn range_at_offset(&self, offset: u32, gap: u32) -> (u32, u32, u32) {
+        dispatch!(self, tree => tree.range_at_offset(offset, gap))
+    }
+
+    pub fn ranges(&self, first: u32, last: u32) -> Vec<(Range, u32)> {
+        dispatch!(self, tree => tree.ranges(first, last))
+    }
+
+    #[cfg(test)]
+    pub fn entries(&self) -> Vec<(u32, u32, u32)> {
+        dispatch!(self, tree => tree.entries())
+    }
+
+    pub fn offsets_of(&self, indices: &[u32], gap: u32) -> Vec<u32> {
+        dispatch!(self, tree => tree.offsets_of(indices, gap))
+    }
+
+    pub fn indices_at(&self, offsets: &[u32], gap: u32) -> Vec<u32> {
+        dispatch!(self, tree => tree.indices_at(offsets, gap))
+    }
+}
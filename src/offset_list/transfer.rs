@@ -0,0 +1,382 @@
+//! The state of an `OffsetList` as one buffer, for handing a list between a
+//! Worker and the main thread with `postMessage`. Its `buffer` goes in the
+//! transfer list, so the receiving side gets it without a copy.
+//!
+//! The buffer is a sequence of little endian `u32` words, `f64`s taking two:
+//! a magic word and the format version, the settings, then the size ranges,
+//! the measured ranges, the types, tags, keys, collapsed spans and groups,
+//! each as a count followed by its entries, and the total count and the
+//! origin. The undo history and the change listener stay behind.
+
+use super::cells::{Lock, Shared};
+use super::collapse::Collapsed;
+use super::groups::Groups;
+use super::keys::KeyRegistry;
+use super::size_tree::SizeTree;
+use super::store::SizeStore;
+use super::{ErrorCode, ListError, OffsetList, Rounding};
+use crate::range_set::RangeSet;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// "RVOL", read as a little endian word.
+const MAGIC: u32 = 0x4c4f_5652;
+const FORMAT: u32 = 2;
+
+#[derive(Default)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+
+    /// A flag word, then the value if there is one.
+    pub fn option(&mut self, value: Option<u32>) {
+        self.u32(value.is_some() as u32);
+        if let Some(value) = value {
+            self.u32(value);
+        }
+    }
+
+    pub fn words(&mut self, words: &[u32]) {
+        self.u32(words.len() as u32);
+        for word in words {
+            self.u32(*word);
+        }
+    }
+
+    pub fn map(&mut self, map: &BTreeMap<u32, u32>) {
+        let words: Vec<u32> = map.iter().flat_map(|(key, value)| [*key, *value]).collect();
+        self.words(&words);
+    }
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes.get(..4)?;
+        let mut word = [0u8; 4];
+        word.copy_from_slice(bytes);
+        self.bytes = &self.bytes[4..];
+        Some(u32::from_le_bytes(word))
+    }
+
+    pub fn f64(&mut self) -> Option<f64> {
+        let bytes = self.bytes.get(..8)?;
+        let mut word = [0u8; 8];
+        word.copy_from_slice(bytes);
+        self.bytes = &self.bytes[8..];
+        Some(f64::from_bits(u64::from_le_bytes(word)))
+    }
+
+    pub fn option(&mut self) -> Option<Option<u32>> {
+        match self.u32()? {
+            0 => Some(None),
+            1 => Some(Some(self.u32()?)),
+            _ => None,
+        }
+    }
+
+    /// A count of words and the words, of which there are `stride` per entry.
+    pub fn words(&mut self, stride: usize) -> Option<Vec<u32>> {
+        let count = self.u32()? as usize;
+        // a corrupt count must not allocate more than the buffer holds
+        if !count.is_multiple_of(stride) || count > self.bytes.len() / 4 {
+            return None;
+        }
+
+        (0..count).map(|_| self.u32()).collect()
+    }
+
+    pub fn map(&mut self) -> Option<BTreeMap<u32, u32>> {
+        let words = self.words(2)?;
+        Some(words.chunks(2).map(|pair| (pair[0], pair[1])).collect())
+    }
+
+    /// `start, end` pairs, none when one of them ends before it starts.
+    pub fn spans(&mut self) -> Option<Vec<(u32, u32)>> {
+        self.words(2)?
+            .chunks(2)
+            .map(|pair| (pair[0] <= pair[1]).then_some((pair[0], pair[1])))
+            .collect()
+    }
+
+    /// A finite `f64` above zero.
+    pub fn positive(&mut self) -> Option<f64> {
+        let value = self.f64()?;
+        (value.is_finite() && value > 0.0).then_some(value)
+    }
+}
+
+/// Whether every value is larger than the one before it.
+pub fn ascending(values: impl IntoIterator<Item = u32>) -> bool {
+    let mut previous: Option<u32> = None;
+    values.into_iter().all(|value| {
+        let larger = previous.is_none_or(|previous| previous < value);
+        previous = Some(value);
+        larger
+    })
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OffsetList {
+    /// The list as a single buffer; see the module docs. A list built from
+    /// it with `from_transferable` lays out its items the same way.
+    pub fn to_transferable(&self) -> Vec<u8> {
+        self.refresh();
+        let mut writer = Writer::default();
+        writer.u32(MAGIC);
+        writer.u32(FORMAT);
+
+        writer.u32(self.version);
+        writer.option(self.default_size);
+        for value in [
+            self.gap,
+            self.header_height,
+            self.footer_height,
+            self.padding_top,
+            self.padding_bottom,
+            self.rounding as u32,
+            self.size_quantum,
+            self.samples,
+        ] {
+            writer.u32(value);
+        }
+        writer.f64(self.scale);
+        writer.f64(self.pixel_step);
+        writer.u32(self.estimation.is_some() as u32);
+        writer.f64(self.estimation.unwrap_or(0.0));
+        writer.f64(self.estimate);
+        writer.option(self.prune_distance);
+
+        writer.words(&self.size_ranges());
+        let measured: Vec<u32> = self
+            .measured
+            .iter()
+            .flat_map(|(start, end)| [start, end])
+            .collect();
+        writer.words(&measured);
+        writer.map(&self.item_types);
+        writer.map(&self.type_sizes);
+        writer.map(&self.tags);
+        writer.words(self.keys.keys());
+        self.collapsed.write(&mut writer);
+        self.groups.write(&mut writer);
+        writer.option(self.total_count);
+        writer.u32(self.origin as u32);
+
+        writer.bytes
+    }
+
+    /// The list `to_transferable` wrote into `bytes`, on the range tree. None
+    /// when the buffer is not one, was written by another format version,
+    /// or holds a field no list could have: an inverted range, unsorted
+    /// starts, or a scale that isn't a positive number. Every field is
+    /// checked before the list is built from it.
+    pub fn from_transferable(bytes: &[u8]) -> Option<OffsetList> {
+        OffsetList::try_from_transferable(bytes).ok()
+    }
+
+    /// `from_transferable`, with an error in place of None.
+    pub fn try_from_transferable(bytes: &[u8]) -> Result<OffsetList, ListError> {
+        OffsetList::read_transferable(bytes).ok_or_else(|| ListError {
+            code: ErrorCode::CorruptBuffer,
+            index: None,
+            message: String::from("not a list buffer, or a corrupt one"),
+            tree_summary: String::from("no ranges"),
+        })
+    }
+}
+
+impl OffsetList {
+    fn read_transferable(bytes: &[u8]) -> Option<OffsetList> {
+        let mut reader = Reader { bytes };
+        if reader.u32()? != MAGIC || reader.u32()? != FORMAT {
+            return None;
+        }
+
+        let mut list = OffsetList::new();
+        list.version = reader.u32()?;
+        list.default_size = reader.option()?;
+        list.gap = reader.u32()?;
+        list.header_height = reader.u32()?;
+        list.footer_height = reader.u32()?;
+        list.padding_top = reader.u32()?;
+        list.padding_bottom = reader.u32()?;
+        list.rounding = match reader.u32()? {
+            0 => Rounding::Round,
+            1 => Rounding::Floor,
+            2 => Rounding::Ceil,
+            3 => Rounding::None,
+            _ => return None,
+        };
+        list.size_quantum = reader.u32()?;
+        list.samples = reader.u32()?;
+        list.scale = reader.positive()?;
+        list.pixel_step = reader.positive()?;
+        let estimated = match reader.u32()? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let alpha = reader.f64()?;
+        if !(0.0..=1.0).contains(&alpha) {
+            return None;
+        }
+        list.estimation = estimated.then_some(alpha);
+        list.estimate = reader.f64()?;
+        if !list.estimate.is_finite() {
+            return None;
+        }
+        list.prune_distance = reader.option()?;
+
+        let sizes = reader.words(2)?;
+        if !ascending(sizes.chunks(2).map(|pair| pair[0])) {
+            return None;
+        }
+        let measured_spans = reader.spans()?;
+
+        let tree = SizeTree::from_entries(sizes.chunks(2).map(|pair| (pair[0], pair[1])));
+        list.tree = Lock::new(Shared::new(SizeStore::Ranges(tree)));
+        let mut measured = RangeSet::new();
+        for (start, end) in measured_spans {
+            measured.insert(start, end);
+        }
+        list.measured = Shared::new(measured);
+        list.item_types = reader.map()?;
+        list.type_sizes = reader.map()?;
+        list.tags = reader.map()?;
+        list.keys = Shared::new(KeyRegistry::new(&reader.words(1)?));
+        list.collapsed = Collapsed::read(&mut reader)?;
+        list.groups = Groups::read(&mut reader)?;
+        list.total_count = reader.option()?;
+        list.origin = reader.u32()? as i32;
+
+        Some(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetList;
+
+    #[test]
+    fn test_round_trip() {
+        let mut list = OffsetList::new();
+        list.set_default_size(10);
+        list.set_gap(2);
+        list.set_header_height(5);
+        list.insert_tagged(3, 5, 30, 7);
+        list.set_keys(&[10, 11, 12, 13, 14, 15]);
+        list.collapse(1, 1);
+
+        let copy = OffsetList::from_transferable(&list.to_transferable()).unwrap();
+        assert!(copy.equals(&list));
+        assert_eq!(
+            copy.offsets_of(&[1, 2, 6, 7]),
+            list.offsets_of(&[1, 2, 6, 7])
+        );
+        assert_eq!(copy.version(), list.version());
+        assert_eq!(copy.tag_of(4), 7);
+        assert_eq!(copy.index_of_key(15), Some(5));
+
+        let mut copy = copy;
+        copy.expand(1, 1);
+        assert_eq!(copy.offsets_of(&[2]), [29]);
+        assert!(copy.is_measured(3));
+    }
+
+    #[test]
+    fn test_rejects_other_buffers() {
+        let bytes = OffsetList::new().to_transferable();
+
+        assert!(OffsetList::from_transferable(&bytes[..bytes.len() - 1]).is_none());
+        assert!(OffsetList::from_transferable(&[0; 64]).is_none());
+        assert!(OffsetList::from_transferable(&bytes).is_some());
+    }
+
+    /// `bytes` with the first run of the words `from` replaced by `to`.
+    fn patched(bytes: &[u8], from: &[u32], to: &[u32]) -> Vec<u8> {
+        let encode = |words: &[u32]| -> Vec<u8> {
+            words.iter().flat_map(|word| word.to_le_bytes()).collect()
+        };
+        let (from, to) = (encode(from), encode(to));
+        let at = (0..bytes.len())
+            .step_by(4)
+            .find(|at| bytes[*at..].starts_with(&from))
+            .expect("the words are in the buffer");
+
+        let mut bytes = bytes.to_vec();
+        bytes[at..at + to.len()].copy_from_slice(&to);
+        bytes
+    }
+
+    #[test]
+    fn test_rejects_truncated_buffers() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert_tagged(4, 9, 20, 3);
+        list.set_keys(&[1, 2, 3]);
+        list.collapse(2, 3);
+        list.set_groups(&[2, 3], 30, 10);
+        let bytes = list.to_transferable();
+
+        for length in 0..bytes.len() {
+            assert!(OffsetList::from_transferable(&bytes[..length]).is_none());
+        }
+        assert!(OffsetList::from_transferable(&bytes).is_some());
+    }
+
+    #[test]
+    fn test_rejects_corrupt_fields() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(0x5555, 0x5555, 20);
+        list.insert(0x1111, 0x2222, 7);
+        list.collapse(0x3333, 0x4444);
+        assert!(OffsetList::from_transferable(&list.to_transferable()).is_some());
+        let bytes = list.to_transferable();
+
+        // inverted measured and collapsed ranges
+        let inverted = patched(&bytes, &[0x1111, 0x2222], &[0x2222, 0x1111]);
+        assert!(OffsetList::from_transferable(&inverted).is_none());
+        let inverted = patched(&bytes, &[0x3333, 0x4444], &[0x4444, 0x3333]);
+        assert!(OffsetList::from_transferable(&inverted).is_none());
+
+        // a size range starting where the next one does
+        let duplicate = patched(&bytes, &[0x5555, 20], &[0x5556, 20]);
+        assert!(OffsetList::from_transferable(&duplicate).is_none());
+
+        let mut grouped = OffsetList::new();
+        grouped.set_groups(&[0x7776, 5], 30, 10);
+        let bytes = grouped.to_transferable();
+        let unsorted = patched(&bytes, &[2, 0, 0x7777], &[2, 0x7777, 0]);
+        assert!(OffsetList::from_transferable(&unsorted).is_none());
+
+        for (scale, pixel_step) in [
+            (f64::NAN, 1.0),
+            (0.0, 1.0),
+            (1.0, -1.0),
+            (1.0, f64::INFINITY),
+        ] {
+            let mut list = OffsetList::new();
+            list.insert(0, 0, 10);
+            list.scale = scale;
+            list.pixel_step = pixel_step;
+            assert!(OffsetList::from_transferable(&list.to_transferable()).is_none());
+        }
+    }
+}
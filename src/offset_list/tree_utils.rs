@@ -1,28 +1,30 @@
-use std::collections::BTreeMap;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Debug)]
-pub struct Range {
+pub struct Range<S = u32> {
     pub start: u32,
     pub end: u32,
-    pub size: u32,
+    pub size: S,
 }
 
-impl Range {
-    fn new(start: u32, end: u32, size: u32) -> Self {
+impl<S> Range<S> {
+    fn new(start: u32, end: u32, size: S) -> Self {
         Range { start, end, size }
     }
 }
 
-pub const LAST_RANGE_END: u32 = std::u32::MAX;
+pub const LAST_RANGE_END: u32 = u32::MAX;
 
-pub fn lte(tree: &BTreeMap<u32, u32>, start: u32) -> (&u32, &u32) {
+pub fn lte<K: Ord, V>(tree: &BTreeMap<K, V>, start: K) -> (&K, &V) {
     tree.range(..=start)
         .last()
         .expect("Tree should contain zero")
 }
 
-pub fn ranges_within(tree: &BTreeMap<u32, u32>, start: u32, end: u32) -> Vec<Range> {
-    let mut ranges: Vec<Range> = Vec::new();
+pub fn ranges_within<S: Copy>(tree: &BTreeMap<u32, S>, start: u32, end: u32) -> Vec<Range<S>> {
+    let mut ranges: Vec<Range<S>> = Vec::new();
 
     let (closest_lte, _) = lte(tree, start);
 
@@ -38,18 +40,79 @@ pub fn ranges_within(tree: &BTreeMap<u32, u32>, start: u32, end: u32) -> Vec<Ran
 
     ranges.push(Range::new(*start, LAST_RANGE_END, *size));
 
-    return ranges;
+    ranges
+}
+
+/// Sets `start..=end` to `value` in a tree of ranges that contains zero,
+/// keeping the values around it and merging equal neighbours.
+pub fn assign(tree: &mut BTreeMap<u32, u32>, start: u32, end: u32, value: u32) {
+    let after = end.checked_add(1);
+    let value_after = after.map(|after| *lte(tree, after).1);
+
+    let covered: Vec<u32> = tree.range(start..=end).map(|(key, _)| *key).collect();
+    for key in covered {
+        tree.remove(&key);
+    }
+
+    if let (Some(after), Some(value_after)) = (after, value_after) {
+        tree.entry(after).or_insert(value_after);
+        if value_after == value {
+            tree.remove(&after);
+        }
+    }
+
+    let value_before = start.checked_sub(1).map(|before| *lte(tree, before).1);
+    if value_before != Some(value) {
+        tree.insert(start, value);
+    }
+}
+
+/// The ranges where their items end up after the item at `from` moves to
+/// `to`, shifting the ones in between by one.
+pub fn moved<S: Copy>(ranges: &[Range<S>], from: u32, to: u32) -> Vec<Range<S>> {
+    let (first, last) = (from.min(to), from.max(to));
+    let between = match from < to {
+        true => (from + 1, to, -1i64),
+        false => (to, from - 1, 1i64),
+    };
+
+    let mut segments = vec![(from, from, i64::from(to) - i64::from(from))];
+    if from != to {
+        segments.push(between);
+    }
+    if first > 0 {
+        segments.push((0, first - 1, 0));
+    }
+    if last < LAST_RANGE_END {
+        segments.push((last + 1, LAST_RANGE_END, 0));
+    }
+
+    let mut result: Vec<Range<S>> = Vec::new();
+    for range in ranges.iter() {
+        for (start, end, shift) in segments.iter() {
+            let (start, end) = (range.start.max(*start), range.end.min(*end));
+            if start <= end {
+                let shifted = |index: u32| (i64::from(index) + shift) as u32;
+                result.push(Range::new(shifted(start), shifted(end), range.size));
+            }
+        }
+    }
+
+    result.sort_by_key(|range| range.start);
+    result
 }
 
 #[cfg(test)]
 mod tests {
+    use super::assign;
+    use super::moved;
     use super::ranges_within;
     use super::Range;
     use super::LAST_RANGE_END;
     use std::cmp::PartialEq;
     use std::collections::BTreeMap;
 
-    impl PartialEq for Range {
+    impl<S: PartialEq> PartialEq for Range<S> {
         fn eq(&self, other: &Self) -> bool {
             self.start == other.start && self.end == other.end && self.size == other.size
         }
@@ -84,4 +147,53 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_assign() {
+        let mut tree: BTreeMap<u32, u32> = BTreeMap::new();
+        tree.insert(0, 0);
+
+        assign(&mut tree, 5, 9, 1);
+        assign(&mut tree, 20, 29, 2);
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            [(&0, &0), (&5, &1), (&10, &0), (&20, &2), (&30, &0)]
+        );
+
+        assign(&mut tree, 8, 24, 1);
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            [(&0, &0), (&5, &1), (&25, &2), (&30, &0)]
+        );
+
+        assign(&mut tree, 0, 29, 0);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), [(&0, &0)]);
+    }
+
+    #[test]
+    fn test_moved() {
+        let ranges = [
+            Range::new(0, 2, 10),
+            Range::new(3, 3, 30),
+            Range::new(4, 9, 20),
+        ];
+
+        assert_eq!(
+            moved(&ranges, 3, 7)[..],
+            [
+                Range::new(0, 2, 10),
+                Range::new(3, 6, 20),
+                Range::new(7, 7, 30),
+                Range::new(8, 9, 20),
+            ]
+        );
+        assert_eq!(
+            moved(&ranges, 3, 0)[..],
+            [
+                Range::new(0, 0, 30),
+                Range::new(1, 3, 10),
+                Range::new(4, 9, 20),
+            ]
+        );
+    }
 }
@@ -0,0 +1,722 @@
+//! Queries about how the items sit in a viewport scrolled to `scroll_top`
+//! and `viewport_height` pixels tall, in the coordinates of the offsets.
+
+use super::OffsetList;
+use crate::axis::Direction;
+#[cfg(not(any(feature = "std", test)))]
+use crate::float::Float;
+use core::cmp;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// The items in the viewport, the way react-virtuoso's `rangeChanged`
+/// reports them: the first and last ones at least partly visible, and the
+/// first and last ones entirely visible, if any is.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VisibleSummary {
+    pub first_visible: u32,
+    pub last_visible: u32,
+    pub first_fully_visible: Option<u32>,
+    pub last_fully_visible: Option<u32>,
+}
+
+/// The item under the middle of the viewport, and how far its own middle is
+/// from there: positive when it sits below (after) the viewport center.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CenterItem {
+    pub index: u32,
+    pub distance: f64,
+}
+
+/// Which edge of the items `snap` aligns the viewport to.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnapAlign {
+    /// The top of the item at the top of the viewport.
+    Start,
+    /// The middle of the item at the middle of the viewport.
+    Center,
+}
+
+/// Where a scroll should settle, and the item it settles on.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnapTarget {
+    pub index: u32,
+    pub scroll_top: f64,
+}
+
+/// A keyboard navigation step.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NavIntent {
+    Home,
+    End,
+    Next,
+    Previous,
+}
+
+/// The item a navigation step lands on, the scroll position that brings it
+/// fully into view, and how far that is from the current one.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NavTarget {
+    pub index: u32,
+    pub scroll_top: f64,
+    pub delta: f64,
+}
+
+/// The thumb of a custom scrollbar, in the pixels of its track.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollbarThumb {
+    pub size: f64,
+    pub position: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl OffsetList {
+    /// The fraction of the item inside the viewport, from 0 to 1. A zero
+    /// sized item is fully visible when it sits within the viewport.
+    pub fn visibility(&self, index: u32, scroll_top: f64, viewport_height: f64) -> f64 {
+        let item = self.item_at(index);
+        let (start, end) = (
+            f64::from(item.offset),
+            f64::from(item.offset) + f64::from(item.size),
+        );
+        let (top, bottom) = (scroll_top, scroll_top + viewport_height);
+
+        if item.size == 0 {
+            return match start >= top && start <= bottom {
+                true => 1.0,
+                false => 0.0,
+            };
+        }
+
+        let visible = end.min(bottom) - start.max(top);
+        (visible / f64::from(item.size)).clamp(0.0, 1.0)
+    }
+
+    /// The item of `count` at the center of the viewport, for carousels that
+    /// snap to it. In a gap, the closer of the two items around it.
+    pub fn index_at_center(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        count: u32,
+    ) -> Option<CenterItem> {
+        if count == 0 {
+            return None;
+        }
+
+        let center = scroll_top + viewport_height / 2.0;
+        let index = self.indices_at_offsets(&[center.max(0.0) as u32])[0].min(count - 1);
+        let distance = |index: u32| {
+            let item = self.item_at(index);
+            f64::from(item.offset) + f64::from(item.size) / 2.0 - center
+        };
+
+        let mut result = CenterItem {
+            index,
+            distance: distance(index),
+        };
+        if index + 1 < count {
+            let next = distance(index + 1);
+            if next.abs() < result.distance.abs() {
+                result = CenterItem {
+                    index: index + 1,
+                    distance: next,
+                };
+            }
+        }
+
+        Some(result)
+    }
+
+    /// The item-aligned scroll position nearest to `scroll_top`, for momentum
+    /// scrolling to settle on. Stays within the scrollable range of `count`
+    /// items, so the last items may not align.
+    pub fn snap(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        count: u32,
+        align: SnapAlign,
+    ) -> Option<SnapTarget> {
+        if count == 0 {
+            return None;
+        }
+
+        let target = match align {
+            SnapAlign::Center => {
+                let center = self.index_at_center(scroll_top, viewport_height, count)?;
+                SnapTarget {
+                    index: center.index,
+                    scroll_top: scroll_top + center.distance,
+                }
+            }
+            SnapAlign::Start => {
+                let index =
+                    self.indices_at_offsets(&[scroll_top.max(0.0) as u32])[0].min(count - 1);
+                let candidates = [index, cmp::min(index + 1, count - 1)];
+                let offsets = self.offsets_of(&candidates);
+
+                let (index, offset) = candidates
+                    .iter()
+                    .zip(offsets)
+                    .min_by(|(_, a), (_, b)| {
+                        (f64::from(*a) - scroll_top)
+                            .abs()
+                            .total_cmp(&(f64::from(*b) - scroll_top).abs())
+                    })
+                    .expect("there are two candidates");
+                SnapTarget {
+                    index: *index,
+                    scroll_top: f64::from(offset),
+                }
+            }
+        };
+
+        Some(self.clamped(target.index, target.scroll_top, viewport_height, count))
+    }
+
+    /// The page after the viewport: it overlaps the current one by `overlap`
+    /// pixels, less if that lets it start at an item boundary. Items taller
+    /// than the viewport are paged past.
+    pub fn page_down(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        overlap: f64,
+        count: u32,
+    ) -> Option<SnapTarget> {
+        if count == 0 {
+            return None;
+        }
+
+        let target = scroll_top + (viewport_height - overlap).max(1.0);
+        let index = self.indices_at_offsets(&[target.max(0.0) as u32])[0].min(count - 1);
+        let offsets = self.offsets_of(&[index, cmp::min(index + 1, count - 1)]);
+
+        let (index, offset) = match f64::from(offsets[0]) > scroll_top {
+            true => (index, offsets[0]),
+            false => (cmp::min(index + 1, count - 1), offsets[1]),
+        };
+        Some(self.clamped(index, f64::from(offset), viewport_height, count))
+    }
+
+    /// The page before the viewport, starting at the item boundary that keeps
+    /// the top `overlap` pixels of the current page in view.
+    pub fn page_up(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        overlap: f64,
+        count: u32,
+    ) -> Option<SnapTarget> {
+        if count == 0 {
+            return None;
+        }
+
+        let target = (scroll_top - (viewport_height - overlap).max(1.0)).max(0.0);
+        let index = self.indices_at_offsets(&[target as u32])[0].min(count - 1);
+        let offsets = self.offsets_of(&[index, cmp::min(index + 1, count - 1)]);
+
+        let (index, offset) =
+            match f64::from(offsets[0]) < target && f64::from(offsets[1]) < scroll_top {
+                true => (cmp::min(index + 1, count - 1), offsets[1]),
+                false => (index, offsets[0]),
+            };
+        Some(self.clamped(index, f64::from(offset), viewport_height, count))
+    }
+
+    /// Where `intent` takes the focus from `index`, among `count` items, and
+    /// the least scrolling that shows the new item in full. An item taller
+    /// than the viewport is aligned to its top.
+    pub fn navigate(
+        &self,
+        intent: NavIntent,
+        index: u32,
+        scroll_top: f64,
+        viewport_height: f64,
+        count: u32,
+    ) -> Option<NavTarget> {
+        if count == 0 {
+            return None;
+        }
+
+        let target = match intent {
+            NavIntent::Home => 0,
+            NavIntent::End => count - 1,
+            NavIntent::Next => cmp::min(index.saturating_add(1), count - 1),
+            NavIntent::Previous => cmp::min(index.saturating_sub(1), count - 1),
+        };
+
+        Some(self.reveal(target, scroll_top, viewport_height, count))
+    }
+
+    /// The first item past `from`, going `direction`, that can take the
+    /// focus, and the scrolling that shows it, as `navigate` does. Zero sized
+    /// and collapsed items are skipped, and so are the ones tagged with
+    /// `disabled_tag`, unless it is zero. None when no item is left that way.
+    pub fn next_focusable(
+        &self,
+        from: u32,
+        direction: Direction,
+        disabled_tag: u32,
+        scroll_top: f64,
+        viewport_height: f64,
+        count: u32,
+    ) -> Option<NavTarget> {
+        let mut index = from;
+        loop {
+            index = match direction {
+                Direction::Forward => index.checked_add(1).filter(|index| *index < count)?,
+                Direction::Backward => cmp::min(index.checked_sub(1)?, count.checked_sub(1)?),
+            };
+
+            if self.collapsed.contains(index) {
+                // jump over the rest of the collapsed span at once
+                index = match direction {
+                    Direction::Forward => self.collapsed.gaps(index, count - 1).first()?.0 - 1,
+                    Direction::Backward => self.collapsed.gaps(0, index).last()?.1 + 1,
+                };
+                continue;
+            }
+
+            if self.item_at(index).size == 0 {
+                // jump to the next sized range at once, rather than over the
+                // zero sized items one by one
+                let sizes = self.sizes();
+                index = match direction {
+                    Direction::Forward => {
+                        let ranges = sizes.ranges(index, count - 1);
+                        let (range, _) = ranges
+                            .iter()
+                            .find(|(range, _)| range.size > 0 && range.end > index)?;
+                        cmp::max(range.start, index + 1) - 1
+                    }
+                    Direction::Backward => {
+                        let ranges = sizes.ranges(0, index);
+                        let (range, _) = ranges
+                            .iter()
+                            .rev()
+                            .find(|(range, _)| range.size > 0 && range.start < index)?;
+                        cmp::min(range.end, index - 1) + 1
+                    }
+                };
+                continue;
+            }
+
+            if disabled_tag == 0 || self.tag_of(index) != disabled_tag {
+                return Some(self.reveal(index, scroll_top, viewport_height, count));
+            }
+        }
+    }
+
+    /// The thumb of a `track_height` pixels tall scrollbar over `count`
+    /// items, at least `min_thumb` tall. It follows the current total, so it
+    /// resizes as the estimates are refined.
+    pub fn scrollbar_thumb(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        track_height: f64,
+        min_thumb: f64,
+        count: u32,
+    ) -> ScrollbarThumb {
+        let (size, max_scroll) = self.thumb_size(viewport_height, track_height, min_thumb, count);
+        let progress = match max_scroll > 0.0 {
+            true => (scroll_top / max_scroll).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+
+        ScrollbarThumb {
+            size,
+            position: (track_height - size) * progress,
+        }
+    }
+
+    /// The inverse of `scrollbar_thumb`: the scroll position that puts the
+    /// thumb at `position`, for dragging it.
+    pub fn scroll_top_for_thumb(
+        &self,
+        position: f64,
+        viewport_height: f64,
+        track_height: f64,
+        min_thumb: f64,
+        count: u32,
+    ) -> f64 {
+        let (size, max_scroll) = self.thumb_size(viewport_height, track_height, min_thumb, count);
+        match track_height > size {
+            true => (position / (track_height - size)).clamp(0.0, 1.0) * max_scroll,
+            false => 0.0,
+        }
+    }
+
+    /// How far the viewport is scrolled through `count` items, from 0 at the
+    /// top to 1 at the bottom.
+    pub fn scroll_fraction(&self, scroll_top: f64, viewport_height: f64, count: u32) -> f64 {
+        let max_scroll = self.max_scroll(viewport_height, count);
+        match max_scroll > 0.0 {
+            true => (scroll_top / max_scroll).clamp(0.0, 1.0),
+            false => 0.0,
+        }
+    }
+
+    /// The inverse of `scroll_fraction`, for "jump to 75%" controls.
+    pub fn scroll_top_at_fraction(&self, fraction: f64, viewport_height: f64, count: u32) -> f64 {
+        let max_scroll = self.max_scroll(viewport_height, count);
+        fraction.clamp(0.0, 1.0) * max_scroll
+    }
+
+    /// The item `fraction` of the way down the total size of `count` items,
+    /// for scrubbers.
+    pub fn index_at_fraction(&self, fraction: f64, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+
+        let offset = fraction.clamp(0.0, 1.0) * f64::from(self.total_size(count));
+        self.indices_at_offsets(&[offset as u32])[0].min(count - 1)
+    }
+
+    /// Where `index` starts, as a fraction of the total size of `count`
+    /// items; how deep into the list a user got by reaching it.
+    pub fn fraction_of_index(&self, index: u32, count: u32) -> f64 {
+        let total = f64::from(self.total_size(count));
+        match total > 0.0 {
+            true => (f64::from(self.offset_of(index)) / total).clamp(0.0, 1.0),
+            false => 0.0,
+        }
+    }
+
+    /// Which of `count` items the viewport shows; none for an empty list.
+    pub fn visible_summary(
+        &self,
+        scroll_top: f64,
+        viewport_height: f64,
+        count: u32,
+    ) -> Option<VisibleSummary> {
+        let top = scroll_top.max(0.0);
+        let bottom = (scroll_top + viewport_height).max(top);
+        let (first, last) = self.visible_span(top as u32, bottom.ceil() as u32, count)?;
+
+        let fully_visible = |index: u32| {
+            let item = self.item_at(index);
+            f64::from(item.offset) >= top && f64::from(item.offset) + f64::from(item.size) <= bottom
+        };
+        let first_fully = (first..=last).find(|index| fully_visible(*index));
+        let last_fully = first_fully.and_then(|first_fully| {
+            (first_fully..=last)
+                .rev()
+                .find(|index| fully_visible(*index))
+        });
+
+        Some(VisibleSummary {
+            first_visible: first,
+            last_visible: last,
+            first_fully_visible: first_fully,
+            last_fully_visible: last_fully,
+        })
+    }
+}
+
+impl OffsetList {
+    /// The thumb size over `count` items, and how far they scroll.
+    fn thumb_size(
+        &self,
+        viewport_height: f64,
+        track_height: f64,
+        min_thumb: f64,
+        count: u32,
+    ) -> (f64, f64) {
+        let total = f64::from(self.total_size(count));
+        let size = match total > viewport_height {
+            true => (track_height * viewport_height / total).max(min_thumb),
+            false => track_height,
+        };
+
+        (size.min(track_height), (total - viewport_height).max(0.0))
+    }
+
+    /// How far `count` items scroll in a viewport `viewport_height` tall.
+    fn max_scroll(&self, viewport_height: f64, count: u32) -> f64 {
+        (f64::from(self.total_size(count)) - viewport_height).max(0.0)
+    }
+
+    /// The least scrolling that shows `index` in full, or its top when it is
+    /// taller than the viewport.
+    fn reveal(&self, index: u32, scroll_top: f64, viewport_height: f64, count: u32) -> NavTarget {
+        let item = self.item_at(index);
+        let (start, end) = (
+            f64::from(item.offset),
+            f64::from(item.offset) + f64::from(item.size),
+        );
+        let into_view = if start < scroll_top || end - start > viewport_height {
+            start
+        } else if end > scroll_top + viewport_height {
+            end - viewport_height
+        } else {
+            scroll_top
+        };
+
+        let settled = self.clamped(index, into_view, viewport_height, count);
+        NavTarget {
+            index,
+            scroll_top: settled.scroll_top,
+            delta: settled.scroll_top - scroll_top,
+        }
+    }
+
+    /// Keeps `scroll_top` within the scrollable range of `count` items.
+    fn clamped(&self, index: u32, scroll_top: f64, viewport_height: f64, count: u32) -> SnapTarget {
+        let max_scroll = self.max_scroll(viewport_height, count);
+        SnapTarget {
+            index,
+            scroll_top: scroll_top.clamp(0.0, max_scroll),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CenterItem, NavIntent, NavTarget, OffsetList, SnapAlign, SnapTarget};
+    use super::{ScrollbarThumb, VisibleSummary};
+    use crate::axis::Direction;
+
+    #[test]
+    fn test_visibility() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 20);
+        list.set_header_height(10);
+
+        assert_eq!(list.visibility(0, 0.0, 100.0), 1.0);
+        assert_eq!(list.visibility(0, 20.0, 100.0), 0.5);
+        assert_eq!(list.visibility(4, 0.0, 100.0), 0.5);
+        assert_eq!(list.visibility(5, 0.0, 100.0), 0.0);
+        assert_eq!(list.visibility(1, 35.0, 10.0), 0.5);
+    }
+
+    #[test]
+    fn test_visible_summary() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 20);
+
+        assert_eq!(
+            list.visible_summary(10.0, 50.0, 100),
+            Some(VisibleSummary {
+                first_visible: 0,
+                last_visible: 2,
+                first_fully_visible: Some(1),
+                last_fully_visible: Some(2),
+            })
+        );
+        assert_eq!(
+            list.visible_summary(0.0, 60.0, 2)
+                .map(|summary| summary.last_fully_visible),
+            Some(Some(1))
+        );
+
+        list.insert(5, 5, 100);
+        let tall = list
+            .visible_summary(110.0, 50.0, 100)
+            .expect("items are visible");
+        assert_eq!((tall.first_visible, tall.last_visible), (5, 5));
+        assert_eq!(tall.first_fully_visible, None);
+        assert_eq!(list.visible_summary(0.0, 50.0, 0), None);
+    }
+
+    #[test]
+    fn test_index_at_center() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 100);
+
+        let center = |scroll_top| list.index_at_center(scroll_top, 100.0, 10);
+        assert_eq!(
+            center(0.0),
+            Some(CenterItem {
+                index: 0,
+                distance: 0.0
+            })
+        );
+        assert_eq!(
+            center(120.0),
+            Some(CenterItem {
+                index: 1,
+                distance: -20.0
+            })
+        );
+        assert_eq!(center(5000.0).map(|item| item.index), Some(9));
+
+        list.set_gap(40);
+        let in_gap = list
+            .index_at_center(80.0, 100.0, 10)
+            .expect("an item is centered");
+        assert_eq!((in_gap.index, in_gap.distance), (1, 60.0));
+        assert_eq!(list.index_at_center(0.0, 100.0, 0), None);
+    }
+
+    #[test]
+    fn test_snap() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 100);
+        list.insert(3, 3, 50);
+
+        let snap = |scroll_top, align| list.snap(scroll_top, 100.0, 10, align);
+        let target = |index, scroll_top| Some(SnapTarget { index, scroll_top });
+
+        assert_eq!(snap(40.0, SnapAlign::Start), target(0, 0.0));
+        assert_eq!(snap(60.0, SnapAlign::Start), target(1, 100.0));
+        assert_eq!(snap(320.0, SnapAlign::Start), target(3, 300.0));
+        assert_eq!(snap(120.0, SnapAlign::Center), target(1, 100.0));
+        assert_eq!(snap(240.0, SnapAlign::Center), target(3, 275.0));
+        assert_eq!(snap(5000.0, SnapAlign::Start), target(9, 850.0));
+        assert_eq!(list.snap(0.0, 100.0, 0, SnapAlign::Start), None);
+    }
+
+    #[test]
+    fn test_paging() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 30);
+        list.insert(4, 4, 300);
+
+        let down = |scroll_top| list.page_down(scroll_top, 100.0, 10.0, 20);
+        let up = |scroll_top| list.page_up(scroll_top, 100.0, 10.0, 20);
+        let target = |index, scroll_top| Some(SnapTarget { index, scroll_top });
+
+        assert_eq!(down(0.0), target(3, 90.0));
+        assert_eq!(down(90.0), target(4, 120.0));
+        assert_eq!(down(120.0), target(5, 420.0));
+        assert_eq!(down(1000.0), target(19, 770.0));
+
+        assert_eq!(up(420.0), target(4, 120.0));
+        assert_eq!(up(120.0), target(1, 30.0));
+        assert_eq!(up(60.0), target(0, 0.0));
+        assert_eq!(list.page_down(0.0, 100.0, 10.0, 0), None);
+    }
+
+    #[test]
+    fn test_navigate() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 40);
+        list.insert(5, 5, 200);
+
+        let navigate =
+            |intent, index, scroll_top| list.navigate(intent, index, scroll_top, 100.0, 10);
+        let target = |index, scroll_top, delta| {
+            Some(NavTarget {
+                index,
+                scroll_top,
+                delta,
+            })
+        };
+
+        assert_eq!(navigate(NavIntent::Next, 0, 0.0), target(1, 0.0, 0.0));
+        assert_eq!(navigate(NavIntent::Next, 1, 0.0), target(2, 20.0, 20.0));
+        assert_eq!(navigate(NavIntent::Previous, 2, 40.0), target(1, 40.0, 0.0));
+        assert_eq!(
+            navigate(NavIntent::Previous, 1, 50.0),
+            target(0, 0.0, -50.0)
+        );
+        assert_eq!(navigate(NavIntent::Next, 4, 100.0), target(5, 200.0, 100.0));
+        assert_eq!(navigate(NavIntent::End, 0, 0.0), target(9, 460.0, 460.0));
+        assert_eq!(navigate(NavIntent::Home, 9, 460.0), target(0, 0.0, -460.0));
+        assert_eq!(navigate(NavIntent::Next, 9, 460.0), target(9, 460.0, 0.0));
+    }
+
+    #[test]
+    fn test_next_focusable() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 20);
+        list.insert_tagged(2, 2, 20, 9);
+        list.collapse(3, 5);
+        list.insert(7, 7, 0);
+
+        let next = |from, direction| list.next_focusable(from, direction, 9, 0.0, 50.0, 10);
+        let index = |target: Option<NavTarget>| target.map(|target| target.index);
+
+        assert_eq!(index(next(1, Direction::Forward)), Some(6));
+        assert_eq!(index(next(6, Direction::Forward)), Some(8));
+        assert_eq!(index(next(6, Direction::Backward)), Some(1));
+        assert_eq!(index(next(0, Direction::Backward)), None);
+        assert_eq!(index(next(9, Direction::Forward)), None);
+        assert_eq!(
+            next(6, Direction::Forward),
+            Some(NavTarget {
+                index: 8,
+                scroll_top: 50.0,
+                delta: 50.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_next_focusable_skips_zero_sized_runs_at_once() {
+        let unmeasured = OffsetList::new();
+        let next = |list: &OffsetList, from, direction| {
+            list.next_focusable(from, direction, 0, 0.0, 50.0, u32::MAX)
+                .map(|target| target.index)
+        };
+        assert_eq!(next(&unmeasured, 0, Direction::Forward), None);
+        assert_eq!(next(&unmeasured, u32::MAX - 1, Direction::Backward), None);
+
+        let mut list = OffsetList::new();
+        list.set_default_size(0);
+        list.insert(3, 3, 20);
+        list.insert(u32::MAX - 3, u32::MAX - 3, 20);
+        assert_eq!(next(&list, 3, Direction::Forward), Some(u32::MAX - 3));
+        assert_eq!(next(&list, u32::MAX - 3, Direction::Backward), Some(3));
+        assert_eq!(next(&list, u32::MAX - 3, Direction::Forward), None);
+        assert_eq!(next(&list, 3, Direction::Backward), None);
+    }
+
+    #[test]
+    fn test_scrollbar_thumb() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+
+        let thumb = |scroll_top, count| list.scrollbar_thumb(scroll_top, 100.0, 50.0, 4.0, count);
+        assert_eq!(
+            thumb(0.0, 100),
+            ScrollbarThumb {
+                size: 5.0,
+                position: 0.0
+            }
+        );
+        assert_eq!(thumb(450.0, 100).position, 22.5);
+        assert_eq!(thumb(900.0, 100).position, 45.0);
+        assert_eq!(thumb(0.0, 1000).size, 4.0);
+        assert_eq!(thumb(0.0, 5).size, 50.0);
+
+        assert_eq!(
+            list.scroll_top_for_thumb(22.5, 100.0, 50.0, 4.0, 100),
+            450.0
+        );
+        assert_eq!(
+            list.scroll_top_for_thumb(80.0, 100.0, 50.0, 4.0, 100),
+            900.0
+        );
+        assert_eq!(list.scroll_top_for_thumb(10.0, 100.0, 50.0, 4.0, 5), 0.0);
+    }
+
+    #[test]
+    fn test_scroll_fractions() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(50, 99, 30);
+
+        assert_eq!(list.scroll_fraction(950.0, 100.0, 100), 0.5);
+        assert_eq!(list.scroll_fraction(5000.0, 100.0, 100), 1.0);
+        assert_eq!(list.scroll_fraction(0.0, 100.0, 5), 0.0);
+        assert_eq!(list.scroll_top_at_fraction(0.5, 100.0, 100), 950.0);
+
+        assert_eq!(list.index_at_fraction(0.25, 100), 50);
+        assert_eq!(list.index_at_fraction(1.0, 100), 99);
+        assert_eq!(list.index_at_fraction(0.5, 0), 0);
+        assert_eq!(list.fraction_of_index(50, 100), 0.25);
+        assert_eq!(list.fraction_of_index(100, 100), 1.0);
+    }
+}
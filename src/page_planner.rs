@@ -0,0 +1,160 @@
+use std::cmp;
+use std::collections::BTreeSet;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// What to do with the data, after `PagePlanner::plan`: the pages to
+/// request, in order, and the cached pages that can be dropped.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PagePlan {
+    request: Vec<u32>,
+    evict: Vec<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PagePlan {
+    pub fn is_empty(&self) -> bool {
+        self.request.is_empty() && self.evict.is_empty()
+    }
+
+    pub fn request(&self) -> Vec<u32> {
+        self.request.clone()
+    }
+
+    pub fn evict(&self) -> Vec<u32> {
+        self.evict.clone()
+    }
+}
+
+/// Plans the loading of the data in pages of `page_size` items, for the
+/// rendered range and `prefetch` items around it. A page is requested once,
+/// and again only after `mark_failed`; the loaded pages more than `retention`
+/// pages away from the needed ones are evicted.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct PagePlanner {
+    page_size: u32,
+    prefetch: u32,
+    retention: u32,
+    loaded: BTreeSet<u32>,
+    pending: BTreeSet<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PagePlanner {
+    pub fn new(page_size: u32, prefetch: u32) -> PagePlanner {
+        PagePlanner {
+            page_size: cmp::max(page_size, 1),
+            prefetch,
+            retention: 0,
+            loaded: BTreeSet::new(),
+            pending: BTreeSet::new(),
+        }
+    }
+
+    /// Keeps `pages` more pages cached on each side of the needed ones.
+    pub fn set_retention(&mut self, pages: u32) {
+        self.retention = pages;
+    }
+
+    pub fn page_of(&self, index: u32) -> u32 {
+        index / self.page_size
+    }
+
+    pub fn is_loaded(&self, page: u32) -> bool {
+        self.loaded.contains(&page)
+    }
+
+    pub fn mark_loaded(&mut self, page: u32) {
+        self.pending.remove(&page);
+        self.loaded.insert(page);
+    }
+
+    /// Forgets a failed request, so that the next plan asks for the page again.
+    pub fn mark_failed(&mut self, page: u32) {
+        self.pending.remove(&page);
+    }
+
+    /// The plan for rendering `first_index..=last_index` of `total_count`
+    /// items. The pages to request count as pending from then on, and the
+    /// evicted ones as no longer loaded.
+    pub fn plan(&mut self, first_index: u32, last_index: u32, total_count: u32) -> PagePlan {
+        if total_count == 0 {
+            return self.evict_outside(1, 0);
+        }
+
+        let last_index = cmp::min(last_index, total_count - 1);
+        let first_index = cmp::min(first_index, last_index);
+        let first_page = self.page_of(first_index.saturating_sub(self.prefetch));
+        let last_page = self.page_of(cmp::min(
+            last_index.saturating_add(self.prefetch),
+            total_count - 1,
+        ));
+
+        let mut plan = self.evict_outside(
+            first_page.saturating_sub(self.retention),
+            last_page.saturating_add(self.retention),
+        );
+        for page in first_page..=last_page {
+            if !self.loaded.contains(&page) && self.pending.insert(page) {
+                plan.request.push(page);
+            }
+        }
+
+        plan
+    }
+}
+
+impl PagePlanner {
+    /// A plan evicting the loaded pages outside `first..=last`.
+    fn evict_outside(&mut self, first: u32, last: u32) -> PagePlan {
+        let evict: Vec<u32> = self
+            .loaded
+            .iter()
+            .filter(|page| **page < first || **page > last)
+            .copied()
+            .collect();
+        for page in evict.iter() {
+            self.loaded.remove(page);
+        }
+
+        PagePlan {
+            request: Vec::new(),
+            evict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PagePlanner;
+
+    #[test]
+    fn test_requests_once() {
+        let mut planner = PagePlanner::new(50, 20);
+
+        let plan = planner.plan(40, 60, 1000);
+        assert_eq!(plan.request(), [0, 1]);
+        assert!(planner.plan(40, 60, 1000).is_empty());
+
+        planner.mark_loaded(0);
+        planner.mark_failed(1);
+        assert_eq!(planner.plan(40, 60, 1000).request(), [1]);
+        assert!(planner.plan(980, 999, 90).request().is_empty());
+    }
+
+    #[test]
+    fn test_evicts_away_from_the_range() {
+        let mut planner = PagePlanner::new(10, 0);
+        planner.set_retention(1);
+        for page in 0..5 {
+            planner.mark_loaded(page);
+        }
+
+        let plan = planner.plan(30, 35, 100);
+        assert_eq!(plan.evict(), [0, 1]);
+        assert!(plan.request().is_empty());
+        assert!(planner.is_loaded(2));
+        assert!(!planner.is_loaded(0));
+    }
+}
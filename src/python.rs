@@ -0,0 +1,73 @@
+//! Python bindings through pyo3, for data apps that compute the rendered
+//! window server side, with the same math the frontend runs.
+
+use crate::bindings::offset_list_bindings;
+use crate::offset_list::OffsetList;
+use pyo3::prelude::*;
+
+/// The list shares its sizes with its snapshots through `Rc`, so it stays on
+/// the thread that made it.
+#[pyclass(name = "OffsetList", unsendable)]
+pub struct PyOffsetList {
+    list: OffsetList,
+}
+
+offset_list_bindings! {
+    #[pyclass(name = "Item", get_all)]
+    item PyItem;
+
+    #[pymethods]
+    impl PyOffsetList {
+        #[new]
+        pub fn new() -> PyOffsetList {
+            PyOffsetList {
+                list: OffsetList::new(),
+            }
+        }
+    }
+
+    methods [], writes (&mut self);
+}
+
+impl PyOffsetList {
+    fn list(&self) -> &OffsetList {
+        &self.list
+    }
+
+    fn list_mut(&mut self) -> &mut OffsetList {
+        &mut self.list
+    }
+}
+
+impl Default for PyOffsetList {
+    fn default() -> Self {
+        PyOffsetList::new()
+    }
+}
+
+#[pymodule]
+fn rust_virtuoso(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyOffsetList>()?;
+    module.add_class::<PyItem>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PyOffsetList;
+
+    #[test]
+    fn test_wraps_the_list() {
+        let mut list = PyOffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 3, 20);
+
+        let items = list.range(0, 45, 0, 9);
+        let sizes: Vec<(u32, u32, u32)> = items
+            .iter()
+            .map(|item| (item.index, item.size, item.offset))
+            .collect();
+        assert_eq!(sizes, [(0, 10, 0), (1, 10, 10), (2, 20, 20), (3, 20, 40)]);
+        assert_eq!(list.offsets_of(vec![1, 4]), [10, 60]);
+    }
+}
@@ -0,0 +1,182 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cmp;
+
+/// A set of indices stored as disjoint, non-adjacent inclusive ranges, keyed
+/// by their start.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RangeSet {
+    ranges: BTreeMap<u32, u32>,
+}
+
+impl RangeSet {
+    pub fn new() -> RangeSet {
+        RangeSet {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    /// The number of disjoint ranges.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// The ranges in order, as inclusive `(start, end)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.ranges.iter().map(|(start, end)| (*start, *end))
+    }
+
+    pub fn contains(&self, index: u32) -> bool {
+        match self.ranges.range(..=index).next_back() {
+            None => false,
+            Some((_, end)) => *end >= index,
+        }
+    }
+
+    pub fn insert(&mut self, start: u32, end: u32) {
+        let mut new_start = start;
+        let mut new_end = end;
+
+        if let Some((previous_start, previous_end)) = self.ranges.range(..=start).next_back() {
+            if previous_end.saturating_add(1) >= start {
+                new_start = *previous_start;
+                new_end = cmp::max(new_end, *previous_end);
+            }
+        }
+
+        let merged: Vec<u32> = self
+            .ranges
+            .range(new_start..=new_end.saturating_add(1))
+            .map(|(start, _)| *start)
+            .collect();
+
+        for merged_start in merged {
+            if let Some(merged_end) = self.ranges.remove(&merged_start) {
+                new_end = cmp::max(new_end, merged_end);
+            }
+        }
+
+        self.ranges.insert(new_start, new_end);
+    }
+
+    /// Takes `start..=end` out of the set, splitting the ranges that stick
+    /// out of it. Returns the number of indices removed.
+    pub fn remove(&mut self, start: u32, end: u32) -> u32 {
+        let mut removed: u32 = 0;
+
+        for (range_start, range_end) in self.overlapping(start, end) {
+            self.ranges.remove(&range_start);
+            if range_start < start {
+                self.ranges.insert(range_start, start - 1);
+            }
+            if range_end > end {
+                self.ranges.insert(end + 1, range_end);
+            }
+
+            let covered = cmp::min(range_end, end) - cmp::max(range_start, start);
+            removed = removed.saturating_add(covered).saturating_add(1);
+        }
+
+        removed
+    }
+
+    /// The parts of `start..=end` in the set.
+    pub fn within(&self, start: u32, end: u32) -> Vec<(u32, u32)> {
+        self.overlapping(start, end)
+            .into_iter()
+            .map(|(range_start, range_end)| {
+                (cmp::max(range_start, start), cmp::min(range_end, end))
+            })
+            .collect()
+    }
+
+    /// The parts of `start..=end` not in the set.
+    pub fn gaps(&self, start: u32, end: u32) -> Vec<(u32, u32)> {
+        let mut result = Vec::new();
+        let mut cursor = start;
+
+        for (range_start, range_end) in self.overlapping(start, end) {
+            if range_start > cursor {
+                result.push((cursor, range_start - 1));
+            }
+
+            if range_end >= end {
+                return result;
+            }
+            cursor = cmp::max(cursor, range_end + 1);
+        }
+
+        result.push((cursor, end));
+        result
+    }
+
+    fn overlapping(&self, start: u32, end: u32) -> Vec<(u32, u32)> {
+        let first = match self.ranges.range(..=start).next_back() {
+            None => start,
+            Some((range_start, _)) => *range_start,
+        };
+
+        self.ranges
+            .range(first..=end)
+            .filter(|(_, range_end)| **range_end >= start)
+            .map(|(range_start, range_end)| (*range_start, *range_end))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeSet;
+
+    #[test]
+    fn test_insert_merges() {
+        let mut set = RangeSet::new();
+        set.insert(5, 10);
+        set.insert(20, 30);
+        set.insert(11, 12);
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(5, 12), (20, 30)]);
+
+        set.insert(0, 25);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(0, 30)]);
+        assert!(set.contains(30));
+        assert!(!set.contains(31));
+    }
+
+    #[test]
+    fn test_gaps() {
+        let mut set = RangeSet::new();
+        assert_eq!(set.gaps(0, 5), [(0, 5)]);
+
+        set.insert(2, 3);
+        set.insert(7, 20);
+
+        assert_eq!(set.gaps(0, 10), [(0, 1), (4, 6)]);
+        assert_eq!(set.gaps(3, 25), [(4, 6), (21, 25)]);
+        assert!(set.gaps(8, 9).is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = RangeSet::new();
+        set.insert(0, 10);
+        set.insert(20, 30);
+        set.insert(40, 50);
+
+        assert_eq!(set.remove(5, 25), 12);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(0, 4), (26, 30), (40, 50)]);
+        assert_eq!(set.remove(31, 39), 0);
+        assert_eq!(set.remove(0, u32::MAX), 21);
+        assert_eq!(set.len(), 0);
+    }
+
+    #[test]
+    fn test_within() {
+        let mut set = RangeSet::new();
+        set.insert(0, 10);
+        set.insert(20, 30);
+
+        assert_eq!(set.within(5, 25), [(5, 10), (20, 25)]);
+        assert!(set.within(11, 19).is_empty());
+    }
+}
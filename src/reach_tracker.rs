@@ -0,0 +1,153 @@
+use crate::offset_list::OffsetList;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrefetchUnit {
+    Items,
+    Pixels,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReachEvents {
+    pub start_reached: bool,
+    pub end_reached: bool,
+}
+
+/// Edge-triggered startReached / endReached detection. An event fires once,
+/// when the rendered range comes within `distance` of the respective edge, and
+/// is re-armed after the range moves away again. A change of the total count
+/// (a page was loaded) re-arms both edges.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ReachTracker {
+    distance: u32,
+    unit: PrefetchUnit,
+    total_count: u32,
+    start_armed: bool,
+    end_armed: bool,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ReachTracker {
+    pub fn new(distance: u32, unit: PrefetchUnit) -> ReachTracker {
+        ReachTracker {
+            distance,
+            unit,
+            total_count: 0,
+            start_armed: true,
+            end_armed: true,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        list: &OffsetList,
+        first_index: u32,
+        last_index: u32,
+        total_count: u32,
+    ) -> ReachEvents {
+        if total_count != self.total_count {
+            self.total_count = total_count;
+            self.start_armed = true;
+            self.end_armed = true;
+        }
+
+        let (start_distance, end_distance) = match self.unit {
+            PrefetchUnit::Items => (
+                first_index,
+                total_count.saturating_sub(last_index.saturating_add(1)),
+            ),
+            PrefetchUnit::Pixels => {
+                let offsets =
+                    list.offsets_of(&[first_index, last_index.saturating_add(1), total_count]);
+                (offsets[0], offsets[2].saturating_sub(offsets[1]))
+            }
+        };
+
+        let start_near = start_distance <= self.distance;
+        let end_near = end_distance <= self.distance;
+
+        let events = ReachEvents {
+            start_reached: start_near && self.start_armed,
+            end_reached: end_near && self.end_armed,
+        };
+
+        self.start_armed = !start_near;
+        self.end_armed = !end_near;
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrefetchUnit;
+    use super::ReachEvents;
+    use super::ReachTracker;
+    use crate::offset_list::OffsetList;
+
+    const NOTHING: ReachEvents = ReachEvents {
+        start_reached: false,
+        end_reached: false,
+    };
+
+    const END: ReachEvents = ReachEvents {
+        start_reached: false,
+        end_reached: true,
+    };
+
+    #[test]
+    fn test_item_threshold_is_edge_triggered() {
+        let list = OffsetList::new();
+        let mut tracker = ReachTracker::new(5, PrefetchUnit::Items);
+
+        tracker.update(&list, 0, 10, 100);
+        assert_eq!(tracker.update(&list, 50, 60, 100), NOTHING);
+        assert_eq!(tracker.update(&list, 84, 94, 100), END);
+        assert_eq!(tracker.update(&list, 85, 95, 100), NOTHING);
+
+        tracker.update(&list, 50, 60, 100);
+        assert_eq!(tracker.update(&list, 89, 99, 100), END);
+    }
+
+    #[test]
+    fn test_start_reached() {
+        let list = OffsetList::new();
+        let mut tracker = ReachTracker::new(2, PrefetchUnit::Items);
+
+        let events = tracker.update(&list, 0, 10, 100);
+        assert!(events.start_reached);
+        assert!(!tracker.update(&list, 1, 11, 100).start_reached);
+
+        tracker.update(&list, 20, 30, 100);
+        assert!(tracker.update(&list, 2, 12, 100).start_reached);
+    }
+
+    #[test]
+    fn test_new_page_rearms() {
+        let list = OffsetList::new();
+        let mut tracker = ReachTracker::new(0, PrefetchUnit::Items);
+
+        tracker.update(&list, 0, 10, 20);
+        assert_eq!(tracker.update(&list, 9, 19, 20), END);
+        assert_eq!(tracker.update(&list, 9, 19, 20), NOTHING);
+        assert_eq!(tracker.update(&list, 29, 39, 40), END);
+    }
+
+    #[test]
+    fn test_pixel_threshold() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(90, 99, 100);
+
+        let mut tracker = ReachTracker::new(250, PrefetchUnit::Pixels);
+        tracker.update(&list, 0, 10, 100);
+
+        // items 88..=99 remain below: 2 * 10 + 10 * 100 pixels
+        assert_eq!(tracker.update(&list, 77, 87, 100), NOTHING);
+        // items 98 and 99 remain below: 200 pixels
+        assert_eq!(tracker.update(&list, 87, 97, 100), END);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::range_set::RangeSet;
+use std::cmp;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Selected indices, kept as ranges, so that selecting all of a million rows
+/// costs one range rather than a million entries of a JS `Set`.
+///
+/// The ranges come back flattened into `[start, end, start, end, ...]`, both
+/// ends inclusive.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default)]
+pub struct SelectionSet {
+    ranges: RangeSet,
+    /// Where the last toggle happened, for `extend_to`.
+    anchor: Option<u32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SelectionSet {
+    pub fn new() -> SelectionSet {
+        SelectionSet::default()
+    }
+
+    pub fn is_selected(&self, index: u32) -> bool {
+        self.ranges.contains(index)
+    }
+
+    /// Selects `from..=to`, in either order.
+    pub fn select_range(&mut self, from: u32, to: u32) {
+        self.ranges.insert(cmp::min(from, to), cmp::max(from, to));
+    }
+
+    pub fn deselect_range(&mut self, from: u32, to: u32) {
+        self.ranges.remove(cmp::min(from, to), cmp::max(from, to));
+    }
+
+    /// Flips the selection of `index` and returns whether it is selected now.
+    /// The index becomes the anchor of `extend_to`.
+    pub fn toggle(&mut self, index: u32) -> bool {
+        self.anchor = Some(index);
+        match self.ranges.contains(index) {
+            true => {
+                self.ranges.remove(index, index);
+                false
+            }
+            false => {
+                self.ranges.insert(index, index);
+                true
+            }
+        }
+    }
+
+    /// Selects from the anchor to `index`, as a shift click does. Without an
+    /// anchor, `index` becomes it.
+    pub fn extend_to(&mut self, index: u32) {
+        let anchor = *self.anchor.get_or_insert(index);
+        self.select_range(anchor, index);
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges = RangeSet::new();
+        self.anchor = None;
+    }
+
+    /// The number of selected indices.
+    pub fn count(&self) -> u32 {
+        self.ranges.iter().fold(0u32, |count, (start, end)| {
+            count.saturating_add(end - start).saturating_add(1)
+        })
+    }
+
+    pub fn ranges(&self) -> Vec<u32> {
+        self.ranges
+            .iter()
+            .flat_map(|(start, end)| [start, end])
+            .collect()
+    }
+
+    /// The selected parts of `start..=end`, usually the rendered indices.
+    pub fn intersecting(&self, start: u32, end: u32) -> Vec<u32> {
+        self.ranges
+            .within(start, end)
+            .into_iter()
+            .flat_map(|(start, end)| [start, end])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SelectionSet;
+
+    #[test]
+    fn test_ranges() {
+        let mut selection = SelectionSet::new();
+        selection.select_range(0, 999_999);
+        selection.deselect_range(20, 10);
+        assert!(!selection.toggle(500));
+
+        assert_eq!(selection.ranges(), [0, 9, 21, 499, 501, 999_999]);
+        assert_eq!(selection.count(), 999_988);
+        assert_eq!(selection.intersecting(495, 505), [495, 499, 501, 505]);
+
+        selection.clear();
+        assert_eq!(selection.count(), 0);
+    }
+
+    #[test]
+    fn test_extend_to() {
+        let mut selection = SelectionSet::new();
+        assert!(selection.toggle(10));
+        selection.extend_to(4);
+
+        assert_eq!(selection.ranges(), [4, 10]);
+        assert!(selection.is_selected(7));
+    }
+}
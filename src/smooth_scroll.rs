@@ -0,0 +1,168 @@
+use crate::offset_list::OffsetList;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// A deterministic scrollTop animation. JS either asks for the whole frame
+/// plan up front or calls `step` with the elapsed time on every animation
+/// frame and applies the returned scrollTop.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ScrollAnimation {
+    from: f64,
+    to: f64,
+    duration: f64,
+    easing: Easing,
+    elapsed: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ScrollAnimation {
+    pub fn new(from: f64, to: f64, duration: f64, easing: Easing) -> ScrollAnimation {
+        ScrollAnimation {
+            from,
+            to,
+            duration,
+            easing,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn to_index(
+        list: &OffsetList,
+        from: f64,
+        index: u32,
+        duration: f64,
+        easing: Easing,
+    ) -> ScrollAnimation {
+        let to = list.offsets_of(&[index])[0];
+        ScrollAnimation::new(from, f64::from(to), duration, easing)
+    }
+
+    pub fn target(&self) -> f64 {
+        self.to
+    }
+
+    pub fn value_at(&self, elapsed: f64) -> f64 {
+        if self.duration <= 0.0 || elapsed >= self.duration {
+            return self.to;
+        }
+
+        let progress = self.easing.apply(elapsed.max(0.0) / self.duration);
+        self.from + (self.to - self.from) * progress
+    }
+
+    /// Advances the animation by `delta` milliseconds and returns the
+    /// scrollTop for the new point in time.
+    pub fn step(&mut self, delta: f64) -> f64 {
+        self.elapsed += delta;
+        self.value_at(self.elapsed)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.duration <= 0.0 || self.elapsed >= self.duration
+    }
+
+    /// The scrollTop for every frame of the animation, ending exactly at the target.
+    pub fn frames(&self, frame_duration: f64) -> Vec<f64> {
+        let mut result: Vec<f64> = Vec::new();
+
+        if frame_duration > 0.0 {
+            let mut elapsed = frame_duration;
+            while elapsed < self.duration {
+                result.push(self.value_at(elapsed));
+                elapsed += frame_duration;
+            }
+        }
+
+        result.push(self.to);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Easing;
+    use super::ScrollAnimation;
+    use crate::offset_list::OffsetList;
+
+    #[test]
+    fn test_linear_frames() {
+        let animation = ScrollAnimation::new(0.0, 100.0, 40.0, Easing::Linear);
+
+        assert_eq!(animation.frames(10.0), [25.0, 50.0, 75.0, 100.0]);
+    }
+
+    #[test]
+    fn test_frames_end_at_target() {
+        let animation = ScrollAnimation::new(200.0, 50.0, 35.0, Easing::EaseInOut);
+        let frames = animation.frames(16.0);
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames.last(), Some(&50.0));
+        assert!(frames.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn test_easing_shapes() {
+        let ease_in = ScrollAnimation::new(0.0, 100.0, 100.0, Easing::EaseIn);
+        let ease_out = ScrollAnimation::new(0.0, 100.0, 100.0, Easing::EaseOut);
+
+        assert!(ease_in.value_at(50.0) < 50.0);
+        assert!(ease_out.value_at(50.0) > 50.0);
+        assert_eq!(ease_in.value_at(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut animation = ScrollAnimation::new(0.0, 100.0, 20.0, Easing::Linear);
+
+        assert_eq!(animation.step(10.0), 50.0);
+        assert!(!animation.is_done());
+        assert_eq!(animation.step(15.0), 100.0);
+        assert!(animation.is_done());
+    }
+
+    #[test]
+    fn test_zero_duration() {
+        let animation = ScrollAnimation::new(10.0, 30.0, 0.0, Easing::Linear);
+
+        assert!(animation.is_done());
+        assert_eq!(animation.frames(16.0), [30.0]);
+    }
+
+    #[test]
+    fn test_to_index() {
+        let mut list = OffsetList::new();
+        list.insert(0, 0, 10);
+        list.insert(2, 4, 20);
+
+        let animation = ScrollAnimation::to_index(&list, 0.0, 6, 100.0, Easing::Linear);
+
+        assert_eq!(animation.target(), 90.0);
+    }
+}
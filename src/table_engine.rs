@@ -0,0 +1,271 @@
+use crate::axis::Axis;
+use crate::offset_list::OffsetList;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableRange {
+    pub first_row: u32,
+    pub last_row: u32,
+    pub first_column: u32,
+    pub last_column: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CellSpan {
+    row: u32,
+    column: u32,
+    row_span: u32,
+    column_span: u32,
+}
+
+impl CellSpan {
+    fn intersects(&self, range: &TableRange) -> bool {
+        self.row <= range.last_row
+            && self.row.saturating_add(self.row_span) > range.first_row
+            && self.column <= range.last_column
+            && self.column.saturating_add(self.column_span) > range.first_column
+    }
+}
+
+/// Virtualizes a table in both directions. Row heights and column widths are
+/// kept in two independent offset lists, and the visible rectangle is the
+/// product of the visible span along each axis. Ranges are inclusive.
+///
+/// Cells spanning several rows or columns pull the rectangle out to their
+/// origin whenever any part of them is visible, so they get rendered.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TableEngine {
+    rows: OffsetList,
+    columns: OffsetList,
+    spans: Vec<CellSpan>,
+    row_count: u32,
+    column_count: u32,
+    scroll_left: f64,
+    scroll_top: f64,
+    viewport_width: f64,
+    viewport_height: f64,
+    overscan: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TableEngine {
+    pub fn new(row_count: u32, column_count: u32) -> TableEngine {
+        TableEngine {
+            rows: OffsetList::new(),
+            columns: OffsetList::new(),
+            spans: Vec::new(),
+            row_count,
+            column_count,
+            scroll_left: 0.0,
+            scroll_top: 0.0,
+            viewport_width: 0.0,
+            viewport_height: 0.0,
+            overscan: 0,
+        }
+    }
+
+    pub fn set_counts(&mut self, row_count: u32, column_count: u32) {
+        self.row_count = row_count;
+        self.column_count = column_count;
+    }
+
+    pub fn set_viewport(&mut self, viewport_width: f64, viewport_height: f64) {
+        self.viewport_width = viewport_width.max(0.0);
+        self.viewport_height = viewport_height.max(0.0);
+    }
+
+    pub fn set_scroll(&mut self, scroll_left: f64, scroll_top: f64) {
+        self.scroll_left = scroll_left.max(0.0);
+        self.scroll_top = scroll_top.max(0.0);
+    }
+
+    pub fn set_overscan(&mut self, overscan: u32) {
+        self.overscan = overscan;
+    }
+
+    pub fn measure_row(&mut self, index: u32, height: u32) {
+        self.rows.insert(index, index, height);
+    }
+
+    pub fn measure_column(&mut self, index: u32, width: u32) {
+        self.columns.insert(index, index, width);
+    }
+
+    pub fn add_span(&mut self, row: u32, column: u32, row_span: u32, column_span: u32) {
+        self.spans.push(CellSpan {
+            row,
+            column,
+            row_span: row_span.max(1),
+            column_span: column_span.max(1),
+        });
+    }
+
+    pub fn clear_spans(&mut self) {
+        self.spans.clear();
+    }
+
+    pub fn row_offset(&self, index: u32) -> u32 {
+        self.rows.offsets_of(&[index])[0]
+    }
+
+    pub fn column_offset(&self, index: u32) -> u32 {
+        self.columns.offsets_of(&[index])[0]
+    }
+
+    pub fn total_height(&self) -> u32 {
+        self.rows.total_size(self.row_count)
+    }
+
+    pub fn total_width(&self) -> u32 {
+        self.columns.total_size(self.column_count)
+    }
+
+    pub fn visible_range(&self) -> Option<TableRange> {
+        let (first_row, last_row) = self.visible_span(Axis::Vertical)?;
+        let (first_column, last_column) = self.visible_span(Axis::Horizontal)?;
+
+        let mut range = TableRange {
+            first_row,
+            last_row,
+            first_column,
+            last_column,
+        };
+
+        // pulling in one span can make another one intersect
+        loop {
+            let mut expanded = range;
+            for span in self.spans.iter().filter(|span| span.intersects(&range)) {
+                expanded.first_row = expanded.first_row.min(span.row);
+                expanded.first_column = expanded.first_column.min(span.column);
+            }
+
+            if expanded == range {
+                return Some(range);
+            }
+            range = expanded;
+        }
+    }
+}
+
+impl TableEngine {
+    fn visible_span(&self, axis: Axis) -> Option<(u32, u32)> {
+        let (list, count) = match axis {
+            Axis::Vertical => (&self.rows, self.row_count),
+            Axis::Horizontal => (&self.columns, self.column_count),
+        };
+
+        let overscan = f64::from(self.overscan);
+        let scroll_offset = axis.main(self.scroll_left, self.scroll_top);
+        let viewport_size = axis.main(self.viewport_width, self.viewport_height);
+
+        let start_offset = (scroll_offset - overscan).max(0.0) as u32;
+        let end_offset = (scroll_offset + viewport_size + overscan) as u32;
+
+        list.visible_span(start_offset, end_offset, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TableEngine;
+    use super::TableRange;
+
+    #[test]
+    fn test_visible_range() {
+        let mut table = TableEngine::new(1000, 50);
+        table.set_viewport(400.0, 300.0);
+        table.measure_row(0, 30);
+        table.measure_column(0, 100);
+        table.measure_column(1, 250);
+        table.set_scroll(120.0, 95.0);
+
+        assert_eq!(
+            table.visible_range(),
+            Some(TableRange {
+                first_row: 3,
+                last_row: 13,
+                first_column: 1,
+                last_column: 3,
+            })
+        );
+        assert_eq!(table.total_height(), 30000);
+        assert_eq!(table.total_width(), 5150);
+    }
+
+    #[test]
+    fn test_clamps_to_counts() {
+        let mut table = TableEngine::new(5, 2);
+        table.set_viewport(400.0, 300.0);
+        table.measure_row(0, 30);
+        table.measure_column(0, 100);
+
+        assert_eq!(
+            table.visible_range(),
+            Some(TableRange {
+                first_row: 0,
+                last_row: 4,
+                first_column: 0,
+                last_column: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unmeasured_and_empty() {
+        let mut table = TableEngine::new(5, 2);
+        table.set_viewport(400.0, 300.0);
+
+        assert_eq!(
+            table.visible_range(),
+            Some(TableRange {
+                first_row: 0,
+                last_row: 0,
+                first_column: 0,
+                last_column: 0,
+            })
+        );
+
+        table.set_counts(0, 2);
+        assert_eq!(table.visible_range(), None);
+    }
+
+    #[test]
+    fn test_spans_pull_in_their_origin() {
+        let mut table = TableEngine::new(100, 10);
+        table.set_viewport(200.0, 100.0);
+        table.measure_row(0, 20);
+        table.measure_column(0, 100);
+        table.set_scroll(300.0, 200.0);
+
+        let visible = TableRange {
+            first_row: 10,
+            last_row: 14,
+            first_column: 3,
+            last_column: 4,
+        };
+        assert_eq!(table.visible_range(), Some(visible));
+
+        // not visible: ends right before the first visible row
+        table.add_span(5, 3, 5, 1);
+        assert_eq!(table.visible_range(), Some(visible));
+
+        table.add_span(8, 2, 3, 2);
+        // becomes visible through the previous span, and then pulls in the
+        // first one as well
+        table.add_span(6, 0, 3, 3);
+        assert_eq!(
+            table.visible_range(),
+            Some(TableRange {
+                first_row: 5,
+                last_row: 14,
+                first_column: 0,
+                last_column: 4,
+            })
+        );
+
+        table.clear_spans();
+        assert_eq!(table.visible_range(), Some(visible));
+    }
+}
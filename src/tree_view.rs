@@ -0,0 +1,269 @@
+use std::cell::{Cell, Ref, RefCell};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Not a node, in `TreeView::rows`.
+const HIDDEN: u32 = u32::MAX;
+
+#[derive(Clone, Debug)]
+struct Node {
+    parent: Option<u32>,
+    children: Vec<u32>,
+    expanded: bool,
+}
+
+/// A tree of nodes flattened into the index space of a list: every root is a
+/// row, and the children of an expanded node follow it, depth first. Nodes
+/// start collapsed and are identified by the order they were added in.
+///
+/// The node ids of the rows make good keys for `OffsetList::set_keys`, so
+/// that the measurements follow the nodes when a subtree opens or closes.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct TreeView {
+    nodes: Vec<Node>,
+    roots: Vec<u32>,
+    /// The node of every row, flattened again on the first query after a
+    /// change.
+    flat: RefCell<Vec<u32>>,
+    /// The row of every node, `HIDDEN` under a collapsed ancestor.
+    rows: RefCell<Vec<u32>>,
+    stale: Cell<bool>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl TreeView {
+    pub fn new() -> TreeView {
+        TreeView {
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            flat: RefCell::new(Vec::new()),
+            rows: RefCell::new(Vec::new()),
+            stale: Cell::new(false),
+        }
+    }
+
+    /// Adds a node as the last child of `parent`, or as the last root, and
+    /// returns its id. An unknown parent adds a root.
+    pub fn add_node(&mut self, parent: Option<u32>) -> u32 {
+        let id = self.nodes.len() as u32;
+        let parent = parent.filter(|parent| self.node(*parent).is_some());
+        match parent {
+            None => self.roots.push(id),
+            Some(parent) => self.nodes[parent as usize].children.push(id),
+        }
+
+        self.nodes.push(Node {
+            parent,
+            children: Vec::new(),
+            expanded: false,
+        });
+        self.stale.set(true);
+        id
+    }
+
+    /// Ignores a node that was never added, rather than trapping on an id
+    /// from JS.
+    pub fn set_expanded(&mut self, node: u32, expanded: bool) {
+        let node = match self.nodes.get_mut(node as usize) {
+            None => return,
+            Some(node) => node,
+        };
+        if node.expanded != expanded {
+            node.expanded = expanded;
+            self.stale.set(true);
+        }
+    }
+
+    /// Flips the expanded state of `node` and returns the new one.
+    pub fn toggle(&mut self, node: u32) -> bool {
+        self.set_expanded(node, !self.is_expanded(node));
+        self.is_expanded(node)
+    }
+
+    /// False for an unknown node.
+    pub fn is_expanded(&self, node: u32) -> bool {
+        self.node(node).is_some_and(|node| node.expanded)
+    }
+
+    pub fn parent_of(&self, node: u32) -> Option<u32> {
+        self.node(node)?.parent
+    }
+
+    pub fn children_of(&self, node: u32) -> Vec<u32> {
+        match self.node(node) {
+            None => Vec::new(),
+            Some(node) => node.children.clone(),
+        }
+    }
+
+    /// The number of ancestors of `node`, zero for the roots and the unknown
+    /// nodes.
+    pub fn depth_of(&self, node: u32) -> u32 {
+        let mut depth = 0;
+        let mut current = self.parent_of(node);
+        while let Some(parent) = current {
+            depth += 1;
+            current = self.nodes[parent as usize].parent;
+        }
+
+        depth
+    }
+
+    /// The number of rows, which is the count of the list rendering them.
+    pub fn row_count(&self) -> u32 {
+        self.flat().len() as u32
+    }
+
+    pub fn node_at(&self, row: u32) -> Option<u32> {
+        self.flat().get(row as usize).copied()
+    }
+
+    /// The row of `node`, none while one of its ancestors is collapsed.
+    pub fn row_of(&self, node: u32) -> Option<u32> {
+        self.flat();
+        match self.rows.borrow().get(node as usize) {
+            None | Some(&HIDDEN) => None,
+            Some(row) => Some(*row),
+        }
+    }
+
+    /// The nodes of the rows `start..=end`.
+    pub fn nodes_in(&self, start: u32, end: u32) -> Vec<u32> {
+        let flat = self.flat();
+        let end = (end as usize).saturating_add(1).min(flat.len());
+        match flat.get(start as usize..end) {
+            None => Vec::new(),
+            Some(nodes) => nodes.to_vec(),
+        }
+    }
+
+    /// The position of the node at `row` among its siblings, at every level
+    /// from its root down.
+    pub fn path_at(&self, row: u32) -> Vec<u32> {
+        let mut path = Vec::new();
+        let mut current = self.node_at(row);
+        while let Some(node) = current {
+            let parent = self.nodes[node as usize].parent;
+            let siblings = match parent {
+                None => &self.roots,
+                Some(parent) => &self.nodes[parent as usize].children,
+            };
+            path.push(
+                siblings
+                    .iter()
+                    .position(|sibling| *sibling == node)
+                    .unwrap_or(0) as u32,
+            );
+            current = parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+impl TreeView {
+    fn node(&self, node: u32) -> Option<&Node> {
+        self.nodes.get(node as usize)
+    }
+
+    fn flat(&self) -> Ref<'_, Vec<u32>> {
+        if self.stale.replace(false) {
+            self.flatten();
+        }
+        self.flat.borrow()
+    }
+
+    fn flatten(&self) {
+        let mut flat = self.flat.borrow_mut();
+        let mut rows = self.rows.borrow_mut();
+        flat.clear();
+        rows.clear();
+        rows.resize(self.nodes.len(), HIDDEN);
+
+        let mut pending: Vec<u32> = self.roots.iter().rev().copied().collect();
+        while let Some(node) = pending.pop() {
+            rows[node as usize] = flat.len() as u32;
+            flat.push(node);
+
+            let node = &self.nodes[node as usize];
+            if node.expanded {
+                pending.extend(node.children.iter().rev());
+            }
+        }
+    }
+}
+
+impl Default for TreeView {
+    fn default() -> Self {
+        TreeView::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeView;
+
+    // 0
+    //   1
+    //     3
+    //   2
+    // 4
+    fn tree() -> TreeView {
+        let mut tree = TreeView::new();
+        let first = tree.add_node(None);
+        let child = tree.add_node(Some(first));
+        tree.add_node(Some(first));
+        tree.add_node(Some(child));
+        tree.add_node(None);
+        tree
+    }
+
+    #[test]
+    fn test_flattening() {
+        let mut tree = tree();
+        assert_eq!(tree.nodes_in(0, 10), [0, 4]);
+
+        tree.set_expanded(0, true);
+        assert_eq!(tree.nodes_in(0, 10), [0, 1, 2, 4]);
+        assert_eq!(tree.row_of(3), None);
+
+        assert!(tree.toggle(1));
+        assert_eq!(tree.nodes_in(0, 10), [0, 1, 3, 2, 4]);
+        assert_eq!(tree.row_count(), 5);
+        assert_eq!(tree.row_of(2), Some(3));
+        assert_eq!(tree.depth_of(3), 2);
+
+        tree.set_expanded(0, false);
+        assert_eq!(tree.nodes_in(0, 10), [0, 4]);
+        assert_eq!(tree.row_of(3), None);
+    }
+
+    #[test]
+    fn test_unknown_nodes() {
+        let mut tree = tree();
+        tree.set_expanded(99, true);
+        assert!(!tree.toggle(99));
+        assert!(!tree.is_expanded(99));
+        assert_eq!(tree.parent_of(99), None);
+        assert!(tree.children_of(99).is_empty());
+        assert_eq!(tree.depth_of(99), 0);
+        assert_eq!(tree.row_of(99), None);
+
+        let root = tree.add_node(Some(99));
+        assert_eq!(tree.parent_of(root), None);
+        assert_eq!(tree.nodes_in(0, 10), [0, 4, root]);
+    }
+
+    #[test]
+    fn test_paths() {
+        let mut tree = tree();
+        tree.set_expanded(0, true);
+        tree.set_expanded(1, true);
+
+        assert_eq!(tree.path_at(2), [0, 0, 0]);
+        assert_eq!(tree.path_at(3), [0, 1]);
+        assert_eq!(tree.path_at(4), [1]);
+        assert!(tree.path_at(5).is_empty());
+    }
+}
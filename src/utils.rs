@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -8,3 +9,61 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// FNV-1a over the bit patterns of the given settings. Results carry this hash
+/// so that async consumers can tell which configuration produced them.
+pub fn config_hash(settings: &[f64]) -> u32 {
+    settings
+        .iter()
+        .flat_map(|setting| setting.to_bits().to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// How far an offset moved from `before` to `after`, clamped to the `i32`
+/// range.
+pub fn delta(before: u32, after: u32) -> i32 {
+    (i64::from(after) - i64::from(before)).clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+/// Rounds `size` to the nearest multiple of `quantum`.
+pub fn quantize(size: u32, quantum: u32) -> u32 {
+    if quantum <= 1 {
+        return size;
+    }
+
+    (size.saturating_add(quantum / 2) / quantum).saturating_mul(quantum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::config_hash;
+    use super::delta;
+    use super::quantize;
+
+    #[test]
+    fn test_delta() {
+        assert_eq!(delta(10, 4), -6);
+        assert_eq!(delta(0, u32::MAX), i32::MAX);
+        assert_eq!(delta(u32::MAX, 0), i32::MIN);
+    }
+
+    #[test]
+    fn test_quantize() {
+        assert_eq!(quantize(21, 4), 20);
+        assert_eq!(quantize(22, 4), 24);
+        assert_eq!(quantize(0, 4), 0);
+        assert_eq!(quantize(7, 1), 7);
+    }
+
+    #[test]
+    fn test_config_hash() {
+        assert_eq!(config_hash(&[1.0, 2.0]), config_hash(&[1.0, 2.0]));
+        assert_ne!(config_hash(&[1.0, 2.0]), config_hash(&[2.0, 1.0]));
+        assert_ne!(config_hash(&[0.0]), config_hash(&[]));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::axis::Axis;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Tracks whether the viewport touches the top or the bottom edge of the list
+/// (the left and right edge for horizontal lists).
+///
+/// An edge counts as reached once the viewport is within `tolerance` pixels of
+/// it, and is only left again after moving more than `tolerance + hysteresis`
+/// away, so small momentum-scrolling bounces don't toggle the flags.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ViewportState {
+    tolerance: f64,
+    hysteresis: f64,
+    at_top: bool,
+    at_bottom: bool,
+}
+
+fn edge_reached(was_reached: bool, distance: f64, tolerance: f64, hysteresis: f64) -> bool {
+    if was_reached {
+        distance <= tolerance + hysteresis
+    } else {
+        distance <= tolerance
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ViewportState {
+    pub fn new(tolerance: f64, hysteresis: f64) -> ViewportState {
+        // an empty list shows both of its edges
+        ViewportState {
+            tolerance,
+            hysteresis,
+            at_top: true,
+            at_bottom: true,
+        }
+    }
+
+    pub fn update(&mut self, scroll_offset: f64, viewport_size: f64, total_size: f64) {
+        let top_distance = scroll_offset;
+        let bottom_distance = total_size - (scroll_offset + viewport_size);
+
+        self.at_top = edge_reached(self.at_top, top_distance, self.tolerance, self.hysteresis);
+        self.at_bottom = edge_reached(
+            self.at_bottom,
+            bottom_distance,
+            self.tolerance,
+            self.hysteresis,
+        );
+    }
+
+    /// `update` with the raw scroll position and client size of the scroller.
+    pub fn update_element(
+        &mut self,
+        axis: Axis,
+        scroll_left: f64,
+        scroll_top: f64,
+        client_width: f64,
+        client_height: f64,
+        total_size: f64,
+    ) {
+        self.update(
+            axis.main(scroll_left, scroll_top),
+            axis.main(client_width, client_height),
+            total_size,
+        );
+    }
+
+    pub fn at_top(&self) -> bool {
+        self.at_top
+    }
+
+    pub fn at_bottom(&self) -> bool {
+        self.at_bottom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ViewportState;
+    use crate::axis::Axis;
+
+    #[test]
+    fn test_edges() {
+        let mut state = ViewportState::new(4.0, 0.0);
+
+        state.update(0.0, 300.0, 1000.0);
+        assert!(state.at_top());
+        assert!(!state.at_bottom());
+
+        state.update(698.0, 300.0, 1000.0);
+        assert!(!state.at_top());
+        assert!(state.at_bottom());
+    }
+
+    #[test]
+    fn test_short_list_touches_both_edges() {
+        let mut state = ViewportState::new(0.0, 0.0);
+        state.update(0.0, 300.0, 100.0);
+
+        assert!(state.at_top());
+        assert!(state.at_bottom());
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut state = ViewportState::new(4.0, 10.0);
+        state.update(700.0, 300.0, 1000.0);
+        assert!(state.at_bottom());
+
+        // bouncing within tolerance + hysteresis keeps the flag
+        state.update(688.0, 300.0, 1000.0);
+        assert!(state.at_bottom());
+
+        state.update(680.0, 300.0, 1000.0);
+        assert!(!state.at_bottom());
+
+        // coming back requires reaching the plain tolerance again
+        state.update(690.0, 300.0, 1000.0);
+        assert!(!state.at_bottom());
+
+        state.update(697.0, 300.0, 1000.0);
+        assert!(state.at_bottom());
+    }
+
+    #[test]
+    fn test_horizontal_element() {
+        let mut state = ViewportState::new(0.0, 0.0);
+        state.update_element(Axis::Horizontal, 700.0, 0.0, 300.0, 50.0, 1000.0);
+
+        assert!(!state.at_top());
+        assert!(state.at_bottom());
+    }
+}